@@ -0,0 +1,27 @@
+//! Feed arbitrary cache file contents to the `Tote` read paths,
+//! which must return errors rather than panic
+//!
+//! Run with `cargo +nightly fuzz run read`
+#![no_main]
+
+use std::time::Duration;
+
+use libfuzzer_sys::fuzz_target;
+use serde_json::Value;
+use tote::Tote;
+
+static DEFAULT: Value = Value::Null;
+
+fuzz_target!(|data: &[u8]| {
+    let path = std::env::temp_dir().join(format!("tote-fuzz-{}.cache", std::process::id()));
+    if std::fs::write(&path, data).is_err() {
+        return;
+    }
+    let cache: Tote<Value> = Tote::new(&path, Duration::from_secs(60));
+
+    let _ = cache.get_or_static(&DEFAULT);
+    let _ = cache.clone().with_max_depth(16).get_or_static(&DEFAULT);
+    if let Ok(items) = cache.stream_items::<Value>() {
+        items.for_each(drop);
+    }
+});
@@ -0,0 +1,212 @@
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use serde::de::DeserializeOwned;
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+use crate::{Backend, JsonCodec, Tote};
+
+/// `Backend` storing cache contents as entries inside a zip archive,
+/// see `Tote::in_zip`
+///
+/// Cache paths are used as entry names. Each write rewrites the whole
+/// archive (via a temp file renamed over it), copying the other entries
+/// without recompressing them
+#[derive(Debug)]
+pub struct ZipBackend {
+    archive: PathBuf,
+    /// Serializes rewrites of the archive
+    lock: Mutex<()>,
+}
+
+impl ZipBackend {
+    /// Store cache contents in the zip archive at `archive`, created on
+    /// the first write if missing
+    pub fn new<P: AsRef<Path>>(archive: P) -> Self {
+        Self {
+            archive: archive.as_ref().to_owned(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn open(&self) -> io::Result<ZipArchive<fs::File>> {
+        Ok(ZipArchive::new(fs::File::open(&self.archive)?)?)
+    }
+
+    /// Rewrite the archive without `name`, adding `data` as `name` if given
+    fn rewrite(&self, name: &str, data: Option<&[u8]>) -> io::Result<bool> {
+        let _lock = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut existing = match self.open() {
+            Ok(archive) => Some(archive),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e),
+        };
+
+        let mut tmp = self.archive.clone().into_os_string();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+        let mut writer = ZipWriter::new(fs::File::create(&tmp)?);
+        let mut found = false;
+        if let Some(archive) = existing.as_mut() {
+            for i in 0..archive.len() {
+                let entry = archive.by_index_raw(i)?;
+                if entry.name() == name {
+                    found = true;
+                    continue;
+                }
+                writer.raw_copy_file(entry)?;
+            }
+        }
+        if let Some(data) = data {
+            writer.start_file(name, SimpleFileOptions::default())?;
+            writer.write_all(data)?;
+        }
+        writer.finish()?.sync_all()?;
+        fs::rename(&tmp, &self.archive)?;
+        Ok(found)
+    }
+}
+
+/// Archive entry name for a cache path
+fn entry_name(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+impl Backend for ZipBackend {
+    fn read_bytes(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let mut archive = self.open()?;
+        let mut entry = archive.by_name(&entry_name(path))?;
+        let mut data = Vec::with_capacity(entry.size() as usize);
+        entry.read_to_end(&mut data)?;
+        Ok(data)
+    }
+
+    fn write_bytes(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        self.rewrite(&entry_name(path), Some(data)).map(|_| ())
+    }
+
+    /// The archive's modified time, as entries' times are unreliable
+    fn modified(&self, path: &Path) -> io::Result<SystemTime> {
+        self.open()?.by_name(&entry_name(path))?;
+        fs::metadata(&self.archive)?.modified()
+    }
+
+    fn remove(&self, path: &Path) -> io::Result<()> {
+        if self.rewrite(&entry_name(path), None)? {
+            Ok(())
+        } else {
+            Err(io::ErrorKind::NotFound.into())
+        }
+    }
+}
+
+impl<T: DeserializeOwned> Tote<T, JsonCodec> {
+    /// Create a cache stored as the `entry_name` entry inside the zip
+    /// archive at `archive`, read on `get` & rewritten on `put`
+    ///
+    /// Expiry uses the fetch time embedded in the entry (see
+    /// `with_embedded_timestamp`), as archive entries' modified times are
+    /// unreliable once archives are copied or re-packed
+    pub fn in_zip<P: AsRef<Path>>(archive: P, entry_name: &str, max_age: Duration) -> Self {
+        let mut tote = Tote::new(entry_name, max_age)
+            .with_backend(ZipBackend::new(archive))
+            .with_embedded_timestamp(true);
+        tote.check_by_value();
+        tote
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::Fetch;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Data(u8);
+
+    impl Fetch for Data {
+        type Cached = Data;
+
+        fn fetch() -> Result<Data, Box<dyn std::error::Error>> {
+            Ok(Data(1))
+        }
+    }
+
+    fn seed(archive: &Path, entries: &[(&str, &str)]) {
+        let mut writer = ZipWriter::new(fs::File::create(archive).unwrap());
+        for (name, contents) in entries {
+            writer
+                .start_file(*name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(contents.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap();
+    }
+
+    #[test]
+    fn test_in_zip() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("caches.zip");
+        let fetched_at = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        seed(
+            &archive,
+            &[
+                ("readme.txt", "warmed caches"),
+                (
+                    "data.json",
+                    &format!(r#"{{"fetched_at":{},"data":7}}"#, fetched_at),
+                ),
+            ],
+        );
+
+        // Seeded entry is read without fetching
+        let cache: Tote<Data> = Tote::in_zip(&archive, "data.json", Duration::from_secs(60));
+        assert!(cache.is_valid());
+        assert_eq!(cache.get().unwrap(), Data(7));
+
+        // A new entry is written, keeping the others
+        let other: Tote<Data> = Tote::in_zip(&archive, "other.json", Duration::from_secs(60));
+        assert!(!other.is_valid());
+        assert_eq!(other.get().unwrap(), Data(1));
+        other.put(&Data(2)).unwrap();
+        assert_eq!(other.get().unwrap(), Data(2));
+        assert_eq!(cache.get().unwrap(), Data(7));
+        let mut entries = ZipArchive::new(fs::File::open(&archive).unwrap()).unwrap();
+        let mut readme = String::new();
+        entries
+            .by_name("readme.txt")
+            .unwrap()
+            .read_to_string(&mut readme)
+            .unwrap();
+        assert_eq!(readme, "warmed caches");
+
+        cache.invalidate().unwrap();
+        assert!(!cache.is_valid());
+        assert_eq!(other.get().unwrap(), Data(2));
+    }
+
+    #[test]
+    fn test_in_zip_expiry() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("caches.zip");
+        // Expired by the embedded timestamp, though the archive was just written
+        seed(
+            &archive,
+            &[("data.json", r#"{"fetched_at":1000,"data":7}"#)],
+        );
+
+        let cache: Tote<Data> = Tote::in_zip(&archive, "data.json", Duration::from_secs(60));
+        assert!(!cache.is_valid());
+        assert_eq!(cache.get().unwrap(), Data(1));
+        assert!(cache.is_valid());
+    }
+}
@@ -0,0 +1,332 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use crate::{Clock, Codec, Tote};
+
+/// Storage for cache contents, replacing the filesystem, see `Tote::with_backend`
+///
+/// Paths are used as keys, so a backend doesn't need to store them as files
+/// (e.g. `localStorage` in a WASM build). Missing entries return an
+/// `io::ErrorKind::NotFound` error
+pub trait Backend: Send + Sync + 'static {
+    /// Read the contents stored at `path`
+    fn read_bytes(&self, path: &Path) -> io::Result<Vec<u8>>;
+
+    /// Store `data` at `path`, replacing any existing contents
+    fn write_bytes(&self, path: &Path, data: &[u8]) -> io::Result<()>;
+
+    /// When the contents at `path` were last written
+    fn modified(&self, path: &Path) -> io::Result<SystemTime>;
+
+    /// Delete the contents stored at `path`
+    fn remove(&self, path: &Path) -> io::Result<()>;
+}
+
+/// `Backend` reading & writing files with `std::fs`
+///
+/// A `Tote` without a backend uses its own filesystem storage, which
+/// supports more options (e.g. atomic writes & memory mapping)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FsBackend;
+
+impl Backend for FsBackend {
+    fn read_bytes(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(path)
+    }
+
+    fn write_bytes(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        fs::write(path, data)
+    }
+
+    fn modified(&self, path: &Path) -> io::Result<SystemTime> {
+        fs::metadata(path)?.modified()
+    }
+
+    fn remove(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+}
+
+/// In-memory `Backend`, for tests or targets without a filesystem
+///
+/// Contents are lost when the backend is dropped
+#[derive(Default)]
+pub struct MemoryBackend {
+    entries: Mutex<HashMap<PathBuf, (Vec<u8>, SystemTime)>>,
+    clock: Option<Arc<dyn Clock + Send + Sync>>,
+}
+
+impl std::fmt::Debug for MemoryBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MemoryBackend")
+            .field("entries", &self.entries)
+            .field("clock", &self.clock.is_some())
+            .finish()
+    }
+}
+
+impl MemoryBackend {
+    /// Create an empty backend
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use the given `Clock` as the modified time of writes, rather than
+    /// the system clock
+    ///
+    /// Pass the same clock to `Tote::with_clock` so expiry can be tested
+    /// without sleeping
+    pub fn with_clock<K>(mut self, clock: K) -> Self
+    where
+        K: Clock + Send + Sync + 'static,
+    {
+        self.clock = Some(Arc::new(clock));
+        self
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, HashMap<PathBuf, (Vec<u8>, SystemTime)>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn not_found() -> io::Error {
+    io::ErrorKind::NotFound.into()
+}
+
+impl Backend for MemoryBackend {
+    fn read_bytes(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let entries = self.entries();
+        entries
+            .get(path)
+            .map(|(data, _)| data.clone())
+            .ok_or_else(not_found)
+    }
+
+    fn write_bytes(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        let now = match &self.clock {
+            Some(clock) => clock.now(),
+            None => SystemTime::now(),
+        };
+        self.entries().insert(path.to_owned(), (data.to_vec(), now));
+        Ok(())
+    }
+
+    fn modified(&self, path: &Path) -> io::Result<SystemTime> {
+        let entries = self.entries();
+        entries
+            .get(path)
+            .map(|(_, modified)| *modified)
+            .ok_or_else(not_found)
+    }
+
+    fn remove(&self, path: &Path) -> io::Result<()> {
+        self.entries()
+            .remove(path)
+            .map(|_| ())
+            .ok_or_else(not_found)
+    }
+}
+
+impl<T, C: Codec> Tote<T, C> {
+    /// Store the cache contents in `backend` instead of the filesystem,
+    /// e.g. a `MemoryBackend` or browser storage in a WASM build
+    ///
+    /// `get`, `put`, `is_valid`, `invalidate` & `migrate_from` use the
+    /// backend. Reads & writes with filesystem specific options (expiry
+    /// policies, generation files, mirrors, candidates, lock files, adaptive
+    /// TTLs, sliding expiration, quarantining, temp file & open options,
+    /// hash sidecars & memory mapping) return `ToteError::InvalidOptions`
+    /// (and `is_valid` is false), as does `stream_items`, `update`,
+    /// `get_and_merge` & `get_conditional`
+    pub fn with_backend<B: Backend>(mut self, backend: B) -> Self {
+        self.backend = Some(std::sync::Arc::new(backend));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::{Fetch, ToteError};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Data(u8);
+
+    impl Fetch for Data {
+        type Cached = Data;
+
+        fn fetch() -> Result<Data, Box<dyn std::error::Error>> {
+            Ok(Data(1))
+        }
+    }
+
+    #[test]
+    fn test_memory_backend() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache.json");
+        let cache: Tote<Data> =
+            Tote::new(&path, Duration::from_secs(60)).with_backend(MemoryBackend::new());
+        assert!(!cache.is_valid());
+        assert_eq!(cache.get().unwrap(), Data(1));
+        assert!(cache.is_valid());
+        assert!(cache.exists());
+        // Nothing is written to the filesystem
+        assert!(!path.exists());
+
+        cache.put(&Data(2)).unwrap();
+        assert_eq!(cache.get().unwrap(), Data(2));
+
+        cache.invalidate().unwrap();
+        assert!(!cache.is_valid());
+        cache.invalidate().unwrap();
+        assert_eq!(cache.get().unwrap(), Data(1));
+
+        // Expired
+        let cache: Tote<Data> = Tote::new(&path, Duration::ZERO).with_backend(MemoryBackend::new());
+        cache.put(&Data(2)).unwrap();
+        assert!(!cache.is_valid());
+        assert_eq!(cache.get().unwrap(), Data(1));
+    }
+
+    #[test]
+    fn test_backend_unsupported_options() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache.json");
+        let cache: Tote<Data> = Tote::new(&path, Duration::from_secs(60))
+            .with_backend(MemoryBackend::new())
+            .with_expiry_policy(Duration::from_secs(60));
+        assert!(matches!(cache.get(), Err(ToteError::InvalidOptions(_))));
+        assert!(matches!(
+            cache.put(&Data(2)),
+            Err(ToteError::InvalidOptions(_))
+        ));
+        assert!(!cache.is_valid());
+
+        let cache: Tote<Data> = Tote::new(&path, Duration::from_secs(60))
+            .with_backend(MemoryBackend::new())
+            .with_generation_file(dir.path().join("generation"));
+        assert!(matches!(cache.get(), Err(ToteError::InvalidOptions(_))));
+        assert!(!cache.is_valid());
+
+        let cache: Tote<Data> = Tote::new(&path, Duration::from_secs(60))
+            .with_backend(MemoryBackend::new())
+            .with_mirror(dir.path().join("mirror"));
+        assert!(matches!(cache.get(), Err(ToteError::InvalidOptions(_))));
+        assert!(!dir.path().join("mirror").exists());
+
+        let cache: Tote<Data> =
+            Tote::new(&path, Duration::from_secs(60)).with_backend(MemoryBackend::new());
+        assert!(matches!(
+            cache.update(|data| data.0 += 1),
+            Err(ToteError::InvalidOptions(_))
+        ));
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    /// Fails the first `failures` reads with a transient (non-`NotFound`) error
+    #[derive(Default)]
+    struct FlakyBackend {
+        inner: MemoryBackend,
+        failures: Mutex<u32>,
+    }
+
+    impl Backend for FlakyBackend {
+        fn read_bytes(&self, path: &Path) -> io::Result<Vec<u8>> {
+            let mut failures = self.failures.lock().unwrap();
+            if *failures > 0 {
+                *failures -= 1;
+                return Err(io::Error::other("stale handle"));
+            }
+            self.inner.read_bytes(path)
+        }
+
+        fn write_bytes(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+            self.inner.write_bytes(path, data)
+        }
+
+        fn modified(&self, path: &Path) -> io::Result<SystemTime> {
+            self.inner.modified(path)
+        }
+
+        fn remove(&self, path: &Path) -> io::Result<()> {
+            self.inner.remove(path)
+        }
+    }
+
+    #[test]
+    fn test_backend_read_retry() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static FETCHES: AtomicUsize = AtomicUsize::new(0);
+
+        let backend = FlakyBackend::default();
+        backend
+            .inner
+            .write_bytes(Path::new("cache.json"), b"2")
+            .unwrap();
+        *backend.failures.lock().unwrap() = 1;
+        let cache: Tote<Data> = Tote::new("cache.json", Duration::from_secs(60))
+            .with_backend(backend)
+            .with_read_retry(2)
+            .on_fetch_start(|| {
+                FETCHES.fetch_add(1, Ordering::SeqCst);
+            });
+
+        // The failed read is retried rather than re-fetching
+        assert_eq!(cache.get().unwrap(), Data(2));
+        assert_eq!(FETCHES.load(Ordering::SeqCst), 0);
+    }
+
+    #[cfg(feature = "sha256")]
+    #[test]
+    fn test_backend_hash_sidecar() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache.json");
+        let cache: Tote<Data> = Tote::new(&path, Duration::from_secs(60))
+            .with_backend(MemoryBackend::new())
+            .with_hash_sidecar(true);
+        assert!(matches!(cache.get(), Err(ToteError::InvalidOptions(_))));
+        assert!(matches!(
+            cache.put(&Data(2)),
+            Err(ToteError::InvalidOptions(_))
+        ));
+        assert!(!cache.is_valid());
+        assert!(!path.with_extension("json.sha256").exists());
+    }
+
+    #[test]
+    fn test_backend_migrate_from() {
+        let cache: Tote<Data> =
+            Tote::new("cache.json", Duration::from_secs(60)).with_backend(MemoryBackend::new());
+        // Clones share the backend
+        let old = cache.clone_with_path("old.json");
+        old.put(&Data(2)).unwrap();
+
+        assert!(cache
+            .migrate_from("old.json", |old: Data| Data(old.0 + 1))
+            .unwrap());
+        assert_eq!(cache.get().unwrap(), Data(3));
+        assert!(!old.exists());
+        assert!(!Path::new("old.json").exists());
+    }
+
+    #[test]
+    fn test_fs_backend() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache.json");
+        let cache: Tote<Data> = Tote::new(&path, Duration::from_secs(60)).with_backend(FsBackend);
+        cache.put(&Data(2)).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "2");
+        assert_eq!(cache.get().unwrap(), Data(2));
+        cache.invalidate().unwrap();
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,402 @@
+use std::fmt;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+
+use crate::hooks::Hooks;
+use crate::{Codec, ExtensionCodec, JsonCodec, Tote, ToteError, TtlFn};
+
+/// Builder for a `Tote`, returned from `Tote::builder`
+///
+/// `path` and `max_age` are required, other options default to those
+/// of `Tote::new`
+pub struct ToteBuilder<T, C = JsonCodec> {
+    path: Option<PathBuf>,
+    max_age: Option<Duration>,
+    create_dirs: bool,
+    fetch_retry: (u32, Duration),
+    grace: Duration,
+    pretty: bool,
+    strict_serialization: bool,
+    tempfile_affixes: Option<(String, String)>,
+    hooks: Hooks<T>,
+    ttl_from: Option<TtlFn<T>>,
+    #[cfg(any(feature = "async", feature = "async-native"))]
+    fetch_timeout: Option<(Duration, bool)>,
+    #[cfg(feature = "encrypt")]
+    encryption_key: Option<[u8; 32]>,
+    codec: C,
+    /// Picks the codec from `path` on `build`, overriding `codec`
+    resolve_codec: Option<fn(&Path) -> C>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T, C> fmt::Debug for ToteBuilder<T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ToteBuilder")
+            .field("path", &self.path)
+            .field("max_age", &self.max_age)
+            .field("create_dirs", &self.create_dirs)
+            .field("fetch_retry", &self.fetch_retry)
+            .field("grace", &self.grace)
+            .field("pretty", &self.pretty)
+            .field("strict_serialization", &self.strict_serialization)
+            .field("tempfile_affixes", &self.tempfile_affixes)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> Tote<T> {
+    /// Configure a new cache with a `ToteBuilder`
+    pub fn builder() -> ToteBuilder<T> {
+        ToteBuilder {
+            path: None,
+            max_age: None,
+            create_dirs: true,
+            fetch_retry: (1, Duration::ZERO),
+            grace: Duration::ZERO,
+            pretty: false,
+            strict_serialization: false,
+            tempfile_affixes: None,
+            hooks: Hooks::default(),
+            ttl_from: None,
+            #[cfg(any(feature = "async", feature = "async-native"))]
+            fetch_timeout: None,
+            #[cfg(feature = "encrypt")]
+            encryption_key: None,
+            codec: JsonCodec,
+            resolve_codec: None,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T> ToteBuilder<T> {
+    /// See `Tote::pretty`
+    pub fn pretty(mut self, enabled: bool) -> Self {
+        self.pretty = enabled;
+        self
+    }
+}
+
+impl<T, C: Codec> ToteBuilder<T, C> {
+    /// Filepath to write cached data
+    pub fn path<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.path = Some(path.as_ref().to_owned());
+        self
+    }
+
+    /// Cached data older than this age is considered expired
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// (De)serialize the cached data with the given `Codec`
+    pub fn codec<D: Codec>(self, codec: D) -> ToteBuilder<T, D> {
+        ToteBuilder {
+            path: self.path,
+            max_age: self.max_age,
+            create_dirs: self.create_dirs,
+            fetch_retry: self.fetch_retry,
+            grace: self.grace,
+            // Pretty-printing only applies to `JsonCodec`
+            pretty: false,
+            strict_serialization: self.strict_serialization,
+            tempfile_affixes: self.tempfile_affixes,
+            hooks: self.hooks,
+            ttl_from: self.ttl_from,
+            #[cfg(any(feature = "async", feature = "async-native"))]
+            fetch_timeout: self.fetch_timeout,
+            #[cfg(feature = "encrypt")]
+            encryption_key: self.encryption_key,
+            codec,
+            resolve_codec: None,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// (De)serialize the cached data with the `Codec` matching the
+    /// extension of `path`, see `ExtensionCodec::from_extension`
+    pub fn codec_from_extension(self) -> ToteBuilder<T, ExtensionCodec> {
+        let mut builder = self.codec(ExtensionCodec::Json);
+        builder.resolve_codec = Some(|path| ExtensionCodec::from_extension(path));
+        builder
+    }
+
+    /// See `Tote::create_dirs`
+    pub fn create_dirs(mut self, enabled: bool) -> Self {
+        self.create_dirs = enabled;
+        self
+    }
+
+    /// See `Tote::with_fetch_retry`
+    pub fn fetch_retry(mut self, attempts: u32, base_delay: Duration) -> Self {
+        self.fetch_retry = (attempts, base_delay);
+        self
+    }
+
+    #[cfg(any(feature = "async", feature = "async-native"))]
+    /// See `Tote::with_fetch_timeout`
+    pub fn fetch_timeout(mut self, timeout: Duration, stale_fallback: bool) -> Self {
+        self.fetch_timeout = Some((timeout, stale_fallback));
+        self
+    }
+
+    /// See `Tote::with_grace`
+    pub fn grace(mut self, grace: Duration) -> Self {
+        self.grace = grace;
+        self
+    }
+
+    /// See `Tote::strict_serialization`
+    pub fn strict_serialization(mut self, enabled: bool) -> Self {
+        self.strict_serialization = enabled;
+        self
+    }
+
+    /// See `Tote::with_tempfile_affixes`
+    pub fn tempfile_affixes(mut self, prefix: &str, suffix: &str) -> Self {
+        self.tempfile_affixes = Some((prefix.to_owned(), suffix.to_owned()));
+        self
+    }
+
+    /// See `Tote::with_ttl_from`
+    pub fn ttl_from<F>(mut self, ttl_from: F) -> Self
+    where
+        F: Fn(&T) -> Duration + Send + Sync + 'static,
+    {
+        self.ttl_from = Some(Arc::new(ttl_from));
+        self
+    }
+
+    /// See `Tote::on_hit`
+    pub fn on_hit<F>(mut self, f: F) -> Self
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.hooks.set_hit(f);
+        self
+    }
+
+    /// See `Tote::on_miss`
+    pub fn on_miss<F>(mut self, f: F) -> Self
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.hooks.set_miss(f);
+        self
+    }
+
+    /// See `Tote::on_fetch_start`
+    pub fn on_fetch_start<F>(mut self, f: F) -> Self
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.hooks.set_fetch_start(f);
+        self
+    }
+
+    /// See `Tote::on_fetch`
+    pub fn on_fetch<F>(mut self, f: F) -> Self
+    where
+        F: Fn(Result<&T, &ToteError>) + Send + Sync + 'static,
+    {
+        self.hooks.set_fetch(f);
+        self
+    }
+
+    #[cfg(feature = "encrypt")]
+    /// Encrypt the cache file with a `ChaChaCipher` using `key`,
+    /// see `Tote::with_cipher`
+    pub fn encryption_key(mut self, key: [u8; 32]) -> Self {
+        self.encryption_key = Some(key);
+        self
+    }
+
+    /// Build the `Tote`, returning `ToteError::InvalidOptions` if
+    /// `path` or `max_age` weren't set
+    pub fn build(self) -> Result<Tote<T, C>, ToteError>
+    where
+        T: DeserializeOwned,
+    {
+        let path = self
+            .path
+            .ok_or(ToteError::InvalidOptions("`path` is required"))?;
+        let max_age = self
+            .max_age
+            .ok_or(ToteError::InvalidOptions("`max_age` is required"))?;
+        let (attempts, base_delay) = self.fetch_retry;
+        let codec = match self.resolve_codec {
+            Some(resolve) => resolve(&path),
+            None => self.codec,
+        };
+        let mut tote = Tote::new_with_codec(path, max_age, codec)
+            .create_dirs(self.create_dirs)
+            .with_fetch_retry(attempts, base_delay)
+            .with_grace(self.grace)
+            .strict_serialization(self.strict_serialization);
+        tote.pretty = self.pretty;
+        tote.hooks = self.hooks;
+        tote.tempfile_affixes = self.tempfile_affixes;
+        if let Some(ttl_from) = self.ttl_from {
+            tote.set_ttl_from(ttl_from);
+        }
+        #[cfg(any(feature = "async", feature = "async-native"))]
+        if let Some((timeout, stale_fallback)) = self.fetch_timeout {
+            tote = tote.with_fetch_timeout(timeout, stale_fallback);
+        }
+        #[cfg(feature = "encrypt")]
+        let tote = match self.encryption_key {
+            Some(key) => tote.with_cipher(crate::ChaChaCipher::new(key)),
+            None => tote,
+        };
+        Ok(tote)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("cache.json");
+        let cache: Tote<Vec<u8>> = Tote::builder()
+            .path(&path)
+            .max_age(Duration::from_secs(60))
+            .create_dirs(false)
+            .build()
+            .unwrap();
+        assert!(cache.put(&vec![1]).is_err());
+
+        let cache: Tote<Vec<u8>> = Tote::builder()
+            .path(&path)
+            .max_age(Duration::from_secs(60))
+            .build()
+            .unwrap();
+        cache.put(&vec![1]).unwrap();
+        assert_eq!(cache.read().unwrap(), vec![1]);
+
+        let cache: Tote<Vec<u8>> = Tote::builder()
+            .path(&path)
+            .max_age(Duration::from_secs(60))
+            .fetch_retry(3, Duration::from_millis(10))
+            .grace(Duration::from_secs(5))
+            .tempfile_affixes(".", ".partial")
+            .build()
+            .unwrap();
+        assert_eq!(cache.grace, Duration::from_secs(5));
+        assert_eq!(cache.fetch_attempts, 3);
+        assert_eq!(cache.fetch_backoff, Duration::from_millis(10));
+        assert_eq!(
+            cache.tempfile_affixes,
+            Some((".".to_owned(), ".partial".to_owned()))
+        );
+
+        let cache: Tote<Vec<u8>> = Tote::builder()
+            .path(&path)
+            .max_age(Duration::from_secs(60))
+            .pretty(true)
+            .build()
+            .unwrap();
+        cache.put(&vec![1]).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "[\n  1\n]");
+
+        // Expiry from the data itself
+        let cache: Tote<Vec<u8>> = Tote::builder()
+            .path(&path)
+            .max_age(Duration::from_secs(60))
+            .ttl_from(|data: &Vec<u8>| Duration::from_secs(data.len() as u64))
+            .build()
+            .unwrap();
+        assert!(cache.is_valid());
+        cache.put(&vec![]).unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(!cache.is_valid());
+
+        // Non-finite floats are written as `null`, which can't be read back
+        let cache: Tote<Vec<f64>> = Tote::builder()
+            .path(&path)
+            .max_age(Duration::from_secs(60))
+            .strict_serialization(true)
+            .build()
+            .unwrap();
+        assert!(matches!(
+            cache.put(&vec![f64::NAN]),
+            Err(ToteError::Serde(_))
+        ));
+        cache.put(&vec![1.5]).unwrap();
+
+        let missing = Tote::<Vec<u8>>::builder().path(&path).build();
+        assert!(matches!(missing, Err(ToteError::InvalidOptions(_))));
+    }
+
+    #[cfg(feature = "encrypt")]
+    #[test]
+    fn test_builder_encryption_key() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let cache: Tote<Vec<u8>> = Tote::builder()
+            .path(file.path())
+            .max_age(Duration::from_secs(60))
+            .encryption_key([3; 32])
+            .build()
+            .unwrap();
+        cache.put(&vec![1, 2]).unwrap();
+        assert_ne!(std::fs::read(file.path()).unwrap(), b"[1,2]");
+        assert_eq!(cache.read().unwrap(), vec![1, 2]);
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn test_builder_codec_from_extension() {
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Host {
+            name: String,
+        }
+
+        fn round_trip(path: &Path) -> Vec<u8> {
+            let cache: Tote<Host, ExtensionCodec> = Tote::builder()
+                .path(path)
+                .max_age(Duration::from_secs(60))
+                .codec_from_extension()
+                .build()
+                .unwrap();
+            let host = Host {
+                name: "router-1".to_owned(),
+            };
+            cache.put(&host).unwrap();
+            assert_eq!(cache.read().unwrap(), host);
+            std::fs::read(path).unwrap()
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let json = round_trip(&dir.path().join("hosts.json"));
+        assert_eq!(json, br#"{"name":"router-1"}"#);
+        let cbor = round_trip(&dir.path().join("hosts.cbor"));
+        assert!(serde_json::from_slice::<serde_json::Value>(&cbor).is_err());
+        assert!(cbor.len() < json.len());
+        // Unknown extensions fall back to JSON
+        assert_eq!(round_trip(&dir.path().join("hosts.cache")), json);
+    }
+
+    #[cfg(feature = "bincode")]
+    #[test]
+    fn test_builder_codec() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let cache: Tote<Vec<u8>, crate::BincodeCodec> = Tote::builder()
+            .path(file.path())
+            .max_age(Duration::from_secs(60))
+            .codec(crate::BincodeCodec)
+            .build()
+            .unwrap();
+        cache.put(&vec![1, 2]).unwrap();
+        assert_eq!(cache.read().unwrap(), vec![1, 2]);
+    }
+}
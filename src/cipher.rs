@@ -0,0 +1,91 @@
+use std::fmt;
+
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+
+use crate::ToteError;
+
+/// Symmetric encryption applied to the serialized cache data before it's
+/// written, and reversed when it's read, see `Tote::with_cipher`
+pub trait Cipher: Send + Sync + 'static {
+    /// Encrypt serialized data to be written to the cache file
+    fn encrypt(&self, data: &[u8]) -> Vec<u8>;
+
+    /// Decrypt data read from the cache file
+    ///
+    /// Any error is treated as an invalid cache file, so the data is re-fetched
+    fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, ToteError>;
+}
+
+/// Length of the random nonce prefixed to each encrypted cache file
+const NONCE_LEN: usize = 12;
+
+/// ChaCha20-Poly1305 `Cipher` (via `chacha20poly1305`) with a 32-byte key
+///
+/// Each write uses a new random nonce, stored at the start of the cache file
+#[derive(Clone)]
+pub struct ChaChaCipher {
+    cipher: ChaCha20Poly1305,
+}
+
+// Manual impl, so the key is never printed
+impl fmt::Debug for ChaChaCipher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChaChaCipher").finish_non_exhaustive()
+    }
+}
+
+impl ChaChaCipher {
+    /// Create a cipher with the given key
+    pub fn new(key: [u8; 32]) -> Self {
+        Self {
+            cipher: ChaCha20Poly1305::new(&key.into()),
+        }
+    }
+}
+
+impl Cipher for ChaChaCipher {
+    fn encrypt(&self, data: &[u8]) -> Vec<u8> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        // Only fails for plaintexts over ~256 GiB
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, data)
+            .expect("cache data too large to encrypt");
+        let mut encrypted = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+        encrypted.extend_from_slice(&nonce);
+        encrypted.extend(ciphertext);
+        encrypted
+    }
+
+    fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, ToteError> {
+        if data.len() < NONCE_LEN {
+            return Err(ToteError::InvalidCache);
+        }
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| ToteError::InvalidCache)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chacha_cipher() {
+        let cipher = ChaChaCipher::new([7; 32]);
+        let encrypted = cipher.encrypt(b"secret");
+        assert!(!encrypted.windows(6).any(|w| w == b"secret"));
+        assert_ne!(cipher.encrypt(b"secret"), encrypted);
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), b"secret");
+
+        let other = ChaChaCipher::new([8; 32]);
+        assert!(matches!(
+            other.decrypt(&encrypted),
+            Err(ToteError::InvalidCache)
+        ));
+        assert!(cipher.decrypt(&encrypted[..4]).is_err());
+    }
+}
@@ -0,0 +1,204 @@
+use std::sync::Arc;
+
+use crate::{Codec, Tote, ToteError};
+
+type HookFn = Arc<dyn Fn() + Send + Sync>;
+type FetchHookFn<T> = Arc<dyn Fn(Result<&T, &ToteError>) + Send + Sync>;
+
+/// Callbacks for cache events, each a no-op when unset
+pub(crate) struct Hooks<T> {
+    hit: Option<HookFn>,
+    miss: Option<HookFn>,
+    fetch_start: Option<HookFn>,
+    fetch: Option<FetchHookFn<T>>,
+}
+
+// Manual impls, deriving would require `T: Default + Clone`
+impl<T> Default for Hooks<T> {
+    fn default() -> Self {
+        Self {
+            hit: None,
+            miss: None,
+            fetch_start: None,
+            fetch: None,
+        }
+    }
+}
+
+impl<T> Clone for Hooks<T> {
+    fn clone(&self) -> Self {
+        Self {
+            hit: self.hit.clone(),
+            miss: self.miss.clone(),
+            fetch_start: self.fetch_start.clone(),
+            fetch: self.fetch.clone(),
+        }
+    }
+}
+
+impl<T> Hooks<T> {
+    pub(crate) fn hit(&self) {
+        if let Some(hit) = &self.hit {
+            hit();
+        }
+    }
+
+    pub(crate) fn miss(&self) {
+        if let Some(miss) = &self.miss {
+            miss();
+        }
+    }
+
+    pub(crate) fn fetch_start(&self) {
+        if let Some(fetch_start) = &self.fetch_start {
+            fetch_start();
+        }
+    }
+
+    pub(crate) fn fetched(&self, res: Result<&T, &ToteError>) {
+        if let Some(fetch) = &self.fetch {
+            fetch(res);
+        }
+    }
+
+    pub(crate) fn set_hit(&mut self, f: impl Fn() + Send + Sync + 'static) {
+        self.hit = Some(Arc::new(f));
+    }
+
+    pub(crate) fn set_miss(&mut self, f: impl Fn() + Send + Sync + 'static) {
+        self.miss = Some(Arc::new(f));
+    }
+
+    pub(crate) fn set_fetch_start(&mut self, f: impl Fn() + Send + Sync + 'static) {
+        self.fetch_start = Some(Arc::new(f));
+    }
+
+    pub(crate) fn set_fetch(&mut self, f: impl Fn(Result<&T, &ToteError>) + Send + Sync + 'static) {
+        self.fetch = Some(Arc::new(f));
+    }
+}
+
+impl<T, C: Codec> Tote<T, C> {
+    /// Call `f` when a fetching read (`get`, `get_async`, `get_with`,
+    /// `get_or_fetch`, `get_opts` etc.) returns valid cached data
+    /// without fetching
+    pub fn on_hit<F>(mut self, f: F) -> Self
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.hooks.set_hit(f);
+        self
+    }
+
+    /// Call `f` when a fetching read finds the cache file missing, expired
+    /// or unreadable, so falls back to fetching (or, for offline
+    /// `GetOptions`, returns the error)
+    pub fn on_miss<F>(mut self, f: F) -> Self
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.hooks.set_miss(f);
+        self
+    }
+
+    /// Call `f` as a read starts fetching data with `Fetch` (or another
+    /// fetch trait) or a `get_or_fetch` closure
+    pub fn on_fetch_start<F>(mut self, f: F) -> Self
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.hooks.set_fetch_start(f);
+        self
+    }
+
+    /// Call `f` with the result of each fetch started as reported to
+    /// `on_fetch_start`, before the fetched data is written
+    pub fn on_fetch<F>(mut self, f: F) -> Self
+    where
+        F: Fn(Result<&T, &ToteError>) + Send + Sync + 'static,
+    {
+        self.hooks.set_fetch(f);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::Fetch;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Data(u8);
+
+    impl Fetch for Data {
+        type Cached = Data;
+
+        fn fetch() -> Result<Data, Box<dyn std::error::Error>> {
+            Ok(Data(1))
+        }
+    }
+
+    #[test]
+    fn test_hooks() {
+        static EVENTS: Mutex<Vec<&str>> = Mutex::new(Vec::new());
+        static FETCHED: AtomicUsize = AtomicUsize::new(0);
+        let event = |name| move || EVENTS.lock().unwrap().push(name);
+
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let cache: Tote<Data> = Tote::new(file.path(), Duration::from_secs(60))
+            .on_hit(event("hit"))
+            .on_miss(event("miss"))
+            .on_fetch_start(event("fetch"))
+            .on_fetch(|res: Result<&Data, &ToteError>| {
+                let value = res.map(|data| data.0 as usize).unwrap_or(100);
+                FETCHED.fetch_add(value, Ordering::SeqCst);
+            });
+
+        cache.get().unwrap();
+        cache.get().unwrap();
+        assert_eq!(*EVENTS.lock().unwrap(), ["miss", "fetch", "hit"]);
+        assert_eq!(FETCHED.load(Ordering::SeqCst), 1);
+
+        // Unset hooks are skipped
+        let cache: Tote<Data> = Tote::new(file.path(), Duration::from_secs(60));
+        cache.get().unwrap();
+        assert_eq!(EVENTS.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_hooks_read_paths() {
+        static EVENTS: Mutex<Vec<&str>> = Mutex::new(Vec::new());
+        let event = |name| move || EVENTS.lock().unwrap().push(name);
+        let take = || std::mem::take(&mut *EVENTS.lock().unwrap());
+
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let cache: Tote<Data> = Tote::new(file.path(), Duration::from_secs(60))
+            .on_hit(event("hit"))
+            .on_miss(event("miss"))
+            .on_fetch_start(event("fetch"));
+
+        cache.get_or_fetch(|| Ok(Data(2))).unwrap();
+        cache.get_or_fetch(|| Ok(Data(2))).unwrap();
+        assert_eq!(take(), ["miss", "fetch", "hit"]);
+
+        cache.invalidate().unwrap();
+        cache
+            .get_or_try_insert_with(|| Ok::<_, ToteError>(Data(3)))
+            .unwrap();
+        cache
+            .get_or_try_insert_with(|| Ok::<_, ToteError>(Data(3)))
+            .unwrap();
+        assert_eq!(take(), ["miss", "fetch", "hit"]);
+
+        cache.invalidate().unwrap();
+        cache.get_opts(Default::default()).unwrap();
+        cache.get_opts(Default::default()).unwrap();
+        assert_eq!(take(), ["miss", "fetch", "hit"]);
+    }
+}
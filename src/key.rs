@@ -0,0 +1,90 @@
+use std::fmt::{self, Display, Write};
+
+/// Builds a stable, filename-safe cache key from multiple components
+///
+/// Components are joined with `-`, and any character other than ASCII
+/// alphanumerics is escaped (as `_` + hex bytes), so differently split
+/// components never collide (e.g. `"a"` + `"bc"` vs `"ab"` + `"c"`).
+/// Use with `Tote::segmented_by` to cache per composite key, e.g.
+/// `cache.segmented_by(KeyBuilder::new().push(region).push(date))`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct KeyBuilder {
+    key: String,
+    components: usize,
+}
+
+impl KeyBuilder {
+    /// Create a key with no components
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a component to the key
+    pub fn push(mut self, component: impl Display) -> Self {
+        if self.components > 0 {
+            self.key.push('-');
+        }
+        for c in component.to_string().chars() {
+            if c.is_ascii_alphanumeric() {
+                self.key.push(c);
+            } else {
+                let mut bytes = [0; 4];
+                for byte in c.encode_utf8(&mut bytes).bytes() {
+                    // Writing to a `String` can't fail
+                    let _ = write!(self.key, "_{:02X}", byte);
+                }
+            }
+        }
+        self.components += 1;
+        self
+    }
+
+    /// Get the built key
+    pub fn build(&self) -> String {
+        self.key.clone()
+    }
+}
+
+impl Display for KeyBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(components: &[&str]) -> String {
+        components
+            .iter()
+            .fold(KeyBuilder::new(), |key, component| key.push(component))
+            .build()
+    }
+
+    #[test]
+    fn test_key_builder() {
+        assert_eq!(
+            KeyBuilder::new().push("us-east").push(1).build(),
+            "us_2Deast-1"
+        );
+        assert_eq!(key(&["é"]), "_C3_A9");
+
+        // Ambiguous boundaries don't collide
+        let keys = [
+            key(&["a", "bc"]),
+            key(&["ab", "c"]),
+            key(&["a-bc"]),
+            key(&["a", "-bc"]),
+            key(&["a_2Dbc"]),
+            key(&["abc", ""]),
+            key(&["abc"]),
+            key(&[""]),
+        ];
+        for (i, a) in keys.iter().enumerate() {
+            for b in &keys[i + 1..] {
+                assert_ne!(a, b);
+            }
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use std::fmt;
+use std::ops::Deref;
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::{Codec, Fetch, Tote, ToteError};
+
+type RefreshFn = Box<dyn FnOnce() + Send>;
+
+/// Cached data returned from `Tote::lease`, which can refresh the cache
+/// in the background when dropped
+///
+/// Derefs to the cached data
+pub struct CacheLease<T> {
+    data: T,
+    refresh: Option<RefreshFn>,
+}
+
+impl<T: fmt::Debug> fmt::Debug for CacheLease<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CacheLease")
+            .field("data", &self.data)
+            .field("refresh", &self.refresh.is_some())
+            .finish()
+    }
+}
+
+impl<T> Deref for CacheLease<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.data
+    }
+}
+
+impl<T> Drop for CacheLease<T> {
+    fn drop(&mut self) {
+        if let Some(refresh) = self.refresh.take() {
+            refresh();
+        }
+    }
+}
+
+impl<T, C: Codec> Tote<T, C>
+where
+    T: DeserializeOwned,
+    T: Serialize + Fetch<Cached = T> + Send + 'static,
+{
+    /// Fetch the cached data (like `get`) as a `CacheLease`
+    ///
+    /// When `refresh_within` is Some, dropping the lease re-fetches the data
+    /// on a background thread if the cache file will expire within that
+    /// window, keeping the cache warm. Refresh errors are ignored
+    pub fn lease(&self, refresh_within: Option<Duration>) -> Result<CacheLease<T>, ToteError> {
+        let data = self.get()?;
+        let refresh = refresh_within.map(|window| {
+            let tote = self.clone();
+            Box::new(move || {
+                let near_expiry = tote
+                    .age_of(&tote.path)
+                    .is_none_or(|age| age + window >= tote.max_age);
+                if near_expiry {
+                    std::thread::spawn(move || {
+                        if let Ok(data) = T::fetch() {
+                            let _ = tote.put(&data);
+                        }
+                    });
+                }
+            }) as RefreshFn
+        });
+        Ok(CacheLease { data, refresh })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use std::time::SystemTime;
+    use tempfile::NamedTempFile;
+
+    use crate::touch;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Name(String);
+
+    impl Fetch for Name {
+        type Cached = Name;
+
+        fn fetch() -> Result<Name, Box<dyn std::error::Error>> {
+            Ok(Name("Fetched".to_owned()))
+        }
+    }
+
+    fn staged(age: Duration) -> (NamedTempFile, Tote<Name>) {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<Name> = Tote::new(file.path(), Duration::from_secs(60));
+        cache.put(&Name("Old".to_owned())).unwrap();
+        touch(file.path(), SystemTime::now() - age).unwrap();
+        (file, cache)
+    }
+
+    #[test]
+    fn test_lease_refresh() {
+        // Fresh lease doesn't refresh
+        let (file, cache) = staged(Duration::ZERO);
+        let lease = cache.lease(Some(Duration::from_secs(10))).unwrap();
+        assert_eq!(lease.0, "Old");
+        drop(lease);
+        std::thread::sleep(Duration::from_millis(100));
+        assert_eq!(cache.read().unwrap().0, "Old");
+        drop(file);
+
+        // Near-expiry lease refreshes in the background
+        let (_file, cache) = staged(Duration::from_secs(55));
+        let lease = cache.lease(Some(Duration::from_secs(10))).unwrap();
+        assert_eq!(lease.0, "Old");
+        drop(lease);
+        let refreshed = (0..50).any(|_| {
+            std::thread::sleep(Duration::from_millis(20));
+            cache.read().is_ok_and(|name| name.0 == "Fetched")
+        });
+        assert!(refreshed);
+    }
+}
@@ -1,174 +1,7918 @@
 #![deny(missing_docs)]
 #![doc = include_str!("../README.md")]
 
+use std::fmt::{self, Display};
 use std::fs;
 use std::io::{self, Write};
 use std::marker::PhantomData;
 use std::path::{Path, PathBuf};
-use std::time::Duration;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 
 #[cfg(feature = "async")]
 pub use async_trait::async_trait;
 
+use serde::de::{DeserializeOwned, DeserializeSeed};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+#[cfg(feature = "zip")]
+mod archive;
+mod backend;
+mod builder;
+#[cfg(feature = "encrypt")]
+mod cipher;
+mod hooks;
+mod key;
+mod lease;
+mod map;
+#[cfg(feature = "async")]
+mod prefetch;
+mod raw;
+mod stream;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+mod tote_map;
+
+#[cfg(feature = "zip")]
+pub use archive::ZipBackend;
+pub use backend::{Backend, FsBackend, MemoryBackend};
+pub use builder::ToteBuilder;
+#[cfg(feature = "encrypt")]
+pub use cipher::{ChaChaCipher, Cipher};
+pub use key::KeyBuilder;
+pub use lease::CacheLease;
+pub use map::MapTote;
+#[cfg(feature = "async")]
+pub use prefetch::{prefetch_all, Prefetch};
+pub use raw::RawTote;
+pub use tote_map::ToteMap;
+
 /// A trait provided to allow `Tote` to fetch the data
 /// when no cache exists or cache is expired
+///
+/// As `fetch` takes no `self`, a closure can't implement `Fetch`. Instead,
+/// pass the closure to `Tote::get_or_fetch` (or `Tote::get_or_try_insert_with`
+/// to keep its error type), which needs no `Fetch` impl:
+///
+/// ```ignore
+/// let cache: Tote<Host> = Tote::new("host.json", Duration::from_secs(60));
+///
+/// let host = cache.get_or_fetch(|| {
+///     Ok(reqwest::blocking::get("https://api.example.com/host")?.json()?)
+/// })?;
+///
+/// let host = cache.get_or_try_insert_with(|| -> Result<Host, reqwest::Error> {
+///     reqwest::blocking::get("https://api.example.com/host")?.json()
+/// })?;
+/// ```
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` does not implement `tote::Fetch`",
+    note = "implement `Fetch` for `{Self}` to use `Tote::get()`, or call `Tote::get_async().await` if it implements `AsyncFetch`"
+)]
 pub trait Fetch: Serialize {
     /// The data type to be fetched & cached
     type Cached;
 
-    /// Strategy for fetching data to cache
-    fn fetch() -> std::result::Result<Self::Cached, Box<dyn std::error::Error>>;
-}
+    /// Strategy for fetching data to cache
+    fn fetch() -> std::result::Result<Self::Cached, Box<dyn std::error::Error>>;
+}
+
+/// A trait provided to allow `Tote` to fetch data as a different (e.g.
+/// verbose) `Raw` type, converted with `Into` before it's cached
+///
+/// Types implementing `FetchInto` implement `Fetch`, so `Tote::get` works
+/// as usual
+///
+/// ```ignore
+/// #[derive(Deserialize)]
+/// struct ApiResponse {
+///     hostname: String,
+///     uptime: u64,
+///     // ...many more fields
+/// }
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Host {
+///     hostname: String,
+/// }
+///
+/// impl From<ApiResponse> for Host {
+///     fn from(resp: ApiResponse) -> Host {
+///         Host { hostname: resp.hostname }
+///     }
+/// }
+///
+/// impl FetchInto for Host {
+///     type Raw = ApiResponse;
+///
+///     fn fetch_raw() -> Result<ApiResponse, Box<dyn std::error::Error>> {
+///         Ok(reqwest::blocking::get("https://api.example.com/host")?.json()?)
+///     }
+/// }
+///
+/// let host: Host = cache.get()?;
+/// ```
+pub trait FetchInto: Serialize + Sized {
+    /// The fetched data type, converted into the cached data type
+    type Raw: Into<Self>;
+
+    /// Strategy for fetching the raw data
+    fn fetch_raw() -> std::result::Result<Self::Raw, Box<dyn std::error::Error>>;
+}
+
+impl<T: FetchInto> Fetch for T {
+    type Cached = T;
+
+    fn fetch() -> std::result::Result<T, Box<dyn std::error::Error>> {
+        T::fetch_raw().map(Into::into)
+    }
+}
+
+/// A trait provided to allow `Tote` to fetch the data using runtime
+/// arguments (e.g. an API token), used by `Tote::get_with`
+///
+/// ```ignore
+/// struct Auth {
+///     token: String,
+/// }
+///
+/// impl FetchWith<&Auth> for Profile {
+///     type Cached = Profile;
+///
+///     fn fetch_with(auth: &Auth) -> Result<Profile, Box<dyn std::error::Error>> {
+///         let profile = reqwest::blocking::Client::new()
+///             .get("https://api.example.com/profile")
+///             .bearer_auth(&auth.token)
+///             .send()?
+///             .json()?;
+///         Ok(profile)
+///     }
+/// }
+///
+/// let profile = cache.get_with(&Auth { token })?;
+/// ```
+pub trait FetchWith<Args>: Serialize {
+    /// The data type to be fetched & cached
+    type Cached;
+
+    /// Strategy for fetching data to cache, given `args`
+    fn fetch_with(args: Args) -> std::result::Result<Self::Cached, Box<dyn std::error::Error>>;
+}
+
+/// A trait provided to allow `Tote` to fetch partial data along with
+/// non-fatal warnings, used by `Tote::get_with_warnings`
+///
+/// The (possibly incomplete) data is cached, so the cache stays warm
+/// in degraded conditions
+pub trait FetchWithWarnings: Serialize {
+    /// The data type to be fetched & cached
+    type Cached;
+    /// Non-fatal issue reported alongside fetched data
+    type Warning;
+
+    /// Strategy for fetching data to cache, with any warnings
+    #[allow(clippy::type_complexity)]
+    fn fetch_with_warnings(
+    ) -> std::result::Result<(Self::Cached, Vec<Self::Warning>), Box<dyn std::error::Error>>;
+}
+
+#[cfg(feature = "async")]
+/// A trait provided to allow `Tote` to fetch the data
+/// when no cache exists or cache is expired
+#[async_trait]
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` does not implement `tote::AsyncFetch`",
+    note = "implement `AsyncFetch` for `{Self}` to use `Tote::get_async()`, or call `Tote::get()` if it implements `Fetch`"
+)]
+pub trait AsyncFetch {
+    /// The data type to be fetched & cached
+    type Cached;
+
+    #[cfg(feature = "async")]
+    /// Strategy for fetching data to cache
+    async fn fetch_async() -> std::result::Result<Self::Cached, Box<dyn std::error::Error>>;
+}
+
+#[cfg(feature = "async-native")]
+/// A trait provided to allow `Tote` to fetch the data
+/// when no cache exists or cache is expired
+///
+/// Unlike `AsyncFetch`, this uses native `async fn` in traits so fetch
+/// futures aren't boxed. Implement with `async fn fetch_async()`
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` does not implement `tote::AsyncFetchNative`",
+    note = "implement `AsyncFetchNative` for `{Self}` to use `Tote::get_async_native()`"
+)]
+pub trait AsyncFetchNative {
+    /// The data type to be fetched & cached
+    type Cached;
+
+    /// Strategy for fetching data to cache
+    fn fetch_async(
+    ) -> impl std::future::Future<Output = std::result::Result<Self::Cached, Box<dyn std::error::Error>>>;
+}
+
+/// A freshness policy deciding whether cached data is still valid
+///
+/// Implemented for `Duration` (fresh while the cache file's age is within
+/// the duration) and `SystemTime` (fresh until the given time). Implement
+/// this for custom policies (e.g. "fresh on weekdays only")
+pub trait ExpiryPolicy {
+    /// Whether a cache file last written at `created` is still fresh at `now`
+    fn is_fresh(&self, created: SystemTime, now: SystemTime, metadata: &fs::Metadata) -> bool;
+}
+
+/// `Duration::MAX` never expires and `Duration::ZERO` is always expired.
+/// A `created` time in the future (e.g. from clock skew) is fresh
+impl ExpiryPolicy for Duration {
+    fn is_fresh(&self, created: SystemTime, now: SystemTime, _metadata: &fs::Metadata) -> bool {
+        within_max_age(Some(age_at(created, now)), *self)
+    }
+}
+
+impl ExpiryPolicy for SystemTime {
+    fn is_fresh(&self, _created: SystemTime, now: SystemTime, _metadata: &fs::Metadata) -> bool {
+        now <= *self
+    }
+}
+
+/// Source of the current time used for expiry checks, see `Tote::with_clock`
+///
+/// Implemented for closures returning a `SystemTime`, e.g. a clock which
+/// tests can advance without sleeping
+pub trait Clock {
+    /// The current time
+    fn now(&self) -> SystemTime;
+}
+
+impl<F: Fn() -> SystemTime> Clock for F {
+    fn now(&self) -> SystemTime {
+        self()
+    }
+}
+
+/// Data which can be incrementally updated by merging in a delta,
+/// used by `Tote::get_and_merge`
+pub trait Mergeable {
+    /// Merge `other` into this data, with `other` taking precedence
+    fn merge(&mut self, other: Self);
+}
+
+impl<K: std::hash::Hash + Eq, V> Mergeable for std::collections::HashMap<K, V> {
+    fn merge(&mut self, other: Self) {
+        self.extend(other);
+    }
+}
+
+impl<K: Ord, V> Mergeable for std::collections::BTreeMap<K, V> {
+    fn merge(&mut self, other: Self) {
+        self.extend(other);
+    }
+}
+
+/// An estimate of a value's serialized size, used by `Tote::with_size_hint`
+/// to pre-allocate the write buffer for large payloads
+pub trait SizeHint {
+    /// Approximate serialized size in bytes, or None if unknown
+    fn size_hint(&self) -> Option<usize>;
+}
+
+/// A serialization format used to read & write cached data
+///
+/// `Tote` uses `JsonCodec` by default, use `Tote::new_with_codec` to cache
+/// in another format. Implement this to plug in a custom format, e.g.:
+///
+/// ```ignore
+/// #[derive(Clone)]
+/// struct Bincode;
+///
+/// impl Codec for Bincode {
+///     fn serialize<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, ToteError> {
+///         bincode::serialize(value).map_err(|e| ToteError::Codec(e.into()))
+///     }
+///
+///     fn deserialize<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, ToteError> {
+///         bincode::deserialize(bytes).map_err(|e| ToteError::Codec(e.into()))
+///     }
+/// }
+/// ```
+///
+/// Deserialization errors should be returned as `ToteError::Codec` (or
+/// `ToteError::Parse`) so corrupt cache files are re-fetched & quarantined
+pub trait Codec: Clone + Send + Sync + 'static {
+    /// Serialize data to be written to the cache file
+    fn serialize<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, ToteError>;
+
+    /// Serialize data like `serialize`, into a buffer pre-allocated with
+    /// `capacity` bytes, see `Tote::with_size_hint`
+    ///
+    /// The default ignores `capacity` & calls `serialize`
+    fn serialize_with_capacity<T: Serialize>(
+        &self,
+        value: &T,
+        capacity: usize,
+    ) -> Result<Vec<u8>, ToteError> {
+        let _ = capacity;
+        self.serialize(value)
+    }
+
+    /// Deserialize data read from the cache file
+    fn deserialize<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, ToteError>;
+}
+
+/// The default `Codec`, caching data as JSON (via `serde_json`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct JsonCodec;
+
+impl Codec for JsonCodec {
+    fn serialize<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, ToteError> {
+        Ok(serde_json::to_vec(value)?)
+    }
+
+    fn serialize_with_capacity<T: Serialize>(
+        &self,
+        value: &T,
+        capacity: usize,
+    ) -> Result<Vec<u8>, ToteError> {
+        let mut data = Vec::with_capacity(capacity);
+        serde_json::to_writer(&mut data, value)?;
+        Ok(data)
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, ToteError> {
+        serde_json::from_slice(bytes).map_err(ToteError::Parse)
+    }
+}
+
+#[cfg(feature = "bincode")]
+/// A compact binary `Codec` (via `bincode`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BincodeCodec;
+
+#[cfg(feature = "bincode")]
+impl Codec for BincodeCodec {
+    fn serialize<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, ToteError> {
+        bincode::serialize(value).map_err(|e| ToteError::Codec(e))
+    }
+
+    fn serialize_with_capacity<T: Serialize>(
+        &self,
+        value: &T,
+        capacity: usize,
+    ) -> Result<Vec<u8>, ToteError> {
+        let mut data = Vec::with_capacity(capacity);
+        bincode::serialize_into(&mut data, value).map_err(|e| ToteError::Codec(e))?;
+        Ok(data)
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, ToteError> {
+        bincode::deserialize(bytes).map_err(|e| ToteError::Codec(e))
+    }
+}
+
+#[cfg(feature = "cbor")]
+/// A binary `Codec` caching data as CBOR (via `ciborium`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CborCodec;
+
+#[cfg(feature = "cbor")]
+impl Codec for CborCodec {
+    fn serialize<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, ToteError> {
+        self.serialize_with_capacity(value, 0)
+    }
+
+    fn serialize_with_capacity<T: Serialize>(
+        &self,
+        value: &T,
+        capacity: usize,
+    ) -> Result<Vec<u8>, ToteError> {
+        let mut data = Vec::with_capacity(capacity);
+        ciborium::into_writer(value, &mut data).map_err(|e| ToteError::Codec(e.into()))?;
+        Ok(data)
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, ToteError> {
+        ciborium::from_reader(bytes).map_err(|e| ToteError::Codec(e.into()))
+    }
+}
+
+/// A `Codec` chosen at runtime from the cache file's extension, see
+/// `from_extension` & `ToteBuilder::codec_from_extension`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExtensionCodec {
+    /// `JsonCodec`, for `.json` & unknown extensions
+    #[default]
+    Json,
+    #[cfg(feature = "cbor")]
+    /// `CborCodec`, for `.cbor`
+    Cbor,
+    #[cfg(feature = "bincode")]
+    /// `BincodeCodec`, for `.bin`
+    Bincode,
+}
+
+impl ExtensionCodec {
+    /// Pick the codec for the extension of `path` (`.json`, `.cbor` or `.bin`)
+    ///
+    /// Falls back to JSON for other extensions, or those whose codec's
+    /// feature isn't enabled
+    pub fn from_extension<P: AsRef<Path>>(path: P) -> Self {
+        match path.as_ref().extension().and_then(|ext| ext.to_str()) {
+            #[cfg(feature = "cbor")]
+            Some("cbor") => Self::Cbor,
+            #[cfg(feature = "bincode")]
+            Some("bin") => Self::Bincode,
+            _ => Self::Json,
+        }
+    }
+}
+
+impl Codec for ExtensionCodec {
+    fn serialize<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, ToteError> {
+        match self {
+            Self::Json => JsonCodec.serialize(value),
+            #[cfg(feature = "cbor")]
+            Self::Cbor => CborCodec.serialize(value),
+            #[cfg(feature = "bincode")]
+            Self::Bincode => BincodeCodec.serialize(value),
+        }
+    }
+
+    fn serialize_with_capacity<T: Serialize>(
+        &self,
+        value: &T,
+        capacity: usize,
+    ) -> Result<Vec<u8>, ToteError> {
+        match self {
+            Self::Json => JsonCodec.serialize_with_capacity(value, capacity),
+            #[cfg(feature = "cbor")]
+            Self::Cbor => CborCodec.serialize_with_capacity(value, capacity),
+            #[cfg(feature = "bincode")]
+            Self::Bincode => BincodeCodec.serialize_with_capacity(value, capacity),
+        }
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, ToteError> {
+        match self {
+            Self::Json => JsonCodec.deserialize(bytes),
+            #[cfg(feature = "cbor")]
+            Self::Cbor => CborCodec.deserialize(bytes),
+            #[cfg(feature = "bincode")]
+            Self::Bincode => BincodeCodec.deserialize(bytes),
+        }
+    }
+}
+
+/// Validators from a previous fetch, passed to `FetchConditional` so the
+/// source can be queried conditionally (e.g. HTTP `If-None-Match`)
+///
+/// Stored in a `<path>.meta` sidecar file next to the cache
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FetchMeta {
+    /// Entity tag of the fetched data
+    pub etag: Option<String>,
+    /// Last modified time of the fetched data, as reported by the source
+    pub last_modified: Option<String>,
+}
+
+/// Outcome of a `FetchConditional` fetch
+#[derive(Debug)]
+pub enum Conditional<T> {
+    /// The source data is unchanged, keep using the cached data
+    Unchanged,
+    /// The source data has changed
+    Changed(T, FetchMeta),
+}
+
+/// A trait provided to allow `Tote` to conditionally fetch the data
+/// when the cache is expired, using validators from the previous fetch
+pub trait FetchConditional: Serialize {
+    /// The data type to be fetched & cached
+    type Cached;
+
+    /// Strategy for fetching data to cache
+    ///
+    /// `meta` is the `FetchMeta` stored with the previously cached data, if any
+    fn fetch_conditional(
+        meta: Option<&FetchMeta>,
+    ) -> std::result::Result<Conditional<Self::Cached>, Box<dyn std::error::Error>>;
+}
+
+/// Errors that can occur during `Tote` operations
+#[derive(Error, Debug)]
+pub enum ToteError {
+    /// Error reading/writing from given cache file path
+    #[error(transparent)]
+    FileAccess(#[from] std::io::Error),
+    /// Error with Serde serialization of data being written
+    #[error(transparent)]
+    Serde(#[from] serde_json::Error),
+    /// Cached data read from the cache file could not be deserialized
+    #[error("Error parsing cached data: {0}")]
+    Parse(#[source] serde_json::Error),
+    /// Cached data is missing or cannot be read
+    #[error("Cached data is not valid")]
+    InvalidCache,
+    /// Error while fetching data
+    #[error(transparent)]
+    Fetching(Box<dyn std::error::Error>),
+    /// Error while fetching data, with the path of the cache being populated
+    ///
+    /// Returned instead of `Fetching` when enabled with `Tote::map_err_with_path`
+    #[error("Error while fetching data for {path:?}: {source}")]
+    FetchingFor {
+        /// The cache filepath
+        path: PathBuf,
+        /// The fetch error
+        source: Box<dyn std::error::Error>,
+    },
+    /// The cache filepath can't be used
+    #[error("Invalid cache path {path:?}: {reason}")]
+    InvalidPath {
+        /// The offending filepath
+        path: PathBuf,
+        /// Why the filepath can't be used
+        reason: &'static str,
+    },
+    /// Conflicting or unsupported options were given
+    #[error("Invalid options: {0}")]
+    InvalidOptions(&'static str),
+    /// Error from a user-provided encoder/decoder
+    #[error("Error encoding/decoding cached data: {0}")]
+    Codec(Box<dyn std::error::Error>),
+    /// Data read back after a write did not match the written value
+    #[error("Cached data did not match after writing")]
+    WriteVerificationFailed,
+    /// Fetching took longer than the `Tote::with_fetch_timeout` limit
+    #[error("Fetching data timed out after {0:?}")]
+    Timeout(Duration),
+    /// Fetching failed after an existing, unexpired cache file couldn't be
+    /// deserialized (e.g. as the cached type changed), so clearing the
+    /// cache file may be needed
+    ///
+    /// Returned by `get` (and `get_async`) instead of the fetch error
+    #[error("Error while fetching data ({path:?} is unreadable: {read}): {source}")]
+    UnreadableCache {
+        /// The cache filepath
+        path: PathBuf,
+        /// The error deserializing the cache file
+        read: Box<ToteError>,
+        /// The fetch error
+        source: Box<ToteError>,
+    },
+    /// Serialized data exceeded the hard limit of `Tote::with_size_budget`,
+    /// even once compressed
+    #[error("Cached data is {size} bytes, over the {limit} byte limit")]
+    TooLarge {
+        /// Size of the data to write, in bytes
+        size: usize,
+        /// The hard limit, in bytes
+        limit: usize,
+    },
+}
+
+/// Kind of a `ToteError`, without the inner error, so it can be
+/// copied & compared
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ToteErrorKind {
+    /// See `ToteError::FileAccess`
+    FileAccess,
+    /// See `ToteError::Serde`
+    Serde,
+    /// See `ToteError::Parse`
+    Parse,
+    /// See `ToteError::InvalidCache`
+    InvalidCache,
+    /// See `ToteError::Fetching` & `ToteError::FetchingFor`
+    Fetching,
+    /// See `ToteError::InvalidPath`
+    InvalidPath,
+    /// See `ToteError::InvalidOptions`
+    InvalidOptions,
+    /// See `ToteError::Codec`
+    Codec,
+    /// See `ToteError::WriteVerificationFailed`
+    WriteVerificationFailed,
+    /// See `ToteError::Timeout`
+    Timeout,
+    /// See `ToteError::UnreadableCache`
+    UnreadableCache,
+    /// See `ToteError::TooLarge`
+    TooLarge,
+}
+
+impl ToteError {
+    /// The kind of this error
+    pub fn kind(&self) -> ToteErrorKind {
+        match self {
+            Self::FileAccess(_) => ToteErrorKind::FileAccess,
+            Self::Serde(_) => ToteErrorKind::Serde,
+            Self::Parse(_) => ToteErrorKind::Parse,
+            Self::InvalidCache => ToteErrorKind::InvalidCache,
+            Self::Fetching(_) | Self::FetchingFor { .. } => ToteErrorKind::Fetching,
+            Self::InvalidPath { .. } => ToteErrorKind::InvalidPath,
+            Self::InvalidOptions(_) => ToteErrorKind::InvalidOptions,
+            Self::Codec(_) => ToteErrorKind::Codec,
+            Self::WriteVerificationFailed => ToteErrorKind::WriteVerificationFailed,
+            Self::Timeout(_) => ToteErrorKind::Timeout,
+            Self::UnreadableCache { .. } => ToteErrorKind::UnreadableCache,
+            Self::TooLarge { .. } => ToteErrorKind::TooLarge,
+        }
+    }
+}
+
+/// Errors from `Tote` operations that use a caller-provided fetch closure,
+/// preserving the concrete fetch error type
+#[derive(Error, Debug)]
+pub enum GetError<E> {
+    /// Error reading/writing the cache
+    #[error(transparent)]
+    Cache(#[from] ToteError),
+    /// Error returned by the fetch closure
+    #[error("Error while fetching data: {0}")]
+    Fetch(E),
+}
+
+/// How current a cache file is, returned from `Tote::freshness`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Freshness {
+    /// Within `max_age`
+    Fresh,
+    /// Past `max_age` but within the `with_grace` window, still served
+    /// by `get` without fetching
+    Stale,
+    /// Missing, invalid or past `max_age` plus any grace window
+    Expired,
+}
+
+/// Where data returned from a `Tote` came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// Read from the cache file
+    Cache,
+    /// Freshly fetched
+    Fetched,
+}
+
+/// Metadata about data returned from `Tote::get_with_meta`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheMeta {
+    /// Where the data came from
+    pub source: Source,
+    /// Age of the data, from the cache file's modified time
+    /// (zero for freshly fetched data, None if unknown)
+    pub age: Option<Duration>,
+}
+
+/// Data returned from `Tote::get_or_static`, either a borrowed static default
+/// or owned cached data
+///
+/// Only fetched data is ever written to the cache file, the default is not
+#[derive(Debug, Clone, PartialEq)]
+pub enum DefaultOrFetched<T: 'static> {
+    /// Compiled-in default, returned when the cache file is missing or expired
+    Default(&'static T),
+    /// Previously fetched data, read from the cache file
+    Fetched(T),
+}
+
+impl<T> DefaultOrFetched<T> {
+    /// Returns true if this is the static default
+    pub fn is_default(&self) -> bool {
+        matches!(self, Self::Default(_))
+    }
+
+    /// Get owned data, cloning the default if needed
+    pub fn into_owned(self) -> T
+    where
+        T: Clone,
+    {
+        match self {
+            Self::Default(data) => data.clone(),
+            Self::Fetched(data) => data,
+        }
+    }
+}
+
+impl<T> std::ops::Deref for DefaultOrFetched<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        match self {
+            Self::Default(data) => data,
+            Self::Fetched(data) => data,
+        }
+    }
+}
+
+/// Optional features compiled into this build of `tote`, see `capabilities`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Capabilities {
+    /// `"async"`: `AsyncFetch` & `Tote::get_async`
+    pub async_fetch: bool,
+    /// `"async-native"`: `AsyncFetchNative` & `Tote::get_async_native`
+    pub async_native: bool,
+    /// `"bytes"`: `Tote::get_bytes`
+    pub bytes: bool,
+    /// `"sha256"`: `Tote::with_hash_sidecar`
+    pub sha256: bool,
+    /// `"mmap"`: `Tote::memory_mapped`
+    pub mmap: bool,
+    /// `"bincode"`: `BincodeCodec`
+    pub bincode: bool,
+    /// `"cbor"`: `CborCodec`
+    pub cbor: bool,
+    /// `"gzip"`: `Tote::with_gzip`
+    pub gzip: bool,
+    /// `"encrypt"`: `Tote::with_cipher`
+    pub encrypt: bool,
+    /// `"tracing"`: `tracing` spans & events
+    pub tracing: bool,
+    /// `"zip"`: `ZipBackend` & `Tote::in_zip`
+    pub zip: bool,
+}
+
+/// Report which optional features were compiled in, e.g. for
+/// `--version --verbose` output
+pub fn capabilities() -> Capabilities {
+    Capabilities {
+        async_fetch: cfg!(feature = "async"),
+        async_native: cfg!(feature = "async-native"),
+        bytes: cfg!(feature = "bytes"),
+        sha256: cfg!(feature = "sha256"),
+        mmap: cfg!(feature = "mmap"),
+        bincode: cfg!(feature = "bincode"),
+        cbor: cfg!(feature = "cbor"),
+        gzip: cfg!(feature = "gzip"),
+        encrypt: cfg!(feature = "encrypt"),
+        tracing: cfg!(feature = "tracing"),
+        zip: cfg!(feature = "zip"),
+    }
+}
+
+/// Status of a cache file, as reported by `Tote::probe`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheStatus {
+    /// Cache file exists and is not expired
+    Hit,
+    /// Cache file exists but is expired
+    Expired,
+    /// Cache file is missing (or empty)
+    Miss,
+}
+
+/// Options for a single `Tote::get_opts` call
+///
+/// | `force_refresh` | `offline` | Behavior                                     |
+/// |-----------------|-----------|----------------------------------------------|
+/// | `false`         | `false`   | Read the cache, fetching if missing/expired  |
+/// | `true`          | `false`   | Always fetch                                 |
+/// | `false`         | `true`    | Read the cache, Err if missing/expired       |
+/// | `true`          | `true`    | Invalid, returns `ToteError::InvalidOptions` |
+///
+/// `write` controls whether fetched data is written to the cache file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GetOptions {
+    /// Skip cached data and always fetch
+    pub force_refresh: bool,
+    /// Never fetch, only return cached data
+    pub offline: bool,
+    /// Write fetched data to the cache file
+    pub write: bool,
+}
+
+impl Default for GetOptions {
+    fn default() -> Self {
+        Self {
+            force_refresh: false,
+            offline: false,
+            write: true,
+        }
+    }
+}
+
+impl GetOptions {
+    fn validate(&self) -> Result<(), ToteError> {
+        if self.force_refresh && self.offline {
+            return Err(ToteError::InvalidOptions(
+                "`force_refresh` requires fetching, which `offline` disallows",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// How `Tote` handles failing to write freshly fetched data
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OnWriteError {
+    /// Return the write error (default)
+    #[default]
+    Fail,
+    /// Ignore the write error and return the fetched data (the error is
+    /// reported as a `tracing` warning with the `tracing` feature)
+    ReturnData,
+}
+
+/// Size limits for written cache files, see `Tote::with_size_budget`
+#[cfg(feature = "gzip")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SizeBudget {
+    /// Size in bytes over which data is gzip-compressed
+    pub soft: usize,
+    /// Size in bytes over which (compressed) data isn't written
+    pub hard: usize,
+}
+
+/// How `Tote` handles writing data which serializes identically to the
+/// existing cache file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OnUnchanged {
+    /// Rewrite the cache file (default)
+    #[default]
+    Write,
+    /// Skip the write, but reset the cache file's expiry
+    RefreshExpiry,
+    /// Skip the write, keeping the cache file's original expiry
+    PreserveExpiry,
+}
+
+/// Serializable configuration of a `Tote` (not its cached data), see
+/// `Tote::into_parts` & `Tote::from_parts`
+///
+/// Options set with closures or trait objects (e.g. `with_ttl_from`,
+/// `with_expiry_policy`, `with_serde_transform`) aren't included
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToteConfig {
+    /// Cache filepath
+    pub path: PathBuf,
+    /// Expiry age of the cached data
+    pub max_age: Duration,
+    /// See `Tote::deterministic`
+    #[serde(default)]
+    pub deterministic: bool,
+    /// See `Tote::pretty`
+    #[serde(default)]
+    pub pretty: bool,
+    /// See `Tote::newest_of`
+    #[serde(default)]
+    pub candidates: Vec<PathBuf>,
+    /// See `Tote::with_mirror`
+    #[serde(default)]
+    pub mirror: Option<PathBuf>,
+    /// See `Tote::with_buffer_size`
+    #[serde(default = "default_buffer_size")]
+    pub buffer_size: usize,
+    #[cfg(feature = "sha256")]
+    /// See `Tote::with_hash_sidecar`
+    #[serde(default)]
+    pub hash_sidecar: bool,
+    #[cfg(feature = "gzip")]
+    /// See `Tote::with_gzip`
+    #[serde(default)]
+    pub gzip: bool,
+    #[cfg(feature = "gzip")]
+    /// See `Tote::with_size_budget`
+    #[serde(default)]
+    pub size_budget: Option<SizeBudget>,
+    #[cfg(feature = "mmap")]
+    /// See `Tote::memory_mapped`
+    #[serde(default)]
+    pub memory_mapped: bool,
+    #[cfg(unix)]
+    /// See `Tote::with_file_mode`
+    #[serde(default = "default_file_mode")]
+    pub file_mode: u32,
+    /// See `Tote::sliding_expiration`
+    #[serde(default)]
+    pub sliding_expiration: bool,
+    /// See `Tote::on_write_error`
+    #[serde(default)]
+    pub on_write_error: OnWriteError,
+    /// See `Tote::on_unchanged`
+    #[serde(default)]
+    pub on_unchanged: OnUnchanged,
+    /// See `Tote::quarantine_to`
+    #[serde(default)]
+    pub quarantine: Option<PathBuf>,
+    /// See `Tote::with_read_retry`
+    #[serde(default = "default_attempts")]
+    pub read_attempts: u32,
+    /// See `Tote::with_fetch_retry`
+    #[serde(default = "default_attempts")]
+    pub fetch_attempts: u32,
+    /// See `Tote::with_fetch_retry`
+    #[serde(default)]
+    pub fetch_backoff: Duration,
+    #[cfg(any(feature = "async", feature = "async-native"))]
+    /// See `Tote::with_fetch_timeout`
+    #[serde(default)]
+    pub fetch_timeout: Option<Duration>,
+    #[cfg(any(feature = "async", feature = "async-native"))]
+    /// See `Tote::with_fetch_timeout`
+    #[serde(default)]
+    pub stale_on_timeout: bool,
+    /// See `Tote::with_max_depth`
+    #[serde(default)]
+    pub max_depth: Option<usize>,
+    /// See `Tote::with_generation_file`
+    #[serde(default)]
+    pub generation: Option<PathBuf>,
+    /// See `Tote::map_err_with_path`
+    #[serde(default)]
+    pub fetch_err_path: bool,
+    /// See `Tote::with_min_refresh_interval`
+    #[serde(default)]
+    pub min_refresh_interval: Option<Duration>,
+    /// See `Tote::with_grace`
+    #[serde(default)]
+    pub grace: Duration,
+    /// See `Tote::with_tempfile_dir`
+    #[serde(default)]
+    pub tempfile_dir: Option<PathBuf>,
+    /// See `Tote::with_tempfile_affixes`
+    #[serde(default)]
+    pub tempfile_affixes: Option<(String, String)>,
+    /// See `Tote::lock_fetches`
+    #[serde(default)]
+    pub lock_fetches: bool,
+    /// See `Tote::create_dirs`
+    #[serde(default = "default_create_dirs")]
+    pub create_dirs: bool,
+    /// See `Tote::with_embedded_timestamp`
+    #[serde(default)]
+    pub embedded_timestamp: bool,
+    /// See `Tote::with_schema_version`
+    #[serde(default)]
+    pub schema_version: Option<u32>,
+    /// See `Tote::with_checksum`
+    #[serde(default)]
+    pub checksum: bool,
+    /// See `Tote::with_memory_cache`
+    #[serde(default)]
+    pub memory_cache: bool,
+}
+
+fn default_buffer_size() -> usize {
+    DEFAULT_BUFFER_SIZE
+}
+
+#[cfg(unix)]
+fn default_file_mode() -> u32 {
+    0o600
+}
+
+fn default_create_dirs() -> bool {
+    true
+}
+
+fn default_attempts() -> u32 {
+    1
+}
+
+/// Cached data with its fetch time, written by `Tote::with_embedded_timestamp`
+#[derive(Serialize, Deserialize)]
+struct Envelope<D> {
+    /// Seconds since the Unix epoch
+    fetched_at: u64,
+    /// Seconds since the Unix epoch, see `Tote::with_valid_until`
+    #[serde(default)]
+    valid_until: Option<u64>,
+    /// Hash of the serialized data, see `Tote::on_unchanged`
+    #[serde(default)]
+    hash: Option<u64>,
+    data: D,
+}
+
+/// Leading fields of an `Envelope`, to check the hash without
+/// deserializing the data
+#[derive(Deserialize)]
+struct EnvelopeHeader {
+    #[serde(rename = "fetched_at")]
+    _fetched_at: u64,
+    #[serde(default, rename = "valid_until")]
+    _valid_until: Option<u64>,
+    #[serde(default)]
+    hash: Option<u64>,
+}
+
+/// Cached data with its fetch time & schema version, written by
+/// `Tote::with_schema_version`
+#[derive(Serialize, Deserialize)]
+struct VersionedEnvelope<D> {
+    /// Seconds since the Unix epoch
+    fetched_at: u64,
+    version: u32,
+    /// Seconds since the Unix epoch, see `Tote::with_valid_until`
+    #[serde(default)]
+    valid_until: Option<u64>,
+    /// Hash of the serialized data, see `Tote::on_unchanged`
+    #[serde(default)]
+    hash: Option<u64>,
+    data: D,
+}
+
+/// Leading fields of a `VersionedEnvelope`, to check the version
+/// before deserializing the data
+#[derive(Deserialize)]
+struct VersionHeader {
+    fetched_at: u64,
+    version: u32,
+    #[serde(default)]
+    valid_until: Option<u64>,
+    #[serde(default)]
+    hash: Option<u64>,
+}
+
+/// Times recorded inside an enveloped cache file
+#[derive(Debug, Clone, Copy)]
+struct Stamp {
+    fetched_at: SystemTime,
+    valid_until: Option<SystemTime>,
+}
+
+impl Stamp {
+    fn new(fetched_at: u64, valid_until: Option<u64>) -> Self {
+        Self {
+            fetched_at: unix_time(fetched_at),
+            valid_until: valid_until.map(unix_time),
+        }
+    }
+}
+
+/// Contents of a cache file, either read into memory or memory-mapped
+enum Contents {
+    Read(Vec<u8>),
+    #[cfg(feature = "mmap")]
+    Mapped(memmap2::Mmap),
+}
+
+impl Contents {
+    #[cfg_attr(not(feature = "bytes"), allow(dead_code))]
+    fn into_vec(self) -> Vec<u8> {
+        match self {
+            Self::Read(contents) => contents,
+            #[cfg(feature = "mmap")]
+            Self::Mapped(map) => map.to_vec(),
+        }
+    }
+}
+
+impl std::ops::Deref for Contents {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Self::Read(contents) => contents,
+            #[cfg(feature = "mmap")]
+            Self::Mapped(map) => map,
+        }
+    }
+}
+
+/// Default capacity of the buffered writer used by `put`
+const DEFAULT_BUFFER_SIZE: usize = 8 * 1024;
+
+/// Read-after-write check, captured where its extra bounds (`PartialEq`) are known
+type VerifyFn<T, C> = fn(&Tote<T, C>, &T) -> Result<(), ToteError>;
+type ValidFn<T, C> = fn(&Tote<T, C>) -> bool;
+type RoundTripFn<T, C> = fn(&Tote<T, C>, &[u8]) -> Result<(), ToteError>;
+
+/// Computes the expiry age of a cached value from the value itself
+type TtlFn<T> = Arc<dyn Fn(&T) -> Duration + Send + Sync>;
+type ValidUntilFn<T> = Arc<dyn Fn(&T) -> Option<SystemTime> + Send + Sync>;
+type MigrateFn<T> = Arc<dyn Fn(u32, &[u8]) -> Result<T, ToteError> + Send + Sync>;
+type OpenOptionsFn = Arc<dyn Fn(&mut fs::OpenOptions) + Send + Sync>;
+type AdaptiveTtlFn = Arc<dyn Fn(Duration) -> Duration + Send + Sync>;
+/// Data held in memory by `with_memory_cache`, with when it was written
+///
+/// Type-erased so `Tote` stays `Send` for `T` which isn't `Sync`
+type Memo = (Arc<dyn std::any::Any + Send + Sync>, SystemTime);
+/// Shared result of an in-flight `get_shared`, with errors as `SharedError`s
+/// so they can be returned to each waiting caller
+type Flight = Arc<std::sync::OnceLock<Result<Arc<dyn std::any::Any + Send + Sync>, SharedError>>>;
+
+/// A `ToteError` with its inner errors replaced by their messages, so it can
+/// be shared between threads & rebuilt (with the same kind) for each caller
+#[derive(Debug, Clone)]
+enum SharedError {
+    FileAccess(io::ErrorKind, String),
+    Serde(String),
+    Parse(String),
+    InvalidCache,
+    Fetching(Option<PathBuf>, String),
+    InvalidPath(PathBuf, &'static str),
+    InvalidOptions(&'static str),
+    Codec(String),
+    WriteVerificationFailed,
+    Timeout(Duration),
+    UnreadableCache(PathBuf, Box<SharedError>, Box<SharedError>),
+    TooLarge(usize, usize),
+}
+
+impl From<&ToteError> for SharedError {
+    fn from(e: &ToteError) -> Self {
+        match e {
+            ToteError::FileAccess(e) => Self::FileAccess(e.kind(), e.to_string()),
+            ToteError::Serde(e) => Self::Serde(e.to_string()),
+            ToteError::Parse(e) => Self::Parse(e.to_string()),
+            ToteError::InvalidCache => Self::InvalidCache,
+            ToteError::Fetching(e) => Self::Fetching(None, e.to_string()),
+            ToteError::FetchingFor { path, source } => {
+                Self::Fetching(Some(path.clone()), source.to_string())
+            }
+            ToteError::InvalidPath { path, reason } => Self::InvalidPath(path.clone(), reason),
+            ToteError::InvalidOptions(reason) => Self::InvalidOptions(reason),
+            ToteError::Codec(e) => Self::Codec(e.to_string()),
+            ToteError::WriteVerificationFailed => Self::WriteVerificationFailed,
+            ToteError::Timeout(timeout) => Self::Timeout(*timeout),
+            ToteError::UnreadableCache { path, read, source } => Self::UnreadableCache(
+                path.clone(),
+                Box::new(read.as_ref().into()),
+                Box::new(source.as_ref().into()),
+            ),
+            ToteError::TooLarge { size, limit } => Self::TooLarge(*size, *limit),
+        }
+    }
+}
+
+impl From<&SharedError> for ToteError {
+    fn from(e: &SharedError) -> Self {
+        use serde::de::Error;
+        match e {
+            SharedError::FileAccess(kind, message) => {
+                Self::FileAccess(io::Error::new(*kind, message.clone()))
+            }
+            SharedError::Serde(message) => Self::Serde(serde_json::Error::custom(message)),
+            SharedError::Parse(message) => Self::Parse(serde_json::Error::custom(message)),
+            SharedError::InvalidCache => Self::InvalidCache,
+            SharedError::Fetching(None, message) => Self::Fetching(message.clone().into()),
+            SharedError::Fetching(Some(path), message) => Self::FetchingFor {
+                path: path.clone(),
+                source: message.clone().into(),
+            },
+            SharedError::InvalidPath(path, reason) => Self::InvalidPath {
+                path: path.clone(),
+                reason,
+            },
+            SharedError::InvalidOptions(reason) => Self::InvalidOptions(reason),
+            SharedError::Codec(message) => Self::Codec(message.clone().into()),
+            SharedError::WriteVerificationFailed => Self::WriteVerificationFailed,
+            SharedError::Timeout(timeout) => Self::Timeout(*timeout),
+            SharedError::UnreadableCache(path, read, source) => Self::UnreadableCache {
+                path: path.clone(),
+                read: Box::new(read.as_ref().into()),
+                source: Box::new(source.as_ref().into()),
+            },
+            SharedError::TooLarge(size, limit) => Self::TooLarge {
+                size: *size,
+                limit: *limit,
+            },
+        }
+    }
+}
+type TransformFn = Arc<dyn Fn(&mut serde_json::Value) + Send + Sync>;
+
+#[cfg(feature = "async")]
+/// An in-flight background fetch, aborted if dropped before completion
+#[derive(Debug)]
+struct PendingFetch<T>(tokio::task::JoinHandle<Result<T, String>>);
+
+#[cfg(any(feature = "async", feature = "async-native"))]
+type SpawnWriteFn<T, C> = fn(&Tote<T, C>, Vec<u8>) -> tokio::task::JoinHandle<()>;
+
+#[cfg(any(feature = "async", feature = "async-native"))]
+/// Background writes started by `async_writes`, along with the first
+/// failure of a finished write not yet returned by `flush_writes`
+#[derive(Debug, Default)]
+struct PendingWrites {
+    handles: Vec<tokio::task::JoinHandle<()>>,
+    error: Option<io::Error>,
+}
+
+#[cfg(feature = "async")]
+impl<T> Drop for PendingFetch<T> {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Local file cache for data needed across binary instantiations
+///
+/// Given a path & maximum cache age, provides methods
+/// for fetching (unexpired) and writing device info
+///
+/// Cached data is valid up to & including an age of exactly `max_age`,
+/// and expires once it's any older
+pub struct Tote<T, C = JsonCodec> {
+    /// Filepath to write cached data
+    path: PathBuf,
+    /// Cached data older than this age is considered expired
+    max_age: Duration,
+    /// Sort map keys when serializing so equal data is written byte-identically
+    deterministic: bool,
+    /// Write indented JSON, only settable with `JsonCodec`
+    pretty: bool,
+    /// Additional files to read from if newer than `path`
+    candidates: Vec<PathBuf>,
+    /// Secondary location written on each `put` & read if `path` is missing
+    mirror: Option<PathBuf>,
+    /// Capacity of the buffered writer used by `put`
+    buffer_size: usize,
+    /// Read-after-write check run at the end of `put`
+    verify: Option<VerifyFn<T, C>>,
+    /// Deserialization check of serialized data before it's written
+    round_trip: Option<RoundTripFn<T, C>>,
+    #[cfg(feature = "sha256")]
+    /// Write & verify a `<path>.sha256` sidecar file
+    hash_sidecar: bool,
+    #[cfg(feature = "gzip")]
+    /// Gzip-compress the cache file when writing
+    gzip: bool,
+    #[cfg(feature = "gzip")]
+    /// Compress writes over the soft limit & reject them over the hard limit
+    size_budget: Option<SizeBudget>,
+    #[cfg(feature = "encrypt")]
+    /// Encrypts the cache file when writing & decrypts it when reading
+    cipher: Option<Arc<dyn Cipher>>,
+    /// Reset the cache file's modified time on each successful read
+    sliding_expiration: bool,
+    /// Handling of write errors after a successful fetch
+    on_write_error: OnWriteError,
+    /// Handling of writes identical to the existing cache file
+    on_unchanged: OnUnchanged,
+    #[cfg(feature = "async")]
+    /// Fetch started by `with_startup_prefetch`, awaited by `get_async`
+    prefetch: std::sync::Mutex<Option<PendingFetch<T>>>,
+    #[cfg(feature = "async")]
+    /// Background refresh started by `get_stale_while_revalidate`
+    revalidation: std::sync::Mutex<Option<tokio::task::JoinHandle<()>>>,
+    /// Per-value expiry age, overriding `max_age`
+    ttl_from: Option<TtlFn<T>>,
+    /// Per-value absolute expiry time, recorded in the envelope
+    valid_until: Option<ValidUntilFn<T>>,
+    /// Validity check deserializing the cache file, set by `with_ttl_from`
+    /// & `with_valid_until`
+    valid_by_value: Option<ValidFn<T, C>>,
+    /// Directory corrupt cache files are moved to
+    quarantine: Option<PathBuf>,
+    /// Freshness policy, overriding `max_age`
+    expiry_policy: Option<Arc<dyn ExpiryPolicy + Send + Sync>>,
+    /// Number of attempts made for each cache file read
+    read_attempts: u32,
+    /// Number of attempts made for each fetch
+    fetch_attempts: u32,
+    /// Delay before the first fetch retry, doubling for each retry after
+    fetch_backoff: Duration,
+    #[cfg(any(feature = "async", feature = "async-native"))]
+    /// Limit on the time `get_async` spends fetching
+    fetch_timeout: Option<Duration>,
+    #[cfg(any(feature = "async", feature = "async-native"))]
+    /// Return expired cached data when `fetch_timeout` elapses
+    stale_on_timeout: bool,
+    /// Maximum nesting depth of cache files accepted by `read`
+    max_depth: Option<usize>,
+    /// Serialized size estimate used to pre-allocate in `put`
+    size_hint: Option<fn(&T) -> Option<usize>>,
+    /// Customizes the `OpenOptions` used when writing the cache file
+    open_options: Option<OpenOptionsFn>,
+    /// Shared file holding the current cache generation
+    generation: Option<PathBuf>,
+    /// Include `path` in fetch errors
+    fetch_err_path: bool,
+    /// Applied to the serialized `Value` before writing
+    before_write: Option<TransformFn>,
+    /// Applied to the `Value` read from the cache file before deserializing
+    after_read: Option<TransformFn>,
+    /// Minimum time between fetches, serving stale data until it has passed
+    min_refresh_interval: Option<Duration>,
+    /// Time past `max_age` that expired data is still served for
+    grace: Duration,
+    /// Expiry age derived from the last fetch's duration, overriding `max_age`
+    adaptive_ttl: Option<AdaptiveTtlFn>,
+    /// Directory temp files are written to before being renamed over `path`
+    tempfile_dir: Option<PathBuf>,
+    /// Prefix & suffix of temp file names, replacing the `.tmp-` suffix
+    tempfile_affixes: Option<(String, String)>,
+    /// Hold the `<path>.lock` file while fetching & writing data
+    lock_fetches: bool,
+    /// Create missing parent directories of the cache file when writing
+    create_dirs: bool,
+    /// Wrap cached data in an `Envelope` recording when it was fetched
+    embedded_timestamp: bool,
+    /// Schema version written with cached data
+    schema_version: Option<u32>,
+    /// Converts cached data written with another schema version
+    migrate: Option<MigrateFn<T>>,
+    /// Prefix the cache file with a CRC-32 of its contents
+    checksum: bool,
+    /// Keep the last read or fetched data in memory, see `get_cached_arc`
+    memory_cache: bool,
+    memo: std::sync::Mutex<Option<Memo>>,
+    /// In-flight `get_shared` call, joined by concurrent callers
+    flight: std::sync::Mutex<Option<Flight>>,
+    /// Storage used instead of the filesystem
+    backend: Option<Arc<dyn Backend>>,
+    /// Source of the current time, overriding the system clock
+    clock: Option<Arc<dyn Clock + Send + Sync>>,
+    /// Callbacks for cache events, see `on_hit` etc.
+    hooks: hooks::Hooks<T>,
+    #[cfg(unix)]
+    /// Permission bits of newly created cache files
+    file_mode: u32,
+    #[cfg(feature = "mmap")]
+    /// Memory-map the cache file when reading
+    memory_mapped: bool,
+    #[cfg(any(feature = "async", feature = "async-native"))]
+    /// Spawns a background write of fetched data, set by `async_writes`
+    spawn_write: Option<SpawnWriteFn<T, C>>,
+    #[cfg(any(feature = "async", feature = "async-native"))]
+    /// Unfinished background writes & the first write error, taken by `flush_writes`
+    pending_writes: Arc<std::sync::Mutex<PendingWrites>>,
+    /// Format the cached data is (de)serialized with
+    codec: C,
+    _phantom: PhantomData<T>,
+}
+
+// Manual impl, closure fields aren't `Debug`
+impl<T, C> fmt::Debug for Tote<T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tote")
+            .field("path", &self.path)
+            .field("max_age", &self.max_age)
+            .field("deterministic", &self.deterministic)
+            .field("pretty", &self.pretty)
+            .field("candidates", &self.candidates)
+            .field("mirror", &self.mirror)
+            .field("buffer_size", &self.buffer_size)
+            .field("sliding_expiration", &self.sliding_expiration)
+            .field("on_write_error", &self.on_write_error)
+            .field("on_unchanged", &self.on_unchanged)
+            .field("quarantine", &self.quarantine)
+            .field("read_attempts", &self.read_attempts)
+            .field("fetch_attempts", &self.fetch_attempts)
+            .field("fetch_backoff", &self.fetch_backoff)
+            .field("max_depth", &self.max_depth)
+            .field("generation", &self.generation)
+            .field("min_refresh_interval", &self.min_refresh_interval)
+            .field("grace", &self.grace)
+            .field("tempfile_dir", &self.tempfile_dir)
+            .field("tempfile_affixes", &self.tempfile_affixes)
+            .field("lock_fetches", &self.lock_fetches)
+            .field("create_dirs", &self.create_dirs)
+            .field("embedded_timestamp", &self.embedded_timestamp)
+            .field("schema_version", &self.schema_version)
+            .field("checksum", &self.checksum)
+            .field("memory_cache", &self.memory_cache)
+            .finish_non_exhaustive()
+    }
+}
+
+// Manual impl, deriving would require `T: Clone`
+impl<T, C: Clone> Clone for Tote<T, C> {
+    fn clone(&self) -> Self {
+        Self {
+            path: self.path.clone(),
+            max_age: self.max_age,
+            deterministic: self.deterministic,
+            pretty: self.pretty,
+            candidates: self.candidates.clone(),
+            mirror: self.mirror.clone(),
+            buffer_size: self.buffer_size,
+            verify: self.verify,
+            round_trip: self.round_trip,
+            #[cfg(feature = "sha256")]
+            hash_sidecar: self.hash_sidecar,
+            #[cfg(feature = "gzip")]
+            gzip: self.gzip,
+            #[cfg(feature = "gzip")]
+            size_budget: self.size_budget,
+            #[cfg(feature = "encrypt")]
+            cipher: self.cipher.clone(),
+            sliding_expiration: self.sliding_expiration,
+            on_write_error: self.on_write_error,
+            on_unchanged: self.on_unchanged,
+            #[cfg(feature = "async")]
+            // In-flight fetches aren't shared between clones
+            prefetch: Default::default(),
+            #[cfg(feature = "async")]
+            revalidation: Default::default(),
+            ttl_from: self.ttl_from.clone(),
+            valid_until: self.valid_until.clone(),
+            valid_by_value: self.valid_by_value,
+            quarantine: self.quarantine.clone(),
+            expiry_policy: self.expiry_policy.clone(),
+            read_attempts: self.read_attempts,
+            fetch_attempts: self.fetch_attempts,
+            fetch_backoff: self.fetch_backoff,
+            #[cfg(any(feature = "async", feature = "async-native"))]
+            fetch_timeout: self.fetch_timeout,
+            #[cfg(any(feature = "async", feature = "async-native"))]
+            stale_on_timeout: self.stale_on_timeout,
+            max_depth: self.max_depth,
+            size_hint: self.size_hint,
+            open_options: self.open_options.clone(),
+            generation: self.generation.clone(),
+            fetch_err_path: self.fetch_err_path,
+            before_write: self.before_write.clone(),
+            after_read: self.after_read.clone(),
+            min_refresh_interval: self.min_refresh_interval,
+            grace: self.grace,
+            adaptive_ttl: self.adaptive_ttl.clone(),
+            tempfile_dir: self.tempfile_dir.clone(),
+            tempfile_affixes: self.tempfile_affixes.clone(),
+            lock_fetches: self.lock_fetches,
+            create_dirs: self.create_dirs,
+            embedded_timestamp: self.embedded_timestamp,
+            schema_version: self.schema_version,
+            migrate: self.migrate.clone(),
+            checksum: self.checksum,
+            memory_cache: self.memory_cache,
+            // Clones may have another path, so start without in-memory data
+            memo: Default::default(),
+            flight: Default::default(),
+            backend: self.backend.clone(),
+            clock: self.clock.clone(),
+            hooks: self.hooks.clone(),
+            #[cfg(unix)]
+            file_mode: self.file_mode,
+            #[cfg(feature = "mmap")]
+            memory_mapped: self.memory_mapped,
+            #[cfg(any(feature = "async", feature = "async-native"))]
+            spawn_write: self.spawn_write,
+            #[cfg(any(feature = "async", feature = "async-native"))]
+            pending_writes: self.pending_writes.clone(),
+            codec: self.codec.clone(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T> Tote<T> {
+    /// Create a new cache for a given filepath & expiry age
+    ///
+    /// A `max_age` of `Duration::ZERO` is always expired, so `get` always
+    /// fetches (still writing the cache file), see `persistent` for the opposite
+    pub fn new<P: AsRef<Path>>(path: P, max_age: Duration) -> Self {
+        Self::new_with_codec(path, max_age, JsonCodec)
+    }
+
+    /// Create a new cache for a given filepath which never expires, so
+    /// cached data is only re-fetched once invalidated
+    ///
+    /// Equivalent to `Tote::new` with a `max_age` of `Duration::MAX`
+    pub fn persistent<P: AsRef<Path>>(path: P) -> Self {
+        Self::new(path, Duration::MAX)
+    }
+
+    /// Report the status of a cache file at the given path & expiry age,
+    /// without constructing a long-lived `Tote` or fetching
+    ///
+    /// This checks existence & age only, the data is not deserialized
+    pub fn probe<P: AsRef<Path>>(path: P, max_age: Duration) -> CacheStatus {
+        let cache: Self = Self::new(path, max_age);
+        if cache.is_valid() {
+            CacheStatus::Hit
+        } else if fs::metadata(&cache.path).is_ok_and(|metadata| metadata.len() > 0) {
+            CacheStatus::Expired
+        } else {
+            CacheStatus::Miss
+        }
+    }
+
+    /// Create a new cache for a given filepath & expiry age,
+    /// validating the filepath up front
+    ///
+    /// Returns `ToteError::InvalidPath` if the path is empty, is a directory,
+    /// or its parent can't hold (or be created to hold) a writable file.
+    /// An existing cache file is opened for appending (without writing), so
+    /// a read-only file returns `ToteError::FileAccess`
+    pub fn try_new<P: AsRef<Path>>(path: P, max_age: Duration) -> Result<Self, ToteError> {
+        let path = path.as_ref();
+        let invalid = |reason| ToteError::InvalidPath {
+            path: path.to_owned(),
+            reason,
+        };
+        if path.as_os_str().is_empty() {
+            return Err(invalid("path is empty"));
+        }
+        reject_dir(path)?;
+        // The nearest existing ancestor must be a writable directory
+        if let Some(ancestor) = path.ancestors().skip(1).find(|a| a.exists()) {
+            let metadata = fs::metadata(ancestor)?;
+            if !metadata.is_dir() {
+                return Err(invalid("parent is not a directory"));
+            }
+            if metadata.permissions().readonly() {
+                return Err(invalid("parent directory is read-only"));
+            }
+        }
+        if path.exists() {
+            fs::OpenOptions::new().append(true).open(path)?;
+        }
+        Ok(Self::new(path, max_age))
+    }
+
+    /// Create a new cache from a configuration returned by `into_parts`
+    pub fn from_parts(config: ToteConfig) -> Self {
+        Self {
+            deterministic: config.deterministic,
+            pretty: config.pretty,
+            candidates: config.candidates,
+            mirror: config.mirror,
+            buffer_size: config.buffer_size,
+            #[cfg(feature = "sha256")]
+            hash_sidecar: config.hash_sidecar,
+            #[cfg(feature = "gzip")]
+            gzip: config.gzip,
+            #[cfg(feature = "gzip")]
+            size_budget: config.size_budget,
+            #[cfg(feature = "mmap")]
+            memory_mapped: config.memory_mapped,
+            #[cfg(unix)]
+            file_mode: config.file_mode,
+            #[cfg(feature = "encrypt")]
+            cipher: None,
+            sliding_expiration: config.sliding_expiration,
+            on_write_error: config.on_write_error,
+            on_unchanged: config.on_unchanged,
+            quarantine: config.quarantine,
+            read_attempts: config.read_attempts.max(1),
+            fetch_attempts: config.fetch_attempts.max(1),
+            fetch_backoff: config.fetch_backoff,
+            #[cfg(any(feature = "async", feature = "async-native"))]
+            fetch_timeout: config.fetch_timeout,
+            #[cfg(any(feature = "async", feature = "async-native"))]
+            stale_on_timeout: config.stale_on_timeout,
+            max_depth: config.max_depth,
+            generation: config.generation,
+            fetch_err_path: config.fetch_err_path,
+            min_refresh_interval: config.min_refresh_interval,
+            grace: config.grace,
+            tempfile_dir: config.tempfile_dir,
+            tempfile_affixes: config.tempfile_affixes,
+            lock_fetches: config.lock_fetches,
+            create_dirs: config.create_dirs,
+            embedded_timestamp: config.embedded_timestamp,
+            schema_version: config.schema_version,
+            checksum: config.checksum,
+            memory_cache: config.memory_cache,
+            memo: Default::default(),
+            flight: Default::default(),
+            backend: None,
+            clock: None,
+            ..Self::new(config.path, config.max_age)
+        }
+    }
+
+    /// Reject cache files nested deeper than `depth` arrays/objects with
+    /// `ToteError::InvalidCache`, guarding against untrusted cache files
+    ///
+    /// `serde_json` applies its own limit of 128 regardless
+    pub fn with_max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Write the cache file as indented, human-readable JSON
+    ///
+    /// Reading is unaffected, pretty & compact cache files both parse
+    pub fn pretty(mut self, enabled: bool) -> Self {
+        self.pretty = enabled;
+        self
+    }
+
+    /// Lazily iterate the items of a JSON-array-shaped cache (e.g. `Tote<Vec<Item>>`)
+    /// without loading the whole array into memory
+    ///
+    /// Returns Err for I/O issues or if the cache file is expired,
+    /// items that fail to deserialize are yielded as Err
+    pub fn stream_items<Item>(
+        &self,
+    ) -> Result<impl Iterator<Item = Result<Item, ToteError>>, ToteError>
+    where
+        Item: DeserializeOwned,
+    {
+        if self.enveloped() {
+            return Err(ToteError::InvalidOptions(
+                "`stream_items` doesn't support timestamped or versioned cache files",
+            ));
+        }
+        #[cfg(feature = "encrypt")]
+        if self.cipher.is_some() {
+            return Err(ToteError::InvalidOptions(
+                "`stream_items` doesn't support encrypted cache files",
+            ));
+        }
+        if self.checksum {
+            return Err(ToteError::InvalidOptions(
+                "`stream_items` doesn't support checksummed cache files",
+            ));
+        }
+        #[cfg(feature = "sha256")]
+        if self.hash_sidecar {
+            return Err(ToteError::InvalidOptions(
+                "`stream_items` doesn't support hash sidecar files",
+            ));
+        }
+        if self.backend.is_some() {
+            return Err(ToteError::InvalidOptions(
+                "`stream_items` doesn't support backends",
+            ));
+        }
+        let path = self.read_path().ok_or(ToteError::InvalidCache)?;
+        #[cfg_attr(not(feature = "gzip"), allow(unused_mut))]
+        let mut reader: Box<dyn io::Read> = Box::new(io::BufReader::new(fs::File::open(path)?));
+        #[cfg(feature = "gzip")]
+        {
+            let mut buffered = io::BufReader::new(reader);
+            reader = if io::BufRead::fill_buf(&mut buffered)?.starts_with(&GZIP_MAGIC) {
+                Box::new(flate2::bufread::GzDecoder::new(buffered))
+            } else {
+                Box::new(buffered)
+            };
+        }
+        Ok(
+            serde_json::Deserializer::from_reader(stream::ArrayElements::new(reader))
+                .into_iter::<Item>()
+                .map(|item| item.map_err(ToteError::Parse)),
+        )
+    }
+
+    /// Deserialize the cached data with a `DeserializeSeed`, for types which
+    /// need external context (e.g. an interner or registry) to deserialize
+    ///
+    /// This bypasses the normal `get` flow and never fetches, returning Err
+    /// for I/O issues or if the cache file is expired
+    pub fn read_seeded<'de, S>(&self, seed: S) -> Result<S::Value, ToteError>
+    where
+        S: DeserializeSeed<'de>,
+    {
+        if self.enveloped() {
+            return Err(ToteError::InvalidOptions(
+                "`read_seeded` doesn't support timestamped or versioned cache files",
+            ));
+        }
+        let path = self.read_path().ok_or(ToteError::InvalidCache)?;
+        let contents = self.read_bytes_from(path)?;
+        let mut deserializer = serde_json::Deserializer::from_reader(&contents[..]);
+        let data = seed
+            .deserialize(&mut deserializer)
+            .map_err(ToteError::Parse)?;
+        deserializer.end().map_err(ToteError::Parse)?;
+        Ok(data)
+    }
+}
+
+impl<T, C: Codec> Tote<T, C> {
+    /// Create a new cache for a given filepath & expiry age, (de)serializing
+    /// the cached data with the given `Codec`
+    pub fn new_with_codec<P: AsRef<Path>>(path: P, max_age: Duration, codec: C) -> Self {
+        Self {
+            path: path.as_ref().to_owned(),
+            max_age,
+            deterministic: false,
+            pretty: false,
+            candidates: Vec::new(),
+            mirror: None,
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            verify: None,
+            round_trip: None,
+            #[cfg(feature = "sha256")]
+            hash_sidecar: false,
+            #[cfg(feature = "gzip")]
+            gzip: false,
+            #[cfg(feature = "gzip")]
+            size_budget: None,
+            #[cfg(feature = "encrypt")]
+            cipher: None,
+            sliding_expiration: false,
+            on_write_error: OnWriteError::Fail,
+            on_unchanged: OnUnchanged::Write,
+            #[cfg(feature = "async")]
+            prefetch: Default::default(),
+            #[cfg(feature = "async")]
+            revalidation: Default::default(),
+            ttl_from: None,
+            valid_until: None,
+            valid_by_value: None,
+            quarantine: None,
+            expiry_policy: None,
+            read_attempts: 1,
+            fetch_attempts: 1,
+            fetch_backoff: Duration::ZERO,
+            #[cfg(any(feature = "async", feature = "async-native"))]
+            fetch_timeout: None,
+            #[cfg(any(feature = "async", feature = "async-native"))]
+            stale_on_timeout: false,
+            max_depth: None,
+            size_hint: None,
+            open_options: None,
+            generation: None,
+            fetch_err_path: false,
+            before_write: None,
+            after_read: None,
+            min_refresh_interval: None,
+            grace: Duration::ZERO,
+            adaptive_ttl: None,
+            tempfile_dir: None,
+            tempfile_affixes: None,
+            lock_fetches: false,
+            create_dirs: true,
+            embedded_timestamp: false,
+            schema_version: None,
+            migrate: None,
+            checksum: false,
+            memory_cache: false,
+            memo: Default::default(),
+            flight: Default::default(),
+            backend: None,
+            clock: None,
+            hooks: Default::default(),
+            #[cfg(unix)]
+            file_mode: default_file_mode(),
+            #[cfg(feature = "mmap")]
+            memory_mapped: false,
+            #[cfg(any(feature = "async", feature = "async-native"))]
+            spawn_write: None,
+            #[cfg(any(feature = "async", feature = "async-native"))]
+            pending_writes: Default::default(),
+            codec,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Get this cache's serializable configuration
+    ///
+    /// Options which aren't part of `ToteConfig` are dropped
+    pub fn into_parts(self) -> ToteConfig {
+        ToteConfig {
+            path: self.path,
+            max_age: self.max_age,
+            deterministic: self.deterministic,
+            pretty: self.pretty,
+            candidates: self.candidates,
+            mirror: self.mirror,
+            buffer_size: self.buffer_size,
+            #[cfg(feature = "sha256")]
+            hash_sidecar: self.hash_sidecar,
+            #[cfg(feature = "gzip")]
+            gzip: self.gzip,
+            #[cfg(feature = "gzip")]
+            size_budget: self.size_budget,
+            #[cfg(feature = "mmap")]
+            memory_mapped: self.memory_mapped,
+            #[cfg(unix)]
+            file_mode: self.file_mode,
+            sliding_expiration: self.sliding_expiration,
+            on_write_error: self.on_write_error,
+            on_unchanged: self.on_unchanged,
+            quarantine: self.quarantine,
+            read_attempts: self.read_attempts,
+            fetch_attempts: self.fetch_attempts,
+            fetch_backoff: self.fetch_backoff,
+            #[cfg(any(feature = "async", feature = "async-native"))]
+            fetch_timeout: self.fetch_timeout,
+            #[cfg(any(feature = "async", feature = "async-native"))]
+            stale_on_timeout: self.stale_on_timeout,
+            max_depth: self.max_depth,
+            generation: self.generation,
+            fetch_err_path: self.fetch_err_path,
+            min_refresh_interval: self.min_refresh_interval,
+            grace: self.grace,
+            tempfile_dir: self.tempfile_dir,
+            tempfile_affixes: self.tempfile_affixes,
+            lock_fetches: self.lock_fetches,
+            create_dirs: self.create_dirs,
+            embedded_timestamp: self.embedded_timestamp,
+            schema_version: self.schema_version,
+            checksum: self.checksum,
+            memory_cache: self.memory_cache,
+        }
+    }
+
+    /// Set the write buffer capacity (in bytes) used when writing the cache file
+    ///
+    /// Defaults to 8 KiB, larger buffers reduce syscalls for multi-MB caches
+    pub fn with_buffer_size(mut self, size: usize) -> Self {
+        self.buffer_size = size;
+        self
+    }
+
+    /// Also write the cache to a secondary (mirror) location
+    ///
+    /// Writes to the mirror are best-effort and never fail a `put` (failures
+    /// are reported as `tracing` warnings with the `tracing` feature).
+    /// The mirror is read from when no valid primary cache file exists
+    pub fn with_mirror(mut self, mirror: PathBuf) -> Self {
+        self.mirror = Some(mirror);
+        self
+    }
+
+    /// Read from the most recently modified valid file among this cache's
+    /// path and the given candidates, while still writing to this cache's path
+    ///
+    /// Useful when multiple processes (or versions) may write caches under
+    /// slightly different names
+    pub fn newest_of(mut self, candidates: Vec<PathBuf>) -> Self {
+        self.candidates = candidates;
+        self
+    }
+
+    /// Serialize with a stable (sorted) map key order so the same data
+    /// always produces byte-identical cache files
+    ///
+    /// This only affects map-like data (e.g. `HashMap` fields), struct
+    /// fields are always written in declaration order. Enabling this adds
+    /// an intermediate conversion to `serde_json::Value` on each write
+    pub fn deterministic(mut self, enabled: bool) -> Self {
+        self.deterministic = enabled;
+        self
+    }
+
+    #[cfg(feature = "sha256")]
+    /// Write a SHA-256 digest of the cache file to a `<path>.sha256` sidecar
+    /// on each write, and verify it on each read
+    ///
+    /// The sidecar uses the `sha256sum` format so external tooling can verify
+    /// the cache without parsing it. A missing or mismatched sidecar is
+    /// treated as an invalid cache. `stream_items` returns
+    /// `ToteError::InvalidOptions`
+    pub fn with_hash_sidecar(mut self, enabled: bool) -> Self {
+        self.hash_sidecar = enabled;
+        self
+    }
+
+    #[cfg(feature = "gzip")]
+    /// Gzip-compress the cache file when writing, independent of the `Codec`
+    ///
+    /// Compressed cache files are detected (by their gzip header) and
+    /// decompressed on read whether or not this is enabled, so existing
+    /// uncompressed caches remain readable
+    pub fn with_gzip(mut self, enabled: bool) -> Self {
+        self.gzip = enabled;
+        self
+    }
+
+    #[cfg(feature = "gzip")]
+    /// Gzip-compress writes whose serialized data is over `budget.soft`
+    /// bytes, and fail writes still over `budget.hard` bytes (once
+    /// compressed) with `ToteError::TooLarge`, leaving the cache file as is
+    ///
+    /// Smaller writes aren't compressed unless `with_gzip` is enabled
+    pub fn with_size_budget(mut self, budget: SizeBudget) -> Self {
+        self.size_budget = Some(budget);
+        self
+    }
+
+    #[cfg(feature = "encrypt")]
+    /// Encrypt the cache file with `cipher` when writing (after any
+    /// compression) and decrypt it when reading
+    ///
+    /// Cache files which fail to decrypt (e.g. written unencrypted or with
+    /// another key) are treated as invalid, so the data is re-fetched
+    pub fn with_cipher<Ci: Cipher>(mut self, cipher: Ci) -> Self {
+        self.cipher = Some(Arc::new(cipher));
+        self
+    }
+
+    /// Reset the expiry window on each successful read (sliding expiration),
+    /// so frequently read caches stay valid and unread caches expire
+    ///
+    /// This costs an extra metadata write on every cache hit. Already
+    /// expired caches are not revived by a read
+    pub fn sliding_expiration(mut self, enabled: bool) -> Self {
+        self.sliding_expiration = enabled;
+        self
+    }
+
+    /// Create missing parent directories of the cache file when writing
+    /// (enabled by default)
+    pub fn create_dirs(mut self, enabled: bool) -> Self {
+        self.create_dirs = enabled;
+        self
+    }
+
+    /// Record when data was fetched inside the cache file, as
+    /// `{ "fetched_at": <unix timestamp>, "data": <data> }`, and compute
+    /// expiry from that rather than the file's modified time
+    ///
+    /// This keeps expiry correct when cache files are copied or restored
+    /// with reset timestamps. Legacy cache files written without this
+    /// enabled are still read, expiring by their modified time, & are
+    /// rewritten with a timestamp on the next write (files written with this
+    /// enabled fail to parse without it, and are re-fetched).
+    /// `sliding_expiration` has no effect, and `stream_items` &
+    /// `read_seeded` return `ToteError::InvalidOptions`
+    pub fn with_embedded_timestamp(mut self, enabled: bool) -> Self {
+        self.embedded_timestamp = enabled;
+        self
+    }
+
+    /// Record a schema version inside the cache file, as
+    /// `{ "fetched_at": <unix timestamp>, "version": <version>, "data": <data> }`
+    ///
+    /// Like `with_embedded_timestamp`, expiry is computed from `fetched_at`.
+    /// Cache files with another version are converted by the
+    /// `with_migration` hook if set, otherwise re-fetched
+    pub fn with_schema_version(mut self, version: u32) -> Self {
+        self.schema_version = Some(version);
+        self
+    }
+
+    /// Convert cache files written with another `with_schema_version`
+    /// rather than re-fetching
+    ///
+    /// `migrate` is given the old version & the cache file contents
+    /// (the whole versioned envelope, serialized with the `Codec`)
+    pub fn with_migration<F>(mut self, migrate: F) -> Self
+    where
+        F: Fn(u32, &[u8]) -> Result<T, ToteError> + Send + Sync + 'static,
+    {
+        self.migrate = Some(Arc::new(migrate));
+        self
+    }
+
+    /// Prefix the cache file with a `crc32:<hex>` header line holding a
+    /// CRC-32 of the serialized data, verified on read
+    ///
+    /// Corrupt (e.g. truncated or bit-flipped) cache files fail with
+    /// `ToteError::InvalidCache` rather than parsing into wrong data, so
+    /// they're re-fetched. Cache files written without this enabled are
+    /// also re-fetched. `stream_items` returns `ToteError::InvalidOptions`
+    pub fn with_checksum(mut self, enabled: bool) -> Self {
+        self.checksum = enabled;
+        self
+    }
+
+    /// Keep the data last read or fetched by `get_cached_arc` in memory,
+    /// so repeated calls within `max_age` don't re-read & parse the cache file
+    ///
+    /// The in-memory copy is dropped by writes, `invalidate`, `expire_now`
+    /// & `force_refresh`. Writes to the cache file by other processes aren't
+    /// seen until the in-memory copy expires, or `reload` is called
+    pub fn with_memory_cache(mut self, enabled: bool) -> Self {
+        self.memory_cache = enabled;
+        self.forget();
+        self
+    }
+
+    /// Hold an advisory lock on a `<path>.lock` file while fetching & writing
+    /// data, so concurrent `get`s (across threads or processes) of a missing
+    /// or expired cache wait & read the written data rather than each fetching
+    ///
+    /// Applies to the synchronous `get`, `get_with_meta`, `get_with` and
+    /// `get_or_fetch`
+    pub fn lock_fetches(mut self, enabled: bool) -> Self {
+        self.lock_fetches = enabled;
+        self
+    }
+
+    /// Compute the expiry age of cached data from the data itself
+    /// (e.g. a `valid_for_seconds` field), overriding `max_age`
+    ///
+    /// As the age is a pure function of the data, it's computed from the
+    /// deserialized value on each read. This means the cache file must be
+    /// parsed before its expiry is known, including by `is_valid`, which
+    /// then costs a full read rather than a metadata lookup
+    pub fn with_ttl_from<F>(mut self, ttl_from: F) -> Self
+    where
+        T: DeserializeOwned,
+        F: Fn(&T) -> Duration + Send + Sync + 'static,
+    {
+        self.set_ttl_from(Arc::new(ttl_from));
+        self
+    }
+
+    /// Set `ttl_from`, making `is_valid` deserialize the cache file
+    fn set_ttl_from(&mut self, ttl_from: TtlFn<T>)
+    where
+        T: DeserializeOwned,
+    {
+        self.ttl_from = Some(ttl_from);
+        self.check_by_value();
+    }
+
+    /// Record an absolute expiry time computed from the data (e.g. the start
+    /// of a scheduled event) when writing, after which the cached data is
+    /// expired regardless of `max_age`
+    ///
+    /// The time is stored in the envelope as `valid_until` (Unix seconds),
+    /// so this enables `with_embedded_timestamp` unless `with_schema_version`
+    /// is set. Data with no deadline (`None`) expires by `max_age` only.
+    /// Like `with_ttl_from`, `is_valid` deserializes the cache file
+    pub fn with_valid_until<F>(mut self, valid_until: F) -> Self
+    where
+        T: DeserializeOwned,
+        F: Fn(&T) -> Option<SystemTime> + Send + Sync + 'static,
+    {
+        self.valid_until = Some(Arc::new(valid_until));
+        if self.schema_version.is_none() {
+            self.embedded_timestamp = true;
+        }
+        self.check_by_value();
+        self
+    }
+
+    /// Make `is_valid` deserialize the cache file to check its expiry
+    fn check_by_value(&mut self)
+    where
+        T: DeserializeOwned,
+    {
+        self.valid_by_value = Some(|tote| {
+            tote.valid_modified(&tote.path).is_some()
+                && tote
+                    .read_timestamped(&tote.path)
+                    .is_ok_and(|(data, stamp)| tote.is_fresh(&tote.path, &data, stamp))
+        });
+    }
+
+    /// Deserialize data after serializing it & before writing, returning
+    /// `ToteError::Serde` if it can't be read back as `T`
+    ///
+    /// This catches data which serializes but can't be read back, e.g.
+    /// non-finite floats written as `null` by `serde_json`, at the cost of
+    /// an extra deserialization on every write
+    pub fn strict_serialization(mut self, enabled: bool) -> Self
+    where
+        T: DeserializeOwned,
+    {
+        self.round_trip = if enabled {
+            Some(|tote, data| match tote.decode::<T>(data) {
+                Ok(_) => Ok(()),
+                Err(ToteError::Parse(e)) => Err(ToteError::Serde(e)),
+                Err(e) => Err(e),
+            })
+        } else {
+            None
+        };
+        self
+    }
+
+    /// Use a custom `ExpiryPolicy` to decide whether the cache is fresh,
+    /// overriding `max_age`
+    pub fn with_expiry_policy<P>(mut self, policy: P) -> Self
+    where
+        P: ExpiryPolicy + Send + Sync + 'static,
+    {
+        self.expiry_policy = Some(Arc::new(policy));
+        self
+    }
+
+    /// Retry failed cache file reads (e.g. stale NFS handles) up to
+    /// `attempts` times in total, with a short backoff between each
+    ///
+    /// Only the file read is retried, a missing file or data that fails
+    /// to deserialize is not
+    pub fn with_read_retry(mut self, attempts: u32) -> Self {
+        self.read_attempts = attempts.max(1);
+        self
+    }
+
+    /// Retry failed fetches in `get` (and `get_async`) up to `attempts` times
+    /// in total, waiting `base_delay` before the first retry and doubling
+    /// the delay for each retry after
+    ///
+    /// If every attempt fails, the last error is returned. Defaults to a
+    /// single attempt
+    pub fn with_fetch_retry(mut self, attempts: u32, base_delay: Duration) -> Self {
+        self.fetch_attempts = attempts.max(1);
+        self.fetch_backoff = base_delay;
+        self
+    }
+
+    #[cfg(any(feature = "async", feature = "async-native"))]
+    /// Give up fetching in `get_async` (or `get_async_native`) after `timeout` (including any
+    /// `with_fetch_retry` retries), returning `ToteError::Timeout`
+    ///
+    /// With `stale_fallback`, expired cached data is returned instead of
+    /// the error if it's readable. There's no timeout by default
+    pub fn with_fetch_timeout(mut self, timeout: Duration, stale_fallback: bool) -> Self {
+        self.fetch_timeout = Some(timeout);
+        self.stale_on_timeout = stale_fallback;
+        self
+    }
+
+    /// Customize the `OpenOptions` used when writing the cache file, e.g. to
+    /// set platform-specific flags via `OpenOptionsExt`
+    ///
+    /// The options required for writing are applied after `f`, but other
+    /// flags (e.g. `append` or `O_DIRECT`) can still break writes
+    pub fn with_open_options<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&mut fs::OpenOptions) + Send + Sync + 'static,
+    {
+        self.open_options = Some(Arc::new(f));
+        self
+    }
+
+    /// Share a generation counter file with other `Tote`s (possibly in other
+    /// processes), expiring cached data written under an older generation
+    ///
+    /// Each cache file's generation is stored in a `<path>.gen` sidecar,
+    /// see `bump_generation`
+    pub fn with_generation_file<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.generation = Some(path.as_ref().to_owned());
+        self
+    }
+
+    /// Increment the shared generation counter, expiring the cached data of
+    /// every `Tote` sharing the generation file, returning the new generation
+    pub fn bump_generation(&self) -> Result<u64, ToteError> {
+        let path = self.generation.as_deref().ok_or(ToteError::InvalidOptions(
+            "`bump_generation` requires `with_generation_file`",
+        ))?;
+        let _lock = lock_file(&sidecar_path(path, "lock"))?;
+        let generation = read_generation(path) + 1;
+        fs::write(path, generation.to_string())?;
+        Ok(generation)
+    }
+
+    /// Return fetch errors as `ToteError::FetchingFor`, which includes the
+    /// cache filepath, rather than `ToteError::Fetching`
+    ///
+    /// Useful to tell which cache failed in apps with many `Tote`s
+    pub fn map_err_with_path(mut self, enabled: bool) -> Self {
+        self.fetch_err_path = enabled;
+        self
+    }
+
+    /// Transform the serde `Value` of the data at the cache boundary, e.g. to
+    /// strip volatile fields (timestamps, request IDs) before writing or
+    /// inject computed fields after reading
+    ///
+    /// `before_write` is applied to the serialized data before it's written,
+    /// `after_read` to the data read from the cache file before deserializing
+    pub fn with_serde_transform<W, R>(mut self, before_write: W, after_read: R) -> Self
+    where
+        W: Fn(&mut serde_json::Value) + Send + Sync + 'static,
+        R: Fn(&mut serde_json::Value) + Send + Sync + 'static,
+    {
+        self.before_write = Some(Arc::new(before_write));
+        self.after_read = Some(Arc::new(after_read));
+        self
+    }
+
+    /// Rate-limit fetches, returning the last written data (even if expired)
+    /// rather than fetching if the cache file was written within `interval`
+    ///
+    /// The write time is the embedded fetch time if enveloped. With
+    /// `sliding_expiration` (which moves the modified time on reads) it's
+    /// recorded in a `<path>.written` sidecar file
+    ///
+    /// Useful with short expiry ages to avoid overwhelming the data source
+    pub fn with_min_refresh_interval(mut self, interval: Duration) -> Self {
+        self.min_refresh_interval = Some(interval);
+        self
+    }
+
+    /// Keep serving cached data for up to `grace` past its expiry age
+    /// without fetching, see `freshness`
+    ///
+    /// Data within the grace window is reported as `Freshness::Stale`, and
+    /// is refreshed in the background by `get_stale_while_revalidate`.
+    /// Doesn't apply to `with_expiry_policy`
+    pub fn with_grace(mut self, grace: Duration) -> Self {
+        self.grace = grace;
+        self
+    }
+
+    #[cfg(feature = "mmap")]
+    /// Memory-map the cache file when reading rather than reading it into
+    /// memory, for large caches which are read often
+    ///
+    /// The data is deserialized directly from the mapping. If the cache file
+    /// is truncated (e.g. rewritten by another process) while mapped, reading
+    /// it can crash the process with `SIGBUS`, so only use this for caches
+    /// which aren't written concurrently with reads
+    pub fn memory_mapped(mut self, enabled: bool) -> Self {
+        self.memory_mapped = enabled;
+        self
+    }
+
+    #[cfg(unix)]
+    /// Permission bits of newly created cache files (before the umask),
+    /// `0o600` by default so cached data is only readable by its owner
+    pub fn with_file_mode(mut self, mode: u32) -> Self {
+        self.file_mode = mode;
+        self
+    }
+
+    /// Derive the expiry age from how long the last fetch took (e.g. caching
+    /// data from slow sources for longer), overriding `max_age`
+    ///
+    /// Fetch durations are recorded in a `<path>.fetch` sidecar file, see
+    /// `last_fetch_duration`. Until a fetch has been recorded, `ttl` is
+    /// given a zero duration
+    pub fn adaptive_ttl<F>(mut self, ttl: F) -> Self
+    where
+        F: Fn(Duration) -> Duration + Send + Sync + 'static,
+    {
+        self.adaptive_ttl = Some(Arc::new(ttl));
+        self
+    }
+
+    /// Duration of the last fetch, if recorded (see `adaptive_ttl`)
+    pub fn last_fetch_duration(&self) -> Option<Duration> {
+        read_fetch_duration(&self.path)
+    }
+
+    /// Write temp files (which are renamed over the cache file for atomic
+    /// writes) to the given directory, rather than next to the cache file
+    ///
+    /// The directory should be on the same filesystem as the cache file,
+    /// otherwise writes fall back to a non-atomic copy (reported as a
+    /// `cache.write_not_atomic` `tracing` warning), so readers may see a
+    /// partially written cache file
+    pub fn with_tempfile_dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.tempfile_dir = Some(dir.as_ref().to_owned());
+        self
+    }
+
+    /// Name temp files `<prefix><file name>-<pid>-<n><suffix>`, rather
+    /// than `<file name>.tmp-<pid>-<n>`, e.g. so tools scanning the cache
+    /// directory can skip them
+    ///
+    /// Temp files are still written next to the cache file (unless
+    /// `with_tempfile_dir` is set), so they're renamed within one filesystem
+    pub fn with_tempfile_affixes(mut self, prefix: &str, suffix: &str) -> Self {
+        self.tempfile_affixes = Some((prefix.to_owned(), suffix.to_owned()));
+        self
+    }
+
+    /// Use the given `Clock` as the current time when checking expiry,
+    /// rather than the system clock
+    ///
+    /// Cache files' modified times are still set by the filesystem on write
+    pub fn with_clock<K>(mut self, clock: K) -> Self
+    where
+        K: Clock + Send + Sync + 'static,
+    {
+        self.clock = Some(Arc::new(clock));
+        self
+    }
+
+    /// Move cache files which fail to deserialize into the given directory
+    /// (with a unique, timestamped name) before re-fetching, preserving them
+    /// for later analysis
+    ///
+    /// Quarantining is best-effort and never prevents the re-fetch
+    pub fn quarantine_to<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.quarantine = Some(dir.as_ref().to_owned());
+        self
+    }
+
+    /// Set how failing to write freshly fetched data is handled
+    ///
+    /// `OnWriteError::ReturnData` is useful when caching is a nicety rather
+    /// than a necessity (e.g. a read-only filesystem)
+    pub fn on_write_error(mut self, policy: OnWriteError) -> Self {
+        self.on_write_error = policy;
+        self
+    }
+
+    /// Set how writing data which serializes identically to the existing
+    /// cache file is handled
+    ///
+    /// A hash of the serialized data is compared (rather than requiring
+    /// `T: PartialEq`), before any envelope, checksum, compression or
+    /// encryption, so the comparison works with all of them. Enveloped data
+    /// stores its hash in the envelope, so is always rewritten once after
+    /// enabling. Skipping the write avoids needless disk I/O & file churn
+    ///
+    /// `OnUnchanged::RefreshExpiry` rewrites enveloped data (to update its
+    /// fetch time) & data stored with a `Backend`
+    pub fn on_unchanged(mut self, policy: OnUnchanged) -> Self {
+        self.on_unchanged = policy;
+        self
+    }
+
+    /// Create a new cache sharing all of this cache's configuration
+    /// but writing to a different filepath
+    pub fn clone_with_path<P: AsRef<Path>>(&self, path: P) -> Self {
+        Self {
+            path: path.as_ref().to_owned(),
+            ..self.clone()
+        }
+    }
+
+    /// Filepath the cached data is written to
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Cached data older than this age is considered expired
+    pub fn max_age(&self) -> Duration {
+        self.max_age
+    }
+
+    /// Change the filepath cached data is read from & written to
+    ///
+    /// Sidecar files (`.lock`, `.sha256`, etc.) follow the new path, as they
+    /// aren't tracked in memory. The old cache file is left in place, and
+    /// background writes already started by `async_writes` still complete
+    /// to the old path
+    pub fn set_path<P: AsRef<Path>>(&mut self, path: P) {
+        self.path = path.as_ref().to_owned();
+    }
+
+    /// Change the age after which cached data is considered expired,
+    /// taking effect on the next read
+    pub fn set_max_age(&mut self, max_age: Duration) {
+        self.max_age = max_age;
+    }
+
+    /// Create a new cache sharing all of this cache's configuration, with
+    /// the (sanitized) context incorporated into its filepaths
+    ///
+    /// E.g. `colors.cache` segmented by `"fr"` becomes `colors.fr.cache`
+    pub fn segmented_by(&self, context: impl Display) -> Self {
+        let context: String = context
+            .to_string()
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        let segment = |path: &Path| {
+            let mut name = path.file_stem().unwrap_or_default().to_owned();
+            name.push(".");
+            name.push(&context);
+            if let Some(extension) = path.extension() {
+                name.push(".");
+                name.push(extension);
+            }
+            path.with_file_name(name)
+        };
+        Self {
+            path: segment(&self.path),
+            candidates: self.candidates.iter().map(|path| segment(path)).collect(),
+            mirror: self.mirror.as_deref().map(segment),
+            ..self.clone()
+        }
+    }
+
+    /// Fetch the cached data, returning Err for I/O issues
+    /// or if the cache file is expired
+    ///
+    /// Requires `T: Fetch`, see `get_async` for `AsyncFetch` types
+    pub fn get(&self) -> Result<T, ToteError>
+    where
+        T: DeserializeOwned,
+        T: Serialize + Fetch<Cached = T>,
+    {
+        Ok(self.get_sourced()?.0)
+    }
+
+    /// Fetch the cached data like `get`, treating cached data older than
+    /// `max_age` as expired instead of the configured `max_age`
+    ///
+    /// Other expiry options (e.g. `with_grace` or `with_ttl_from`) still apply
+    pub fn get_within(&self, max_age: Duration) -> Result<T, ToteError>
+    where
+        T: DeserializeOwned,
+        T: Serialize + Fetch<Cached = T>,
+    {
+        Self {
+            max_age,
+            ..self.clone()
+        }
+        .get()
+    }
+
+    /// Fetch the cached data like `get`, returning it transformed by `f`
+    /// (e.g. projected to just the fields a caller needs)
+    pub fn get_mapped<U, F>(&self, f: F) -> Result<U, ToteError>
+    where
+        T: DeserializeOwned,
+        T: Serialize + Fetch<Cached = T>,
+        F: FnOnce(T) -> U,
+    {
+        self.get().map(f)
+    }
+
+    /// Fetch the cached data like `get`, along with whether it was freshly
+    /// fetched (`true`) or read from the cache file (`false`)
+    pub fn get_fresh(&self) -> Result<(T, bool), ToteError>
+    where
+        T: DeserializeOwned,
+        T: Serialize + Fetch<Cached = T>,
+    {
+        let (data, source) = self.get_sourced()?;
+        Ok((data, source == Source::Fetched))
+    }
+
+    /// Fetch the cached data like `get`, along with where it came from
+    /// and how old it is
+    pub fn get_with_meta(&self) -> Result<(T, CacheMeta), ToteError>
+    where
+        T: DeserializeOwned,
+        T: Serialize + Fetch<Cached = T>,
+    {
+        let (data, source) = self.get_sourced()?;
+        let age = match source {
+            Source::Cache => self.age_of(&self.path),
+            Source::Fetched => Some(Duration::ZERO),
+        };
+        Ok((data, CacheMeta { source, age }))
+    }
+
+    /// Fetch the cached data like `get` as a shared `Arc`, returning the
+    /// in-memory copy when enabled with `with_memory_cache`
+    ///
+    /// Without `with_memory_cache`, the cache file is read on every call
+    pub fn get_cached_arc(&self) -> Result<Arc<T>, ToteError>
+    where
+        T: DeserializeOwned,
+        T: Serialize + Fetch<Cached = T> + Send + Sync + 'static,
+    {
+        if !self.memory_cache {
+            return Ok(Arc::new(self.get()?));
+        }
+        if let Some(data) = self.remembered() {
+            self.hit();
+            return Ok(data);
+        }
+        let (data, source) = self.get_sourced()?;
+        let written = match source {
+            Source::Cache => self.last_modified()?.unwrap_or_else(|| self.now()),
+            Source::Fetched => self.now(),
+        };
+        let data = Arc::new(data);
+        self.remember(data.clone(), written);
+        Ok(data)
+    }
+
+    /// In-memory data from `get_cached_arc`, if it hasn't expired
+    fn remembered(&self) -> Option<Arc<T>>
+    where
+        T: Send + Sync + 'static,
+    {
+        let memo = self.memo.lock().unwrap_or_else(|e| e.into_inner());
+        let (data, written) = memo.as_ref()?;
+        let age = Some(age_at(*written, self.now()));
+        if !within_max_age(age, self.graced(self.max_age)) {
+            return None;
+        }
+        data.clone().downcast().ok()
+    }
+
+    /// Fetch the cached data like `get_cached_arc`, coalescing concurrent
+    /// calls (e.g. from many threads on a cold cache) into a single read
+    /// or fetch whose result is shared by all callers
+    ///
+    /// Callers joining a failed fetch receive an error of the same kind,
+    /// with any inner errors replaced by their messages. Fetches aren't shared between clones or
+    /// processes, see `lock_fetches`
+    pub fn get_shared(&self) -> Result<Arc<T>, ToteError>
+    where
+        T: DeserializeOwned,
+        T: Serialize + Fetch<Cached = T> + Send + Sync + 'static,
+    {
+        let flight = self
+            .flight
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get_or_insert_with(Default::default)
+            .clone();
+        let mut own_error = None;
+        let res = flight.get_or_init(|| match self.get_cached_arc() {
+            Ok(data) => Ok(data as Arc<dyn std::any::Any + Send + Sync>),
+            Err(e) => {
+                let shared = SharedError::from(&e);
+                own_error = Some(e);
+                Err(shared)
+            }
+        });
+        // Later callers start a new flight, rather than reusing this result
+        let mut current = self.flight.lock().unwrap_or_else(|e| e.into_inner());
+        if current.as_ref().is_some_and(|f| Arc::ptr_eq(f, &flight)) {
+            *current = None;
+        }
+        drop(current);
+        if let Some(e) = own_error {
+            return Err(e);
+        }
+        match res {
+            Ok(data) => data.clone().downcast().map_err(|_| ToteError::InvalidCache),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Keep `data` in memory for `get_cached_arc`, written at `written`
+    fn remember(&self, data: Arc<T>, written: SystemTime)
+    where
+        T: Send + Sync + 'static,
+    {
+        *self.memo.lock().unwrap_or_else(|e| e.into_inner()) = Some((data, written));
+    }
+
+    /// Drop any in-memory data from `get_cached_arc`
+    fn forget(&self) {
+        *self.memo.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    /// Read valid cached data, or fetch & write it, returning which
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "tote.get", level = "debug", skip_all, fields(path = %self.path.display()))
+    )]
+    fn get_sourced(&self) -> Result<(T, Source), ToteError>
+    where
+        T: DeserializeOwned,
+        T: Serialize + Fetch<Cached = T>,
+    {
+        let read_error = match self.read_reported() {
+            Ok(data) => return Ok((data, Source::Cache)),
+            Err(e) => e,
+        };
+        // Fall-back to fetching data and updating cache file
+        self.fetch_and_put(|| self.fetch_retrying().map_err(|e| self.fetch_error(e)))
+            .map_err(|e| self.unreadable_cache_error(read_error, e))
+    }
+
+    /// Read valid cached data (or data kept by `with_min_refresh_interval`),
+    /// reporting it to `on_hit`, or report a miss to `on_miss` & return the
+    /// read error
+    fn read_reported(&self) -> Result<T, ToteError>
+    where
+        T: DeserializeOwned,
+    {
+        let read_error = match self.read() {
+            Ok(data) => {
+                self.hit();
+                return Ok(data);
+            }
+            Err(e) => e,
+        };
+        if let Some(data) = self.read_rate_limited() {
+            self.hit();
+            return Ok(data);
+        }
+        self.miss();
+        Err(read_error)
+    }
+
+    #[cfg(any(feature = "async", feature = "async-native"))]
+    /// Async version of `read_reported`
+    async fn read_reported_async(&self) -> Result<T, ToteError>
+    where
+        T: DeserializeOwned,
+    {
+        let read_error = match self.read_async().await {
+            Ok(data) => {
+                self.hit();
+                return Ok(data);
+            }
+            Err(e) => e,
+        };
+        if let Some(data) = self.read_rate_limited() {
+            self.hit();
+            return Ok(data);
+        }
+        self.miss();
+        Err(read_error)
+    }
+
+    /// Fetch the cached data according to the given `GetOptions`,
+    /// returning the data and where it came from
+    pub fn get_opts(&self, opts: GetOptions) -> Result<(T, Source), ToteError>
+    where
+        T: DeserializeOwned,
+        T: Serialize + Fetch<Cached = T>,
+    {
+        opts.validate()?;
+        if opts.force_refresh {
+            self.forget();
+        } else {
+            match self.read() {
+                Ok(data) => {
+                    self.hit();
+                    return Ok((data, Source::Cache));
+                }
+                Err(e) if opts.offline => {
+                    self.miss();
+                    return Err(e);
+                }
+                Err(_) => {}
+            }
+            if let Some(data) = self.read_rate_limited() {
+                self.hit();
+                return Ok((data, Source::Cache));
+            }
+            self.miss();
+        }
+        self.hooks.fetch_start();
+        let res = self
+            .timed(|| self.fetch_retrying())
+            .map_err(|e| self.fetch_error(e));
+        self.hooks.fetched(res.as_ref());
+        let data = res?;
+        if opts.write {
+            self.put_fetched(&data)?;
+        }
+        Ok((data, Source::Fetched))
+    }
+
+    /// Fetch fresh data & write it to the cache file, regardless of any
+    /// valid cached data
+    pub fn force_refresh(&self) -> Result<T, ToteError>
+    where
+        T: DeserializeOwned,
+        T: Serialize + Fetch<Cached = T>,
+    {
+        let opts = GetOptions {
+            force_refresh: true,
+            ..Default::default()
+        };
+        Ok(self.get_opts(opts)?.0)
+    }
+
+    /// Fetch the cached data like `get`, but without writing fetched data
+    /// to the cache file for just this call
+    pub fn get_no_write(&self) -> Result<T, ToteError>
+    where
+        T: DeserializeOwned,
+        T: Serialize + Fetch<Cached = T>,
+    {
+        let opts = GetOptions {
+            write: false,
+            ..Default::default()
+        };
+        Ok(self.get_opts(opts)?.0)
+    }
+
+    /// Fetch the cached data like `get`, passing `args` to `T::fetch_with`
+    /// when the cache file is missing or expired
+    pub fn get_with<Args>(&self, args: Args) -> Result<T, ToteError>
+    where
+        T: DeserializeOwned,
+        T: Serialize + FetchWith<Args, Cached = T>,
+    {
+        if let Ok(data) = self.read_reported() {
+            return Ok(data);
+        }
+        let fetch = || T::fetch_with(args).map_err(|e| self.fetch_error(e));
+        Ok(self.fetch_and_put(fetch)?.0)
+    }
+
+    /// Fetch the cached data like `get`, along with any warnings from
+    /// `T::fetch_with_warnings`
+    ///
+    /// Fetched data is cached even with warnings. Warnings aren't cached,
+    /// so data read from the cache file has none
+    pub fn get_with_warnings(&self) -> Result<(T, Vec<T::Warning>), ToteError>
+    where
+        T: DeserializeOwned,
+        T: Serialize + FetchWithWarnings<Cached = T>,
+    {
+        if let Ok(data) = self.read_reported() {
+            return Ok((data, Vec::new()));
+        }
+        let mut warnings = Vec::new();
+        let (data, _) = self.fetch_and_put(|| {
+            let (data, fetch_warnings) =
+                T::fetch_with_warnings().map_err(|e| self.fetch_error(e))?;
+            warnings = fetch_warnings;
+            Ok(data)
+        })?;
+        Ok((data, warnings))
+    }
+
+    /// Fetch the cached data like `get`, but if fetching fails fall back to
+    /// the expired cached data (if readable) rather than returning the error
+    ///
+    /// The returned data may be stale
+    pub fn get_or_stale(&self) -> Result<T, ToteError>
+    where
+        T: DeserializeOwned,
+        T: Serialize + Fetch<Cached = T>,
+    {
+        match self.get() {
+            Err(e @ (ToteError::Fetching(_) | ToteError::FetchingFor { .. })) => {
+                self.read_from(&self.path).map_err(|_| e)
+            }
+            res => res,
+        }
+    }
+
+    /// Fetch the cached data like `get`, but if reading the cache & fetching
+    /// both fail return `T::default()` rather than the error
+    ///
+    /// Fetched data is returned even if writing it to the cache file fails
+    /// (as with `OnWriteError::ReturnData`). For non-critical data, the error
+    /// is still passed to any `on_fetch` hook
+    pub fn get_or_default(&self) -> T
+    where
+        T: DeserializeOwned,
+        T: Serialize + Fetch<Cached = T> + Default,
+    {
+        if let Ok(data) = self.read_reported() {
+            return data;
+        }
+        let tote = Self {
+            on_write_error: OnWriteError::ReturnData,
+            ..self.clone()
+        };
+        // The clone's write doesn't drop this cache's in-memory copy
+        self.forget();
+        tote.fetch_and_put(|| tote.fetch_retrying().map_err(|e| tote.fetch_error(e)))
+            .map(|(data, _)| data)
+            .unwrap_or_default()
+    }
+
+    #[cfg(feature = "async")]
+    /// Fetch the cached data according to the given `GetOptions`,
+    /// returning the data and where it came from
+    pub async fn get_opts_async(&self, opts: GetOptions) -> Result<(T, Source), ToteError>
+    where
+        T: DeserializeOwned,
+        T: Serialize + AsyncFetch<Cached = T>,
+    {
+        opts.validate()?;
+        if opts.force_refresh {
+            self.forget();
+        } else {
+            match self.read_async().await {
+                Ok(data) => {
+                    self.hit();
+                    return Ok((data, Source::Cache));
+                }
+                Err(e) if opts.offline => {
+                    self.miss();
+                    return Err(e);
+                }
+                Err(_) => {}
+            }
+            if let Some(data) = self.read_rate_limited() {
+                self.hit();
+                return Ok((data, Source::Cache));
+            }
+            self.miss();
+        }
+        self.hooks.fetch_start();
+        let res = self.fetch_async_bounded(T::fetch_async).await;
+        self.hooks.fetched(res.as_ref());
+        let data = res?;
+        if opts.write {
+            self.put_fetched_async(&data).await?;
+        }
+        Ok((data, Source::Fetched))
+    }
+
+    #[cfg(feature = "async")]
+    /// Fetch fresh data & write it to the cache file, regardless of any
+    /// valid cached data
+    pub async fn force_refresh_async(&self) -> Result<T, ToteError>
+    where
+        T: DeserializeOwned,
+        T: Serialize + AsyncFetch<Cached = T>,
+    {
+        let opts = GetOptions {
+            force_refresh: true,
+            ..Default::default()
+        };
+        Ok(self.get_opts_async(opts).await?.0)
+    }
+
+    #[cfg(feature = "async")]
+    /// Fetch the cached data like `get_async`, but without writing fetched
+    /// data to the cache file for just this call
+    pub async fn get_no_write_async(&self) -> Result<T, ToteError>
+    where
+        T: DeserializeOwned,
+        T: Serialize + AsyncFetch<Cached = T>,
+    {
+        let opts = GetOptions {
+            write: false,
+            ..Default::default()
+        };
+        Ok(self.get_opts_async(opts).await?.0)
+    }
+
+    #[cfg(feature = "async")]
+    /// Fetch the cached data, returning Err for I/O issues
+    /// or if the cache file is expired
+    ///
+    /// Requires `T: AsyncFetch`, see `get` for `Fetch` types
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "tote.get", level = "debug", skip_all, fields(path = %self.path.display()))
+    )]
+    pub async fn get_async(&self) -> Result<T, ToteError>
+    where
+        T: DeserializeOwned,
+        T: Serialize + AsyncFetch<Cached = T>,
+    {
+        // Fall-back to fetching data (or awaiting an in-flight prefetch)
+        // and updating cache file
+        self.get_async_with(async {
+            let prefetch = self.prefetch.lock().ok().and_then(|mut p| p.take());
+            match prefetch {
+                Some(mut prefetch) => match (&mut prefetch.0).await {
+                    Ok(res) => res.map_err(|e| self.fetch_error(e.into())),
+                    // Prefetch task was cancelled or panicked, fetch again
+                    Err(_) => self.fetch_async_bounded(T::fetch_async).await,
+                },
+                None => self.fetch_async_bounded(T::fetch_async).await,
+            }
+        })
+        .await
+    }
+
+    #[cfg(feature = "async-native")]
+    /// Fetch the cached data, returning Err for I/O issues
+    /// or if the cache file is expired
+    ///
+    /// Requires `T: AsyncFetchNative`, and otherwise behaves like `get_async`
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "tote.get", level = "debug", skip_all, fields(path = %self.path.display()))
+    )]
+    pub async fn get_async_native(&self) -> Result<T, ToteError>
+    where
+        T: DeserializeOwned,
+        T: Serialize + AsyncFetchNative<Cached = T>,
+    {
+        self.get_async_with(self.fetch_async_bounded(T::fetch_async))
+            .await
+    }
+
+    #[cfg(any(feature = "async", feature = "async-native"))]
+    /// Read valid cached data, or await `fetch` & write the fetched data,
+    /// shared by `get_async` & `get_async_native`
+    async fn get_async_with<Fut>(&self, fetch: Fut) -> Result<T, ToteError>
+    where
+        T: DeserializeOwned + Serialize,
+        Fut: std::future::Future<Output = Result<T, ToteError>>,
+    {
+        let read_error = match self.read_reported_async().await {
+            Ok(data) => return Ok(data),
+            Err(e) => e,
+        };
+        match self.fetch_and_put_async(fetch).await {
+            Ok((data, _)) => Ok(data),
+            Err(ToteError::Timeout(timeout)) if self.stale_on_timeout => self
+                .read_from(&self.path)
+                .map_err(|_| self.unreadable_cache_error(read_error, ToteError::Timeout(timeout))),
+            Err(e) => Err(self.unreadable_cache_error(read_error, e)),
+        }
+    }
+
+    /// Fetch the cached data, conditionally re-fetching with `FetchConditional`
+    /// when the cache file is missing or expired
+    ///
+    /// If the source reports the data as `Unchanged`, the existing cached
+    /// data is kept & rewritten to reset its expiry. Otherwise the new data
+    /// & its `FetchMeta` (in a `<path>.meta` sidecar file) are written
+    ///
+    /// Fetches are retried & locked like `get`. Returns
+    /// `ToteError::InvalidOptions` with a `Backend`
+    pub fn get_conditional(&self) -> Result<T, ToteError>
+    where
+        T: DeserializeOwned,
+        T: Serialize + FetchConditional<Cached = T>,
+    {
+        if self.backend.is_some() {
+            return Err(ToteError::InvalidOptions(
+                "`get_conditional` isn't supported with `with_backend`",
+            ));
+        }
+        let read_error = match self.read_reported() {
+            Ok(data) => return Ok(data),
+            Err(e) => e,
+        };
+        let meta_path = sidecar_path(&self.path, "meta");
+        let mut fetched_meta = None;
+        let (data, source) = self
+            .fetch_and_put(|| {
+                // Validators are only useful if the stale data they describe is still readable
+                let stale = self.read_from(&self.path).ok().and_then(|data| {
+                    let meta = fs::read(&meta_path).ok()?;
+                    Some((data, serde_json::from_slice::<FetchMeta>(&meta).ok()?))
+                });
+                let validators = stale.as_ref().map(|(_, meta)| meta);
+                match self
+                    .retrying(|| T::fetch_conditional(validators))
+                    .map_err(|e| self.fetch_error(e))?
+                {
+                    Conditional::Unchanged => {
+                        stale.map(|(data, _)| data).ok_or(ToteError::InvalidCache)
+                    }
+                    Conditional::Changed(data, meta) => {
+                        fetched_meta = Some(meta);
+                        Ok(data)
+                    }
+                }
+            })
+            .map_err(|e| self.unreadable_cache_error(read_error, e))?;
+        if let (Source::Fetched, Some(meta)) = (source, fetched_meta) {
+            let meta = serde_json::to_vec(&meta)?;
+            self.on_write_result(self.write_atomic(&meta_path, &meta).map_err(Into::into))?;
+        }
+        Ok(data)
+    }
+
+    /// Atomically read-modify-write the cached data, fetching it first if
+    /// missing or expired, returning the updated data
+    ///
+    /// An advisory lock on a `<path>.lock` file is held for the duration,
+    /// so concurrent updates (across threads or processes) aren't lost
+    pub fn update<F>(&self, f: F) -> Result<T, ToteError>
+    where
+        T: DeserializeOwned,
+        T: Serialize + Fetch<Cached = T>,
+        F: FnOnce(&mut T),
+    {
+        let _lock = self.lock()?;
+        let mut data = match self.read() {
+            Ok(data) => data,
+            Err(_) => self
+                .timed(|| self.fetch_retrying())
+                .map_err(|e| self.fetch_error(e))?,
+        };
+        f(&mut data);
+        self.put(&data)?;
+        Ok(data)
+    }
+
+    /// Write `value` as the cached data without fetching, replacing any
+    /// existing data, e.g. to seed a cache in tests or import data from
+    /// another source
+    ///
+    /// The written data is fresh, and is written with the same options
+    /// (e.g. atomic writes & checksums) as fetched data
+    pub fn set(&self, value: &T) -> Result<(), ToteError>
+    where
+        T: Serialize,
+    {
+        self.put(value)
+    }
+
+    /// Merge a delta (e.g. only the changed entries of a large map) into the
+    /// cached data & write it back, returning the merged data
+    ///
+    /// Expired cached data is still merged into, if there's no cached data
+    /// the delta is written as-is. Like `update`, this holds the
+    /// `<path>.lock` file while merging
+    pub fn get_and_merge(&self, delta: T) -> Result<T, ToteError>
+    where
+        T: DeserializeOwned,
+        T: Serialize + Mergeable,
+    {
+        let _lock = self.lock()?;
+        let data = match self.read_from(&self.path) {
+            Ok(mut cached) => {
+                cached.merge(delta);
+                cached
+            }
+            Err(_) => delta,
+        };
+        self.put(&data)?;
+        Ok(data)
+    }
+
+    /// Fetch the cached data, falling back to the given closure (rather than
+    /// a `Fetch` impl) when the cache file is missing or expired
+    pub fn get_or_fetch<F>(&self, f: F) -> Result<T, ToteError>
+    where
+        T: DeserializeOwned,
+        T: Serialize,
+        F: FnOnce() -> Result<T, Box<dyn std::error::Error>>,
+    {
+        if let Ok(data) = self.read_reported() {
+            return Ok(data);
+        }
+        Ok(self
+            .fetch_and_put(|| f().map_err(|e| self.fetch_error(e)))?
+            .0)
+    }
+
+    #[cfg(feature = "async")]
+    /// Fetch the cached data, falling back to the given async closure (rather
+    /// than an `AsyncFetch` impl) when the cache file is missing or expired
+    pub async fn get_or_fetch_async<F, Fut>(&self, f: F) -> Result<T, ToteError>
+    where
+        T: DeserializeOwned,
+        T: Serialize,
+        F: FnOnce() -> Fut,
+        Fut: std::future::Future<Output = Result<T, Box<dyn std::error::Error>>>,
+    {
+        if let Ok(data) = self.read_async().await {
+            self.hit();
+            return Ok(data);
+        }
+        if let Some(data) = self.read_rate_limited() {
+            self.hit();
+            return Ok(data);
+        }
+        self.miss();
+        self.hooks.fetch_start();
+        let res = self.timed_async(f()).await.map_err(|e| self.fetch_error(e));
+        self.hooks.fetched(res.as_ref());
+        let data = res?;
+        self.put_fetched_async(&data).await?;
+        Ok(data)
+    }
+
+    /// Fetch the cached data, falling back to the given closure
+    /// when the cache file is missing or expired
+    ///
+    /// Errors returned by `f` are passed through as `GetError::Fetch`
+    /// without boxing (hooks receive their message). Fetches are locked
+    /// & rate-limited like `get`
+    pub fn get_or_try_insert_with<F, E>(&self, f: F) -> Result<T, GetError<E>>
+    where
+        T: DeserializeOwned,
+        T: Serialize,
+        F: FnOnce() -> Result<T, E>,
+        E: std::fmt::Display,
+    {
+        if let Ok(data) = self.read_reported() {
+            return Ok(data);
+        }
+        self.fetch_and_put_with(f)
+    }
+
+    /// Fetch the cached data (like `get`), then use it to fetch the dependent
+    /// `next` cache with the given closure, returning `next`'s data
+    ///
+    /// `next` is re-fetched if expired, or if this cache file was written
+    /// after it (so `f` always sees the data `next` was derived from), in
+    /// which case the outdated `next` is removed first. Fetches of `next`
+    /// are locked & rate-limited like `get`, and `f`'s errors are returned
+    /// as `GetError::Fetch`
+    pub fn then<U, D, F, E>(&self, next: &Tote<U, D>, f: F) -> Result<U, GetError<E>>
+    where
+        T: DeserializeOwned,
+        T: Serialize + Fetch<Cached = T>,
+        U: DeserializeOwned,
+        U: Serialize,
+        D: Codec,
+        F: FnOnce(&T) -> Result<U, E>,
+        E: std::fmt::Display,
+    {
+        let data = self.get()?;
+        let outdated = match (
+            self.last_modified().ok().flatten(),
+            next.last_modified().ok().flatten(),
+        ) {
+            (Some(written), Some(next_written)) => written > next_written,
+            _ => false,
+        };
+        if outdated {
+            next.invalidate()?;
+        }
+        if let Ok(next_data) = next.read_reported() {
+            return Ok(next_data);
+        }
+        next.fetch_and_put_with(|| f(&data))
+    }
+
+    #[cfg(feature = "async")]
+    /// Fetch the cached data, falling back to the given async closure
+    /// when the cache file is missing or expired
+    ///
+    /// Errors returned by `f` are passed through as `GetError::Fetch`
+    /// without boxing (hooks receive their message). Fetches are locked
+    /// & rate-limited like `get_async`
+    pub async fn get_or_try_insert_with_async<F, Fut, E>(&self, f: F) -> Result<T, GetError<E>>
+    where
+        T: DeserializeOwned,
+        T: Serialize,
+        F: FnOnce() -> Fut,
+        Fut: std::future::Future<Output = Result<T, E>>,
+        E: std::fmt::Display,
+    {
+        if let Ok(data) = self.read_reported_async().await {
+            return Ok(data);
+        }
+        let mut fetch_error = None;
+        let fetch = async {
+            f().await.map_err(|e| {
+                let error = ToteError::Fetching(e.to_string().into());
+                fetch_error = Some(e);
+                error
+            })
+        };
+        let res = self.fetch_and_put_async(fetch).await.map(|(data, _)| data);
+        match fetch_error {
+            Some(e) => Err(GetError::Fetch(e)),
+            None => Ok(res?),
+        }
+    }
+
+    #[cfg(any(feature = "async", feature = "async-native"))]
+    /// Wait for background writes started with `async_writes` to complete,
+    /// e.g. before shutdown, returning the first write error since the
+    /// last flush
+    pub async fn flush_writes(&self) -> Result<(), ToteError> {
+        let handles = std::mem::take(&mut self.pending_writes().handles);
+        for handle in handles {
+            if let Err(e) = handle.await {
+                self.pending_writes()
+                    .error
+                    .get_or_insert_with(|| io::Error::other(e));
+            }
+        }
+        match self.pending_writes().error.take() {
+            Some(e) => Err(e.into()),
+            None => Ok(()),
+        }
+    }
+
+    #[cfg(any(feature = "async", feature = "async-native"))]
+    /// Lock the background writes started by `async_writes`
+    fn pending_writes(&self) -> std::sync::MutexGuard<'_, PendingWrites> {
+        self.pending_writes
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Read the cached data without ever fetching, returning None if the
+    /// cache file is missing or expired
+    ///
+    /// Returns Err for I/O or deserialization errors of a valid cache file
+    pub fn peek(&self) -> Result<Option<T>, ToteError>
+    where
+        T: DeserializeOwned,
+    {
+        match self.read() {
+            Ok(data) => Ok(Some(data)),
+            Err(ToteError::InvalidCache) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Re-read the cached data like `peek`, bypassing & replacing the
+    /// in-memory copy kept by `with_memory_cache`
+    ///
+    /// Useful when another process rewrote the cache file. Never fetches
+    pub fn reload(&self) -> Result<Option<T>, ToteError>
+    where
+        T: DeserializeOwned + Clone + Send + Sync + 'static,
+    {
+        self.forget();
+        let data = self.peek()?;
+        if let (true, Some(data)) = (self.memory_cache, &data) {
+            let written = self.last_modified()?.unwrap_or_else(|| self.now());
+            self.remember(Arc::new(data.clone()), written);
+        }
+        Ok(data)
+    }
+
+    /// Does a cache file exist, whether or not it's expired (or even
+    /// parses). Never fetches
+    ///
+    /// See `is_valid` to also check expiry
+    pub fn exists(&self) -> bool {
+        match &self.backend {
+            Some(backend) => backend.modified(&self.path).is_ok(),
+            None => fs::metadata(&self.path).is_ok(),
+        }
+    }
+
+    /// Is the cached data valid (exists, is non-empty & not expired).
+    /// Never fetches
+    ///
+    /// Expiry is checked from the file's metadata only, the contents aren't
+    /// read, so a valid cache file may still fail to parse. The exception is
+    /// `with_ttl_from`, where the file is deserialized to compute its expiry
+    ///
+    /// The age limit is inclusive: a file written exactly `max_age` ago is
+    /// still valid, and is expired 1ns later
+    pub fn is_valid(&self) -> bool {
+        match self.valid_by_value {
+            Some(valid_by_value) => valid_by_value(self),
+            None => self.valid_modified(&self.path).is_some(),
+        }
+    }
+
+    /// Whether the cached data is fresh, stale (within the `with_grace`
+    /// window) or expired. Never fetches
+    ///
+    /// Staleness is judged from the cache file's age against `max_age`
+    pub fn freshness(&self) -> Freshness {
+        if !self.is_valid() {
+            return Freshness::Expired;
+        }
+        if self.grace.is_zero() || self.expiry_policy.is_some() {
+            return Freshness::Fresh;
+        }
+        match self.age_of(&self.path) {
+            Some(age) if !within_max_age(Some(age), self.base_max_age(&self.path)) => {
+                Freshness::Stale
+            }
+            _ => Freshness::Fresh,
+        }
+    }
+
+    /// Modified time of the cache file (when data was last written),
+    /// or None if it doesn't exist. Never fetches
+    pub fn last_modified(&self) -> Result<Option<SystemTime>, ToteError> {
+        let modified = match &self.backend {
+            Some(backend) => backend.modified(&self.path),
+            None => fs::metadata(&self.path).and_then(|metadata| metadata.modified()),
+        };
+        unless_not_found(modified)
+    }
+
+    /// Size of the cache file in bytes, as written (after any compression or
+    /// encryption), or None if it doesn't exist. Never fetches
+    pub fn size_on_disk(&self) -> Result<Option<u64>, ToteError> {
+        let size = match &self.backend {
+            Some(backend) => backend.read_bytes(&self.path).map(|data| data.len() as u64),
+            None => fs::metadata(&self.path).map(|metadata| metadata.len()),
+        };
+        unless_not_found(size)
+    }
+
+    /// Age of the cache file, or None if it doesn't exist. Never fetches
+    ///
+    /// A modified time in the future (e.g. from clock skew) is reported
+    /// as an age of zero
+    pub fn age(&self) -> Result<Option<Duration>, ToteError> {
+        let now = self.now();
+        Ok(self.last_modified()?.map(|modified| age_at(modified, now)))
+    }
+
+    /// Read the cached data, falling back to the borrowed `default` (without
+    /// fetching) when the cache file is missing or expired
+    ///
+    /// Cheap for data which is usually a compiled-in default, use `get`
+    /// or `update` to populate the cache with an override
+    pub fn get_or_static(&self, default: &'static T) -> DefaultOrFetched<T>
+    where
+        T: DeserializeOwned,
+    {
+        match self.read() {
+            Ok(data) => DefaultOrFetched::Fetched(data),
+            Err(_) => DefaultOrFetched::Default(default),
+        }
+    }
+
+    #[cfg(feature = "bytes")]
+    /// Fetch the raw (serialized) cache contents as `Bytes`, returning Err
+    /// for I/O issues or if the cache file is expired
+    ///
+    /// The returned `Bytes` can be cheaply cloned and shared across tasks
+    pub fn get_bytes(&self) -> Result<bytes::Bytes, ToteError> {
+        let path = self.read_path().ok_or(ToteError::InvalidCache)?;
+        Ok(bytes::Bytes::from(self.read_bytes_from(path)?.into_vec()))
+    }
+
+    /// Write a human-readable (pretty-printed JSON) copy of the cached data
+    /// to `out`, for inspecting caches while debugging
+    ///
+    /// The live cache file is left unchanged, returns Err for I/O issues
+    /// or if the cache file is expired
+    pub fn export_json<P: AsRef<Path>>(&self, out: P) -> Result<(), ToteError>
+    where
+        T: DeserializeOwned,
+        T: Serialize,
+    {
+        let path = self.read_path().ok_or(ToteError::InvalidCache)?;
+        let data = self.read_from(path)?;
+        fs::write(out, serde_json::to_string_pretty(&data)?)?;
+        Ok(())
+    }
+
+    /// Delete the cache file so the next `get` will re-fetch
+    ///
+    /// Returns Ok if the cache file is already missing
+    pub fn invalidate(&self) -> Result<(), ToteError> {
+        self.forget();
+        let res = match &self.backend {
+            Some(backend) => backend.remove(&self.path),
+            None => fs::remove_file(&self.path),
+        };
+        match res {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Delete the cache file only if it's expired, returning whether it was
+    /// deleted
+    ///
+    /// Fresh cache files are kept, a missing cache file returns `Ok(false)`.
+    /// Never fetches, failing to delete returns `ToteError::FileAccess`
+    pub fn clear_if_expired(&self) -> Result<bool, ToteError> {
+        if self.is_valid() || !self.exists() {
+            return Ok(false);
+        }
+        self.invalidate()?;
+        Ok(true)
+    }
+
+    /// Mark the cached data as expired without deleting it
+    ///
+    /// The cache file's modified time (or embedded fetch time, if enveloped)
+    /// is set to the Unix epoch so the next `get` will re-fetch, but the
+    /// stale data stays on disk until then
+    ///
+    /// Returns `ToteError::InvalidOptions` with a `Backend`, unless enveloped
+    pub fn expire_now(&self) -> Result<(), ToteError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        self.forget();
+        if self.enveloped() {
+            let data = match self.read_from(&self.path) {
+                Ok(data) => data,
+                Err(ToteError::FileAccess(e)) if e.kind() != io::ErrorKind::NotFound => {
+                    return Err(e.into())
+                }
+                // Missing & unreadable data is already re-fetched
+                Err(_) => return Ok(()),
+            };
+            let expired = Self {
+                clock: Some(Arc::new(|| SystemTime::UNIX_EPOCH)),
+                on_unchanged: OnUnchanged::Write,
+                ..self.clone()
+            };
+            return expired.put(&data);
+        }
+        if self.backend.is_some() {
+            return Err(ToteError::InvalidOptions(
+                "`expire_now` requires an envelope with `with_backend`",
+            ));
+        }
+        match touch(&self.path, SystemTime::UNIX_EPOCH) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Migrate a valid cache of type `O` at `old_path` into this cache
+    ///
+    /// Only runs when this cache is missing or expired and the old cache
+    /// is valid. The converted data is written to this cache's path and the
+    /// old cache file is removed. Returns whether a migration took place
+    pub fn migrate_from<O, P, F>(&self, old_path: P, convert: F) -> Result<bool, ToteError>
+    where
+        O: DeserializeOwned,
+        T: Serialize,
+        P: AsRef<Path>,
+        F: FnOnce(O) -> T,
+    {
+        if self.is_valid() {
+            return Ok(false);
+        }
+        let mut old: Tote<O> = Tote::new(old_path, self.max_age);
+        old.backend = self.backend.clone();
+        let data = match old.read() {
+            Ok(data) => data,
+            Err(_) => return Ok(false),
+        };
+        self.put(&convert(data))?;
+        old.invalidate()?;
+        Ok(true)
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "tote.read", level = "debug", skip_all, fields(path = %self.path.display()))
+    )]
+    fn read(&self) -> Result<T, ToteError>
+    where
+        T: DeserializeOwned,
+    {
+        self.check_path()?;
+        let Some(path) = self.read_path() else {
+            return Err(ToteError::InvalidCache);
+        };
+        let contents = self.read_bytes_from(path)?;
+        self.read_contents(path, &contents)
+    }
+
+    /// Async version of `read`, reading the cache file with `tokio::fs`
+    #[cfg(any(feature = "async", feature = "async-native"))]
+    async fn read_async(&self) -> Result<T, ToteError>
+    where
+        T: DeserializeOwned,
+    {
+        if self.backend.is_some() {
+            return self.read();
+        }
+        reject_dir(&self.path)?;
+        let Some(path) = self.read_path() else {
+            return Err(ToteError::InvalidCache);
+        };
+        let contents = retry_async(self.read_attempts, || tokio::fs::read(path)).await?;
+        let contents = self.check_contents(path, Contents::Read(contents))?;
+        self.read_contents(path, &contents)
+    }
+
+    /// Deserialize & check the expiry of valid cache file contents
+    /// read from `path`
+    fn read_contents(&self, path: &Path, contents: &[u8]) -> Result<T, ToteError>
+    where
+        T: DeserializeOwned,
+    {
+        // If the data fails to deserialize or is expired,
+        // callers fall through and re-fetch the data
+        let (data, stamp) = match self.parse(contents) {
+            Err(e @ (ToteError::Parse(_) | ToteError::Codec(_))) => {
+                if let Some(dir) = &self.quarantine {
+                    let _ = quarantine(path, dir);
+                }
+                return Err(e);
+            }
+            res => res?,
+        };
+        if !self.is_fresh(path, &data, stamp) {
+            return Err(ToteError::InvalidCache);
+        }
+        if self.sliding_expiration && self.backend.is_none() {
+            // Best-effort, failing to extend the expiry doesn't invalidate the data
+            let _ = touch(path, self.now());
+        }
+        Ok(data)
+    }
+
+    /// Is data deserialized from `path` unexpired, per `with_ttl_from` or its
+    /// embedded fetch & `valid_until` times. Other expiry is checked before
+    /// reading the file
+    fn is_fresh(&self, path: &Path, data: &T, stamp: Option<Stamp>) -> bool {
+        if stamp
+            .and_then(|stamp| stamp.valid_until)
+            .is_some_and(|valid_until| self.now() > valid_until)
+        {
+            return false;
+        }
+        let fetched_at = stamp.map(|stamp| stamp.fetched_at);
+        let max_age = match (&self.ttl_from, fetched_at) {
+            (Some(ttl_from), _) => ttl_from(data),
+            (None, Some(_)) => self.base_max_age(path),
+            // Legacy file without an envelope, skipped by the modified time check
+            (None, None) if self.enveloped() => self.base_max_age(path),
+            (None, None) => return true,
+        };
+        let age = match fetched_at {
+            Some(fetched_at) => Some(age_at(fetched_at, self.now())),
+            None => self.age_of(path),
+        };
+        within_max_age(age, self.graced(max_age))
+    }
+
+    /// Deserialize the cache file at `path`, regardless of expiry
+    fn read_from(&self, path: &Path) -> Result<T, ToteError>
+    where
+        T: DeserializeOwned,
+    {
+        Ok(self.read_timestamped(path)?.0)
+    }
+
+    /// Deserialize the cache file at `path` regardless of expiry, along with
+    /// its embedded times if enveloped
+    fn read_timestamped(&self, path: &Path) -> Result<(T, Option<Stamp>), ToteError>
+    where
+        T: DeserializeOwned,
+    {
+        self.parse(&self.read_bytes_from(path)?)
+    }
+
+    /// Deserialize cache file contents, along with the embedded times
+    /// if enveloped
+    fn parse(&self, contents: &[u8]) -> Result<(T, Option<Stamp>), ToteError>
+    where
+        T: DeserializeOwned,
+    {
+        // A zero-byte file (e.g. interrupted write or `touch`) is never valid,
+        // regardless of its age
+        if contents.is_empty() {
+            return Err(ToteError::InvalidCache);
+        }
+        if let Some(max_depth) = self.max_depth {
+            if json_depth(contents) > max_depth {
+                return Err(ToteError::InvalidCache);
+            }
+        }
+        if let Some(version) = self.schema_version {
+            let header: VersionHeader = self.codec.deserialize(contents)?;
+            if header.version != version {
+                let migrate = self.migrate.as_ref().ok_or(ToteError::InvalidCache)?;
+                let data = migrate(header.version, contents)?;
+                return Ok((
+                    data,
+                    Some(Stamp::new(header.fetched_at, header.valid_until)),
+                ));
+            }
+        }
+        if let Some(after_read) = &self.after_read {
+            let (mut value, stamp) = self.decode(contents)?;
+            after_read(&mut value);
+            let data = serde_json::from_value::<T>(value).map_err(ToteError::Parse)?;
+            return Ok((data, stamp));
+        }
+        self.decode(contents)
+    }
+
+    /// Is cached data wrapped in an envelope (`Envelope` or `VersionedEnvelope`)
+    fn enveloped(&self) -> bool {
+        self.embedded_timestamp || self.schema_version.is_some()
+    }
+
+    /// Deserialize cache file contents, unwrapping the envelope if enabled
+    fn decode<V: DeserializeOwned>(
+        &self,
+        contents: &[u8],
+    ) -> Result<(V, Option<Stamp>), ToteError> {
+        if self.schema_version.is_some() {
+            let envelope: VersionedEnvelope<V> = self.codec.deserialize(contents)?;
+            let stamp = Stamp::new(envelope.fetched_at, envelope.valid_until);
+            return Ok((envelope.data, Some(stamp)));
+        }
+        if self.embedded_timestamp {
+            return match self.codec.deserialize::<Envelope<V>>(contents) {
+                Ok(envelope) => {
+                    let stamp = Stamp::new(envelope.fetched_at, envelope.valid_until);
+                    Ok((envelope.data, Some(stamp)))
+                }
+                // Legacy file written before enabling, without an envelope
+                Err(e) => match self.codec.deserialize(contents) {
+                    Ok(data) => Ok((data, None)),
+                    Err(_) => Err(e),
+                },
+            };
+        }
+        Ok((self.codec.deserialize(contents)?, None))
+    }
+
+    /// Serialize a value with the `Codec`, wrapped in an envelope (with
+    /// the given `valid_until` time & data hash) if enabled
+    fn encode<V: Serialize>(
+        &self,
+        value: &V,
+        valid_until: Option<SystemTime>,
+        hash: Option<u64>,
+    ) -> Result<Vec<u8>, ToteError> {
+        let unix_secs = |time: SystemTime| {
+            time.duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+        };
+        let valid_until = valid_until.map(unix_secs);
+        if let Some(version) = self.schema_version {
+            let envelope = VersionedEnvelope {
+                fetched_at: unix_secs(self.now()),
+                version,
+                valid_until,
+                hash,
+                data: value,
+            };
+            return self.codec_serialize(&envelope);
+        }
+        if self.embedded_timestamp {
+            let envelope = Envelope {
+                fetched_at: unix_secs(self.now()),
+                valid_until,
+                hash,
+                data: value,
+            };
+            return self.codec_serialize(&envelope);
+        }
+        self.codec_serialize(value)
+    }
+
+    /// Serialize a value with the `Codec`, or as pretty JSON if `pretty`
+    fn codec_serialize<V: Serialize>(&self, value: &V) -> Result<Vec<u8>, ToteError> {
+        if self.pretty {
+            // Only settable for `JsonCodec`
+            return Ok(serde_json::to_vec_pretty(value)?);
+        }
+        self.codec.serialize(value)
+    }
+
+    /// Read the raw contents of the cache file at `path`, regardless of expiry
+    fn read_bytes_from(&self, path: &Path) -> Result<Contents, ToteError> {
+        if let Some(backend) = &self.backend {
+            let contents = retry(self.read_attempts, || backend.read_bytes(path))?;
+            return self.check_contents(path, Contents::Read(contents));
+        }
+        #[cfg(feature = "mmap")]
+        let contents = if self.memory_mapped {
+            let file = retry(self.read_attempts, || fs::File::open(path))?;
+            // SAFETY: See the caveats documented on `memory_mapped`
+            Contents::Mapped(unsafe { memmap2::Mmap::map(&file)? })
+        } else {
+            Contents::Read(retry(self.read_attempts, || fs::read(path))?)
+        };
+        #[cfg(not(feature = "mmap"))]
+        let contents = Contents::Read(retry(self.read_attempts, || fs::read(path))?);
+        self.check_contents(path, contents)
+    }
+
+    /// Verify the hash sidecar of & decompress contents read from `path`
+    #[cfg_attr(not(feature = "sha256"), allow(unused_variables))]
+    fn check_contents(&self, path: &Path, contents: Contents) -> Result<Contents, ToteError> {
+        #[cfg(feature = "sha256")]
+        if self.hash_sidecar {
+            verify_sha256(path, &contents)?;
+        }
+        #[cfg(feature = "encrypt")]
+        let contents = match &self.cipher {
+            Some(cipher) => Contents::Read(
+                cipher
+                    .decrypt(&contents)
+                    .map_err(|_| ToteError::InvalidCache)?,
+            ),
+            None => contents,
+        };
+        #[cfg(feature = "gzip")]
+        let contents = if contents.starts_with(&GZIP_MAGIC) {
+            Contents::Read(gunzip(&contents)?)
+        } else {
+            contents
+        };
+        if self.checksum {
+            return Ok(Contents::Read(strip_checksum(&contents)?.to_vec()));
+        }
+        Ok(contents)
+    }
+
+    /// Write new or updated device cache data
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "tote.put", level = "debug", skip_all, fields(path = %self.path.display()))
+    )]
+    fn put(&self, value: &T) -> Result<(), ToteError>
+    where
+        T: Serialize,
+    {
+        self.forget();
+        self.check_path()?;
+        let (data, hash) = self.serialize(value)?;
+        if hash.is_some() {
+            let stored = self
+                .read_bytes_from(&self.path)
+                .ok()
+                .and_then(|contents| self.stored_hash(&contents));
+            if stored == hash && self.skip_unchanged()? {
+                return Ok(());
+            }
+        }
+        self.write_bytes(&data)?;
+        if let Some(verify) = self.verify {
+            verify(self, value)?;
+        }
+        Ok(())
+    }
+
+    /// Async version of `put`, writing the cache file with `tokio::fs`
+    #[cfg(any(feature = "async", feature = "async-native"))]
+    async fn put_async(&self, value: &T) -> Result<(), ToteError>
+    where
+        T: Serialize,
+    {
+        if self.backend.is_some() {
+            return self.put(value);
+        }
+        self.forget();
+        reject_dir(&self.path)?;
+        let (data, hash) = self.serialize(value)?;
+        if hash.is_some() {
+            let stored = tokio::fs::read(&self.path)
+                .await
+                .ok()
+                .and_then(|contents| {
+                    self.check_contents(&self.path, Contents::Read(contents))
+                        .ok()
+                })
+                .and_then(|contents| self.stored_hash(&contents));
+            if stored == hash && self.skip_unchanged()? {
+                return Ok(());
+            }
+        }
+        self.write_file_async(&self.path, &data).await?;
+        if let Some(mirror) = &self.mirror {
+            // Mirror writes are best-effort, the primary write has succeeded
+            if let Err(e) = self.write_file_async(mirror, &data).await {
+                mirror_failed(mirror, &e);
+            }
+        }
+        if let Some(verify) = self.verify {
+            verify(self, value)?;
+        }
+        Ok(())
+    }
+
+    /// Serialize data as written to the cache file, along with the hash of
+    /// the serialized data alone (before any envelope, checksum, compression
+    /// or encryption) if needed by `on_unchanged`
+    fn serialize(&self, value: &T) -> Result<(Vec<u8>, Option<u64>), ToteError>
+    where
+        T: Serialize,
+    {
+        let valid_until = self.valid_until.as_ref().and_then(|f| f(value));
+        let transformed = if let Some(before_write) = &self.before_write {
+            let mut value = serde_json::to_value(value)?;
+            before_write(&mut value);
+            Some(value)
+        } else if self.deterministic {
+            // `serde_json::Value` maps are ordered by key
+            Some(serde_json::to_value(value)?)
+        } else {
+            None
+        };
+        let hashed = self.on_unchanged != OnUnchanged::Write;
+        // Enveloped data is serialized again to be hashed without its fetch time
+        let hash = match &transformed {
+            _ if !hashed || !self.enveloped() => None,
+            Some(transformed) => Some(fnv1a(&self.codec_serialize(transformed)?)),
+            None => Some(fnv1a(&self.codec_serialize(value)?)),
+        };
+        let data = if let Some(transformed) = &transformed {
+            self.encode(transformed, valid_until, hash)?
+        } else if let Some(capacity) = self
+            .size_hint
+            .filter(|_| !self.enveloped() && !self.pretty)
+            .and_then(|hint| hint(value))
+        {
+            self.codec.serialize_with_capacity(value, capacity)?
+        } else {
+            self.encode(value, valid_until, hash)?
+        };
+        let hash = hash.or_else(|| hashed.then(|| fnv1a(&data)));
+        if let Some(round_trip) = self.round_trip {
+            round_trip(self, &data)?;
+        }
+        let data = if self.checksum {
+            add_checksum(&data)
+        } else {
+            data
+        };
+        #[cfg(feature = "gzip")]
+        let data = if self.gzip
+            || self
+                .size_budget
+                .is_some_and(|budget| data.len() > budget.soft)
+        {
+            gzip(&data)?
+        } else {
+            data
+        };
+        #[cfg(feature = "gzip")]
+        if let Some(budget) = self.size_budget.filter(|budget| data.len() > budget.hard) {
+            return Err(ToteError::TooLarge {
+                size: data.len(),
+                limit: budget.hard,
+            });
+        }
+        #[cfg(feature = "encrypt")]
+        let data = match &self.cipher {
+            Some(cipher) => cipher.encrypt(&data),
+            None => data,
+        };
+        Ok((data, hash))
+    }
+
+    /// Hash of the serialized data in (decrypted & decompressed) cache file
+    /// `contents`, from the envelope if enveloped
+    fn stored_hash(&self, contents: &[u8]) -> Option<u64> {
+        if self.schema_version.is_some() {
+            return self.codec.deserialize::<VersionHeader>(contents).ok()?.hash;
+        }
+        if self.embedded_timestamp {
+            return self
+                .codec
+                .deserialize::<EnvelopeHeader>(contents)
+                .ok()?
+                .hash;
+        }
+        Some(fnv1a(contents))
+    }
+
+    /// Apply the `on_unchanged` policy to a write identical to the cache
+    /// file, returning whether the write is skipped
+    fn skip_unchanged(&self) -> Result<bool, ToteError> {
+        match self.on_unchanged {
+            OnUnchanged::PreserveExpiry => Ok(true),
+            // Enveloped data is rewritten with a new fetch time, and
+            // backends can't set modified times
+            OnUnchanged::RefreshExpiry if !self.enveloped() && self.backend.is_none() => {
+                touch(&self.path, self.now())?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Write raw contents to the cache file (and mirror, if configured),
+    /// or to the `Backend` if set
+    fn write_bytes(&self, data: &[u8]) -> io::Result<()> {
+        if let Some(backend) = &self.backend {
+            return backend.write_bytes(&self.path, data);
+        }
+        self.write_file(&self.path, data)?;
+        if let Some(mirror) = &self.mirror {
+            // Mirror writes are best-effort, the primary write has succeeded
+            if let Err(e) = self.write_file(mirror, data) {
+                mirror_failed(mirror, &e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Write `data` to `path` atomically (see `write_atomic`), along with
+    /// its sidecar files
+    fn write_file(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        self.write_atomic(path, data)?;
+        self.write_sidecars(path, data)
+    }
+
+    /// Write `data` to a sibling temp file, then atomically rename it over
+    /// `path` so readers never see a partially written file
+    ///
+    /// `fs::rename` replaces an existing destination on all platforms
+    fn write_atomic(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        if self.create_dirs {
+            create_parent_dir(path)?;
+        }
+        let tmp = self.temp_path(path);
+        if let Err(e) = self.write_tmp(&tmp, data).and_then(|_| {
+            match fs::rename(&tmp, path) {
+                // Temp dir is on another filesystem, fall back to a (non-atomic) copy
+                Err(error) if error.kind() == io::ErrorKind::CrossesDevices => {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(name: "cache.write_not_atomic", path = %path.display(), %error);
+                    fs::copy(&tmp, path)?;
+                    fs::remove_file(&tmp)
+                }
+                res => res,
+            }
+        }) {
+            let _ = fs::remove_file(&tmp);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Unique temp file path for writing `path`
+    fn temp_path(&self, path: &Path) -> PathBuf {
+        let affixes = self
+            .tempfile_affixes
+            .as_ref()
+            .map(|(prefix, suffix)| (prefix.as_str(), suffix.as_str()));
+        temp_path(path, self.tempfile_dir.as_deref(), affixes)
+    }
+
+    /// Async version of `write_file`, using `tokio::fs`
+    #[cfg(any(feature = "async", feature = "async-native"))]
+    async fn write_file_async(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        use tokio::io::AsyncWriteExt;
+
+        if self.create_dirs {
+            create_parent_dir(path)?;
+        }
+        let tmp = self.temp_path(path);
+        let res = async {
+            let file = tokio::fs::OpenOptions::from(self.open_options())
+                .open(&tmp)
+                .await?;
+            let mut writer = tokio::io::BufWriter::with_capacity(self.buffer_size, file);
+            writer.write_all(data).await?;
+            writer.flush().await?;
+            match tokio::fs::rename(&tmp, path).await {
+                Err(error) if error.kind() == io::ErrorKind::CrossesDevices => {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(name: "cache.write_not_atomic", path = %path.display(), %error);
+                    tokio::fs::copy(&tmp, path).await?;
+                    tokio::fs::remove_file(&tmp).await
+                }
+                res => res,
+            }
+        };
+        if let Err(e) = res.await {
+            let _ = tokio::fs::remove_file(&tmp).await;
+            return Err(e);
+        }
+        self.write_sidecars(path, data)
+    }
+
+    /// Write the generation & hash sidecars (if enabled) for `path`
+    #[cfg_attr(not(feature = "sha256"), allow(unused_variables))]
+    fn write_sidecars(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        if let Some(generation) = &self.generation {
+            fs::write(
+                sidecar_path(path, "gen"),
+                read_generation(generation).to_string(),
+            )?;
+        }
+        #[cfg(feature = "sha256")]
+        if self.hash_sidecar {
+            write_sha256(path, data)?;
+        }
+        if self.sliding_expiration && self.min_refresh_interval.is_some() && !self.enveloped() {
+            let written = self.now().duration_since(SystemTime::UNIX_EPOCH);
+            fs::write(
+                sidecar_path(path, "written"),
+                written.unwrap_or_default().as_secs().to_string(),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn write_tmp(&self, tmp: &Path, data: &[u8]) -> io::Result<()> {
+        let file = self.open_options().open(tmp)?;
+        let mut writer = io::BufWriter::with_capacity(self.buffer_size, file);
+        writer.write_all(data)?;
+        writer.flush()
+    }
+
+    /// Options for creating a temp file, after any `with_open_options` customization
+    fn open_options(&self) -> fs::OpenOptions {
+        let mut options = fs::OpenOptions::new();
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, self.file_mode);
+        if let Some(customize) = &self.open_options {
+            customize(&mut options);
+        }
+        options.create(true).write(true).truncate(true);
+        options
+    }
+
+    /// Take an exclusive advisory lock on the `<path>.lock` file,
+    /// released when the returned file is dropped
+    fn lock(&self) -> Result<fs::File, ToteError> {
+        Ok(lock_file(&self.lock_path()?)?)
+    }
+
+    #[cfg(any(feature = "async", feature = "async-native"))]
+    /// Async version of `lock`, waiting for the lock on a blocking thread
+    async fn lock_async(&self) -> Result<fs::File, ToteError> {
+        let path = self.lock_path()?;
+        let locked = tokio::task::spawn_blocking(move || lock_file(&path))
+            .await
+            .map_err(io::Error::other)?;
+        Ok(locked?)
+    }
+
+    /// Path of the `<path>.lock` file, which a `Backend` can't use
+    fn lock_path(&self) -> Result<PathBuf, ToteError> {
+        if self.backend.is_some() {
+            return Err(ToteError::InvalidOptions(
+                "`with_backend` doesn't support lock files",
+            ));
+        }
+        Ok(sidecar_path(&self.path, "lock"))
+    }
+
+    /// Current time, from the `Clock` if set
+    fn now(&self) -> SystemTime {
+        match &self.clock {
+            Some(clock) => clock.now(),
+            None => SystemTime::now(),
+        }
+    }
+
+    /// Fetch with `T::fetch`, retrying failures per `with_fetch_retry`
+    fn fetch_retrying(&self) -> Result<T, Box<dyn std::error::Error>>
+    where
+        T: Fetch<Cached = T>,
+    {
+        self.retrying(T::fetch)
+    }
+
+    /// Run a fetch, retrying failures per `with_fetch_retry`
+    fn retrying<R, E>(&self, mut fetch: impl FnMut() -> Result<R, E>) -> Result<R, E> {
+        let mut attempt = 1;
+        loop {
+            match fetch() {
+                Err(_) if attempt < self.fetch_attempts => {}
+                res => return res,
+            }
+            std::thread::sleep(self.fetch_retry_delay(attempt));
+            attempt += 1;
+        }
+    }
+
+    #[cfg(any(feature = "async", feature = "async-native"))]
+    /// Fetch with `fetch`, retrying failures per `with_fetch_retry`
+    async fn fetch_retrying_async<F, Fut>(
+        &self,
+        mut fetch: F,
+    ) -> Result<T, Box<dyn std::error::Error>>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<T, Box<dyn std::error::Error>>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match fetch().await {
+                Err(_) if attempt < self.fetch_attempts => {}
+                res => return res,
+            }
+            tokio::time::sleep(self.fetch_retry_delay(attempt)).await;
+            attempt += 1;
+        }
+    }
+
+    #[cfg(any(feature = "async", feature = "async-native"))]
+    /// Fetch with `fetch_retrying_async`, within `fetch_timeout` if set
+    async fn fetch_async_bounded<F, Fut>(&self, fetch: F) -> Result<T, ToteError>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<T, Box<dyn std::error::Error>>>,
+    {
+        let fetch = self.timed_async(self.fetch_retrying_async(fetch));
+        let res = match self.fetch_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, fetch)
+                .await
+                .map_err(|_| ToteError::Timeout(timeout))?,
+            None => fetch.await,
+        };
+        res.map_err(|e| self.fetch_error(e))
+    }
+
+    /// Exponential backoff before retrying after failed fetch `attempt`
+    fn fetch_retry_delay(&self, attempt: u32) -> Duration {
+        self.fetch_backoff
+            .saturating_mul(2u32.saturating_pow(attempt - 1))
+    }
+
+    /// Run a fetch, recording its duration if `adaptive_ttl` is set
+    fn timed<R>(&self, fetch: impl FnOnce() -> R) -> R {
+        let start = Instant::now();
+        let res = fetch();
+        self.record_fetch_duration(start.elapsed());
+        res
+    }
+
+    #[cfg(any(feature = "async", feature = "async-native"))]
+    /// Await a fetch, recording its duration if `adaptive_ttl` is set
+    async fn timed_async<R>(&self, fetch: impl std::future::Future<Output = R>) -> R {
+        let start = Instant::now();
+        let res = fetch.await;
+        self.record_fetch_duration(start.elapsed());
+        res
+    }
+
+    fn record_fetch_duration(&self, duration: Duration) {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            name: "fetch.duration",
+            path = %self.path.display(),
+            duration_ms = duration.as_millis() as u64,
+        );
+        if self.adaptive_ttl.is_some() {
+            // Best-effort, a missing duration falls back to zero
+            let _ = fs::write(
+                sidecar_path(&self.path, "fetch"),
+                duration.as_secs_f64().to_string(),
+            );
+        }
+    }
+
+    /// Report valid cached data being returned to `on_hit` (and `tracing`)
+    fn hit(&self) {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            name: "cache.hit",
+            path = %self.path.display(),
+            age = ?self.age_of(&self.path),
+        );
+        self.hooks.hit();
+    }
+
+    /// Report falling back to a fetch to `on_miss` (and `tracing`)
+    fn miss(&self) {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(name: "cache.miss", path = %self.path.display());
+        self.hooks.miss();
+    }
+
+    /// Wrap a fetch error, with the cache filepath if `map_err_with_path`
+    fn fetch_error(&self, source: Box<dyn std::error::Error>) -> ToteError {
+        if self.fetch_err_path {
+            ToteError::FetchingFor {
+                path: self.path.clone(),
+                source,
+            }
+        } else {
+            ToteError::Fetching(source)
+        }
+    }
+
+    /// Wrap a fetch error as `ToteError::UnreadableCache` if reading the
+    /// cache file beforehand failed to deserialize it
+    ///
+    /// Other errors (e.g. a missing or expired cache file, or failing to
+    /// write the fetched data) are returned as-is
+    fn unreadable_cache_error(&self, read: ToteError, error: ToteError) -> ToteError {
+        let fetch_failed = matches!(
+            error.kind(),
+            ToteErrorKind::Fetching | ToteErrorKind::Timeout
+        );
+        match read {
+            ToteError::Parse(_) | ToteError::Codec(_) if fetch_failed => {
+                ToteError::UnreadableCache {
+                    path: self.path.clone(),
+                    read: Box::new(read),
+                    source: Box::new(error),
+                }
+            }
+            _ => error,
+        }
+    }
+
+    /// Fetch & write data, holding the `<path>.lock` file if `lock_fetches`
+    /// is enabled
+    ///
+    /// Returns data from the cache file instead if it was written by another
+    /// caller while waiting for the lock
+    fn fetch_and_put<F>(&self, fetch: F) -> Result<(T, Source), ToteError>
+    where
+        T: DeserializeOwned,
+        T: Serialize,
+        F: FnOnce() -> Result<T, ToteError>,
+    {
+        let _lock = if self.lock_fetches {
+            let lock = self.lock()?;
+            if let Ok(data) = self.read() {
+                return Ok((data, Source::Cache));
+            }
+            Some(lock)
+        } else {
+            None
+        };
+        self.hooks.fetch_start();
+        let res = self.timed(fetch);
+        self.hooks.fetched(res.as_ref());
+        let data = res?;
+        self.put_fetched(&data)?;
+        Ok((data, Source::Fetched))
+    }
+
+    /// `fetch_and_put` with a fetch returning a custom error, which is
+    /// returned as `GetError::Fetch` (hooks receive its message)
+    fn fetch_and_put_with<F, E>(&self, fetch: F) -> Result<T, GetError<E>>
+    where
+        T: DeserializeOwned,
+        T: Serialize,
+        F: FnOnce() -> Result<T, E>,
+        E: std::fmt::Display,
+    {
+        let mut fetch_error = None;
+        let res = self.fetch_and_put(|| {
+            fetch().map_err(|e| {
+                let error = ToteError::Fetching(e.to_string().into());
+                fetch_error = Some(e);
+                error
+            })
+        });
+        match fetch_error {
+            Some(e) => Err(GetError::Fetch(e)),
+            None => Ok(res?.0),
+        }
+    }
+
+    #[cfg(any(feature = "async", feature = "async-native"))]
+    /// Async version of `fetch_and_put`, awaiting the `fetch` future &
+    /// writing with `put_fetched_async`
+    async fn fetch_and_put_async<Fut>(&self, fetch: Fut) -> Result<(T, Source), ToteError>
+    where
+        T: DeserializeOwned,
+        T: Serialize,
+        Fut: std::future::Future<Output = Result<T, ToteError>>,
+    {
+        let _lock = if self.lock_fetches {
+            let lock = self.lock_async().await?;
+            if let Ok(data) = self.read_async().await {
+                return Ok((data, Source::Cache));
+            }
+            Some(lock)
+        } else {
+            None
+        };
+        self.hooks.fetch_start();
+        // Scoped so the (non-`Send`) error isn't held across the write
+        let data = {
+            let res = fetch.await;
+            self.hooks.fetched(res.as_ref());
+            res?
+        };
+        self.put_fetched_async(&data).await?;
+        Ok((data, Source::Fetched))
+    }
+
+    /// Write freshly fetched data, applying the `OnWriteError` policy
+    fn put_fetched(&self, value: &T) -> Result<(), ToteError>
+    where
+        T: Serialize,
+    {
+        self.on_write_result(self.put(value))
+    }
+
+    /// Apply the `OnWriteError` policy to the result of writing fetched
+    /// data, reporting ignored errors (to `tracing`)
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    fn on_write_result(&self, res: Result<(), ToteError>) -> Result<(), ToteError> {
+        match res {
+            Err(error) if self.on_write_error == OnWriteError::ReturnData => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(name: "cache.write_failed", path = %self.path.display(), %error);
+                Ok(())
+            }
+            res => res,
+        }
+    }
+
+    #[cfg(any(feature = "async", feature = "async-native"))]
+    /// Write freshly fetched data like `put_fetched`, or in the background
+    /// if `async_writes` is enabled
+    async fn put_fetched_async(&self, value: &T) -> Result<(), ToteError>
+    where
+        T: Serialize,
+    {
+        let Some(spawn_write) = self.spawn_write else {
+            return self.on_write_result(self.put_async(value).await);
+        };
+        self.forget();
+        let handle = spawn_write(self, self.serialize(value)?.0);
+        let mut pending = self.pending_writes();
+        // Finished writes have already recorded any error
+        pending.handles.retain(|handle| !handle.is_finished());
+        pending.handles.push(handle);
+        Ok(())
+    }
+
+    /// Read expired data if it was written within `min_refresh_interval`,
+    /// by its embedded fetch time if enveloped
+    fn read_rate_limited(&self) -> Option<T>
+    where
+        T: DeserializeOwned,
+    {
+        let interval = self.min_refresh_interval?;
+        let (data, stamp) = self.read_timestamped(&self.path).ok()?;
+        let age = match stamp {
+            Some(stamp) => age_at(stamp.fetched_at, self.now()),
+            // Reads move the modified time with `sliding_expiration`
+            None if self.sliding_expiration => age_at(read_written_at(&self.path)?, self.now()),
+            None => self.age_of(&self.path)?,
+        };
+        (age < interval).then_some(data)
+    }
+
+    /// Modified time of the file at `path`, if it is valid
+    fn valid_modified(&self, path: &Path) -> Option<SystemTime> {
+        if let Some(backend) = &self.backend {
+            self.check_backend_options().ok()?;
+            let modified = backend.modified(path).ok()?;
+            let fresh = self.ttl_from.is_some()
+                || self.enveloped()
+                || within_max_age(self.age_of(path), self.graced(self.max_age));
+            return fresh.then_some(modified);
+        }
+        let metadata = fs::metadata(path).ok()?;
+        // A zero-byte file (e.g. interrupted write or `touch`) can never parse
+        if metadata.len() == 0 || metadata.is_dir() {
+            return None;
+        }
+        let modified = metadata.modified().ok()?;
+        if let Some(generation) = &self.generation {
+            if read_generation(&sidecar_path(path, "gen")) < read_generation(generation) {
+                return None;
+            }
+        }
+        let fresh = if let Some(policy) = &self.expiry_policy {
+            policy.is_fresh(modified, self.now(), &metadata)
+        } else {
+            // Per-value & embedded timestamp expiry is checked once the data is deserialized
+            self.ttl_from.is_some()
+                || self.enveloped()
+                || self
+                    .graced(self.base_max_age(path))
+                    .is_fresh(modified, self.now(), &metadata)
+        };
+        fresh.then_some(modified)
+    }
+
+    /// Check the cache filepath isn't a directory, unless using a `Backend`
+    fn check_path(&self) -> Result<(), ToteError> {
+        if self.backend.is_some() {
+            return self.check_backend_options();
+        }
+        reject_dir(&self.path)
+    }
+
+    /// Reject filesystem-only options, which a `Backend` can't honor
+    fn check_backend_options(&self) -> Result<(), ToteError> {
+        let unsupported = [
+            (
+                self.expiry_policy.is_some(),
+                "`with_backend` doesn't support expiry policies",
+            ),
+            (
+                self.generation.is_some(),
+                "`with_backend` doesn't support generation files",
+            ),
+            (
+                self.mirror.is_some() || !self.candidates.is_empty(),
+                "`with_backend` doesn't support mirrors or candidates",
+            ),
+            (
+                self.lock_fetches,
+                "`with_backend` doesn't support lock files",
+            ),
+            (
+                self.adaptive_ttl.is_some(),
+                "`with_backend` doesn't support adaptive TTLs",
+            ),
+            (
+                self.sliding_expiration,
+                "`with_backend` doesn't support sliding expiration",
+            ),
+            (
+                self.quarantine.is_some(),
+                "`with_backend` doesn't support quarantining",
+            ),
+            (
+                self.tempfile_dir.is_some()
+                    || self.tempfile_affixes.is_some()
+                    || self.open_options.is_some(),
+                "`with_backend` doesn't support temp file or open options",
+            ),
+            #[cfg(feature = "sha256")]
+            (
+                self.hash_sidecar,
+                "`with_backend` doesn't support hash sidecars",
+            ),
+            #[cfg(feature = "mmap")]
+            (
+                self.memory_mapped,
+                "`with_backend` doesn't support memory mapping",
+            ),
+        ];
+        match unsupported.into_iter().find(|(set, _)| *set) {
+            Some((_, reason)) => Err(ToteError::InvalidOptions(reason)),
+            None => Ok(()),
+        }
+    }
+
+    /// Time elapsed since the cache contents at `path` were written
+    fn age_of(&self, path: &Path) -> Option<Duration> {
+        match &self.backend {
+            Some(backend) => Some(age_at(backend.modified(path).ok()?, self.now())),
+            None => file_age(path, self.now()),
+        }
+    }
+
+    /// `max_age` extended by the `with_grace` window
+    fn graced(&self, max_age: Duration) -> Duration {
+        if self.grace.is_zero() {
+            // Keep a zero `max_age` always expired
+            return max_age;
+        }
+        max_age.saturating_add(self.grace)
+    }
+
+    /// Expiry age of the cache file at `path`, from `adaptive_ttl` if set
+    fn base_max_age(&self, path: &Path) -> Duration {
+        match &self.adaptive_ttl {
+            Some(ttl) => ttl(read_fetch_duration(path).unwrap_or_default()),
+            None => self.max_age,
+        }
+    }
+
+    /// Path of the newest valid file to read cached data from
+    fn read_path(&self) -> Option<&Path> {
+        // Candidates are listed first so the primary path wins any ties
+        self.candidates
+            .iter()
+            .chain(std::iter::once(&self.path))
+            .filter_map(|path| self.valid_modified(path).map(|modified| (modified, path)))
+            .max_by_key(|(modified, _)| *modified)
+            .map(|(_, path)| path.as_path())
+            .or_else(|| {
+                self.mirror
+                    .as_deref()
+                    .filter(|mirror| self.valid_modified(mirror).is_some())
+            })
+    }
+}
+
+impl<T: SizeHint, C> Tote<T, C> {
+    /// Pre-allocate the serialization buffer in `put` using `T`'s `SizeHint`,
+    /// reducing reallocations for large payloads
+    pub fn with_size_hint(mut self) -> Self {
+        self.size_hint = Some(T::size_hint);
+        self
+    }
+}
+
+impl<T, C: Codec> Tote<T, C>
+where
+    T: DeserializeOwned,
+    T: PartialEq,
+{
+    /// Re-read and deserialize the cache file after each write, returning
+    /// `ToteError::WriteVerificationFailed` if it doesn't equal the written value
+    ///
+    /// This catches filesystem & serialization asymmetries at write time,
+    /// at the cost of an extra read on every write
+    pub fn verify_after_write(mut self, enabled: bool) -> Self {
+        self.verify = if enabled {
+            Some(Self::verify_written)
+        } else {
+            None
+        };
+        self
+    }
+
+    fn verify_written(&self, value: &T) -> Result<(), ToteError> {
+        let contents = self.read_bytes_from(&self.path)?;
+        match self.decode::<T>(&contents) {
+            Ok((written, _)) if &written == value => Ok(()),
+            _ => Err(ToteError::WriteVerificationFailed),
+        }
+    }
+}
+
+/// Report a failed best-effort mirror write (to `tracing`)
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+fn mirror_failed(mirror: &Path, error: &io::Error) {
+    #[cfg(feature = "tracing")]
+    tracing::warn!(name: "mirror.write_failed", path = %mirror.display(), %error);
+}
+
+/// Map a `NotFound` error (a missing cache file) to `Ok(None)`
+fn unless_not_found<V>(res: io::Result<V>) -> Result<Option<V>, ToteError> {
+    match res {
+        Ok(value) => Ok(Some(value)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Returns `ToteError::InvalidPath` if `path` is an existing directory,
+/// rather than the OS error from reading or writing it
+fn reject_dir(path: &Path) -> Result<(), ToteError> {
+    if path.is_dir() {
+        return Err(ToteError::InvalidPath {
+            path: path.to_owned(),
+            reason: "path is a directory",
+        });
+    }
+    Ok(())
+}
+
+/// Is data of `age` (None if unknown) fresh for `max_age`
+///
+/// Inclusive of `max_age` itself. `Duration::MAX` never expires, and
+/// `Duration::ZERO` is always expired (regardless of timer resolution)
+fn within_max_age(age: Option<Duration>, max_age: Duration) -> bool {
+    match max_age {
+        Duration::MAX => true,
+        Duration::ZERO => false,
+        _ => age.is_some_and(|age| age <= max_age),
+    }
+}
+
+/// Time elapsed since the file at `path` was modified
+fn file_age(path: &Path, now: SystemTime) -> Option<Duration> {
+    Some(age_at(fs::metadata(path).ok()?.modified().ok()?, now))
+}
+
+/// Age at `now` of data written at `written`
+///
+/// A `written` time in the future (e.g. from clock skew, or files copied
+/// from another machine) is an age of zero, rather than expired
+fn age_at(written: SystemTime, now: SystemTime) -> Duration {
+    now.duration_since(written).unwrap_or_default()
+}
+
+/// Leading bytes of the header line written by `Tote::with_checksum`
+const CHECKSUM_PREFIX: &[u8] = b"crc32:";
+
+/// Length of the checksum header line, the prefix + 8 hex digits + newline
+const CHECKSUM_HEADER_LEN: usize = CHECKSUM_PREFIX.len() + 9;
+
+/// Prefix `data` with a header line holding its CRC-32
+fn add_checksum(data: &[u8]) -> Vec<u8> {
+    let mut checked = Vec::with_capacity(CHECKSUM_HEADER_LEN + data.len());
+    checked.extend_from_slice(CHECKSUM_PREFIX);
+    checked.extend_from_slice(format!("{:08x}\n", crc32(data)).as_bytes());
+    checked.extend_from_slice(data);
+    checked
+}
+
+/// Verify & strip the header line added by `add_checksum`, returning
+/// `ToteError::InvalidCache` if it's missing or doesn't match
+fn strip_checksum(contents: &[u8]) -> Result<&[u8], ToteError> {
+    let (header, data) = contents
+        .split_at_checked(CHECKSUM_HEADER_LEN)
+        .ok_or(ToteError::InvalidCache)?;
+    let expected = header
+        .strip_prefix(CHECKSUM_PREFIX)
+        .and_then(|hex| hex.strip_suffix(b"\n"))
+        .and_then(|hex| std::str::from_utf8(hex).ok())
+        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+        .ok_or(ToteError::InvalidCache)?;
+    if crc32(data) != expected {
+        return Err(ToteError::InvalidCache);
+    }
+    Ok(data)
+}
+
+/// CRC-32 (IEEE) of `data`
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+/// 64-bit FNV-1a hash, stable across platforms & Rust versions
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Time from seconds since the Unix epoch
+fn unix_time(secs: u64) -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+/// Create the parent directory of `path` (and its ancestors) if missing
+fn create_parent_dir(path: &Path) -> io::Result<()> {
+    let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    else {
+        return Ok(());
+    };
+    if parent.exists() && !parent.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotADirectory,
+            format!("cache directory {} is not a directory", parent.display()),
+        ));
+    }
+    fs::create_dir_all(parent)
+}
+
+/// Move the file at `path` into `dir` with a unique, timestamped name
+fn quarantine(path: &Path, dir: &Path) -> io::Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    let timestamp = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let mut destination = dir.join(format!("{}.{}-{}", name, timestamp, std::process::id()));
+    for n in 1.. {
+        if !destination.exists() {
+            break;
+        }
+        destination = dir.join(format!(
+            "{}.{}-{}.{}",
+            name,
+            timestamp,
+            std::process::id(),
+            n
+        ));
+    }
+    if fs::rename(path, &destination).is_err() {
+        // Likely a different filesystem, fall back to copying
+        fs::copy(path, &destination)?;
+        fs::remove_file(path)?;
+    }
+    Ok(destination)
+}
+
+/// Maximum nesting depth of arrays/objects in (possibly invalid) JSON
+fn json_depth(data: &[u8]) -> usize {
+    let (mut depth, mut max) = (0usize, 0);
+    let (mut in_string, mut escaped) = (false, false);
+    for &byte in data {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match byte {
+            b'"' => in_string = true,
+            b'[' | b'{' => {
+                depth += 1;
+                max = max.max(depth);
+            }
+            b']' | b'}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    max
+}
+
+/// Run an I/O operation up to `attempts` times, backing off between
+/// failures other than `NotFound`
+fn retry<R>(attempts: u32, mut op: impl FnMut() -> io::Result<R>) -> io::Result<R> {
+    let mut attempt = 1;
+    loop {
+        match op() {
+            Err(e) if attempt < attempts && e.kind() != io::ErrorKind::NotFound => {
+                std::thread::sleep(Duration::from_millis(10) * attempt);
+                attempt += 1;
+            }
+            res => return res,
+        }
+    }
+}
+
+/// Async version of `retry`
+#[cfg(any(feature = "async", feature = "async-native"))]
+async fn retry_async<R, F>(attempts: u32, mut op: impl FnMut() -> F) -> io::Result<R>
+where
+    F: std::future::Future<Output = io::Result<R>>,
+{
+    let mut attempt = 1;
+    loop {
+        match op().await {
+            Err(e) if attempt < attempts && e.kind() != io::ErrorKind::NotFound => {
+                tokio::time::sleep(Duration::from_millis(10) * attempt).await;
+                attempt += 1;
+            }
+            res => return res,
+        }
+    }
+}
+
+/// Set the modified time of the file at `path`
+fn touch(path: &Path, time: SystemTime) -> io::Result<()> {
+    fs::File::options()
+        .write(true)
+        .open(path)?
+        .set_modified(time)
+}
+
+/// Open (creating if needed) & exclusively lock the file at `path`,
+/// released when the returned file is dropped
+fn lock_file(path: &Path) -> io::Result<fs::File> {
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path)?;
+    file.lock()?;
+    Ok(file)
+}
+
+/// Fetch duration recorded in the `<path>.fetch` sidecar file
+fn read_fetch_duration(path: &Path) -> Option<Duration> {
+    let secs = fs::read_to_string(sidecar_path(path, "fetch")).ok()?;
+    Duration::try_from_secs_f64(secs.trim().parse().ok()?).ok()
+}
+
+/// Write time recorded in the `<path>.written` sidecar file
+fn read_written_at(path: &Path) -> Option<SystemTime> {
+    let secs = fs::read_to_string(sidecar_path(path, "written")).ok()?;
+    Some(unix_time(secs.trim().parse().ok()?))
+}
+
+/// Generation stored in the file at `path`, 0 if missing or invalid
+fn read_generation(path: &Path) -> u64 {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|generation| generation.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+/// Unique temp file path for writing `path`, as `<path>.tmp-<pid>-<n>`
+/// (or `<prefix><file name>-<pid>-<n><suffix>` if affixes are given),
+/// in `dir` if given
+fn temp_path(path: &Path, dir: Option<&Path>, affixes: Option<(&str, &str)>) -> PathBuf {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // Distinguishes concurrent writes from threads of the same process
+    static WRITES: AtomicUsize = AtomicUsize::new(0);
+    let n = WRITES.fetch_add(1, Ordering::Relaxed);
+    let unique = format!("{}-{}", std::process::id(), n);
+    let tmp = match affixes {
+        Some((prefix, suffix)) => {
+            let mut name = std::ffi::OsString::from(prefix);
+            name.push(path.file_name().unwrap_or_default());
+            name.push(format!("-{}{}", unique, suffix));
+            path.with_file_name(name)
+        }
+        None => sidecar_path(path, &format!("tmp-{}", unique)),
+    };
+    match (dir, tmp.file_name()) {
+        (Some(dir), Some(name)) => dir.join(name),
+        _ => tmp,
+    }
+}
+
+/// Path of a sidecar file stored next to `path`, e.g. `<path>.sha256`
+fn sidecar_path(path: &Path, extension: &str) -> PathBuf {
+    let mut sidecar = path.as_os_str().to_owned();
+    sidecar.push(".");
+    sidecar.push(extension);
+    PathBuf::from(sidecar)
+}
+
+#[cfg(feature = "sha256")]
+fn sha256_hex(data: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Write a `sha256sum` formatted sidecar for the cache file at `path`
+#[cfg(feature = "sha256")]
+fn write_sha256(path: &Path, data: &[u8]) -> io::Result<()> {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    fs::write(
+        sidecar_path(path, "sha256"),
+        format!("{}  {}\n", sha256_hex(data), name),
+    )
+}
+
+/// Verify the cache file contents against its `sha256sum` formatted sidecar
+#[cfg(feature = "sha256")]
+fn verify_sha256(path: &Path, data: &[u8]) -> Result<(), ToteError> {
+    let sidecar =
+        fs::read_to_string(sidecar_path(path, "sha256")).map_err(|_| ToteError::InvalidCache)?;
+    match sidecar.split_whitespace().next() {
+        Some(digest) if digest == sha256_hex(data) => Ok(()),
+        _ => Err(ToteError::InvalidCache),
+    }
+}
+
+/// Leading bytes of gzip-compressed data
+#[cfg(feature = "gzip")]
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+#[cfg(feature = "gzip")]
+fn gzip(data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(data)?;
+    encoder.finish()
+}
+
+/// Decompress gzip data, errors are returned as `ToteError::Codec` so
+/// corrupt cache files are treated like undecodable ones
+#[cfg(feature = "gzip")]
+fn gunzip(data: &[u8]) -> Result<Vec<u8>, ToteError> {
+    use std::io::Read;
+
+    let mut contents = Vec::new();
+    flate2::read::GzDecoder::new(data)
+        .read_to_end(&mut contents)
+        .map_err(|e| ToteError::Codec(e.into()))?;
+    Ok(contents)
+}
+
+#[cfg(any(feature = "async", feature = "async-native"))]
+impl<T: Send + 'static, C: Codec> Tote<T, C> {
+    /// Write fetched data in a background task, so async `get`s return
+    /// without waiting on disk I/O
+    ///
+    /// Write errors can't be returned to the caller, they're reported as
+    /// `cache.write_failed` `tracing` warnings (with the `tracing` feature)
+    /// as they happen. Use `flush_writes` to await pending writes (and their
+    /// errors). `verify_after_write` isn't applied to background writes
+    pub fn async_writes(mut self, enabled: bool) -> Self {
+        self.spawn_write = if enabled {
+            Some(Self::spawn_write)
+        } else {
+            None
+        };
+        self
+    }
+
+    fn spawn_write(&self, data: Vec<u8>) -> tokio::task::JoinHandle<()> {
+        let tote = self.clone();
+        tokio::task::spawn_blocking(move || {
+            if let Err(error) = tote.write_bytes(&data) {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(name: "cache.write_failed", path = %tote.path.display(), %error);
+                tote.pending_writes().error.get_or_insert(error);
+            }
+        })
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T, C: Codec> Tote<T, C>
+where
+    T: DeserializeOwned,
+    T: AsyncFetch<Cached = T> + Send + 'static,
+{
+    /// Start fetching the data in a background task right away if the cache
+    /// is missing or expired, so it is likely ready by the time `get_async`
+    /// is called. `get_async` awaits the in-flight fetch rather than
+    /// starting another
+    ///
+    /// The background fetch is aborted if this `Tote` is dropped first.
+    /// Must be called from within a `tokio` runtime
+    pub fn with_startup_prefetch(self) -> Self {
+        if !self.is_valid() {
+            let handle = tokio::spawn(async { T::fetch_async().await.map_err(|e| e.to_string()) });
+            if let Ok(mut prefetch) = self.prefetch.lock() {
+                *prefetch = Some(PendingFetch(handle));
+            }
+        }
+        self
+    }
+
+    /// Fetch the cached data, immediately returning expired (but readable)
+    /// cached data while re-fetching it in a background task
+    ///
+    /// The returned data may be stale! The background refresh is best-effort,
+    /// fetching & writing like `get_async` but ignoring errors (except for
+    /// reporting them to hooks), and only one runs at a time. Falls back to
+    /// `get_async` if the cache file is missing or unreadable. Must be called
+    /// from within a `tokio` runtime
+    pub async fn get_stale_while_revalidate(&self) -> Result<T, ToteError>
+    where
+        T: Serialize + Sync,
+    {
+        if self.freshness() == Freshness::Fresh {
+            if let Ok(data) = self.read_async().await {
+                return Ok(data);
+            }
+        }
+        let Ok(stale) = self.read_from(&self.path) else {
+            return self.get_async().await;
+        };
+        let mut revalidation = self.revalidation.lock().unwrap_or_else(|e| e.into_inner());
+        if revalidation.as_ref().is_none_or(|task| task.is_finished()) {
+            let tote = self.clone();
+            *revalidation = Some(tokio::spawn(async move {
+                let _ = tote
+                    .fetch_and_put_async(tote.fetch_async_bounded(T::fetch_async))
+                    .await;
+            }));
+        }
+        Ok(stale)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_derive::{Deserialize, Serialize};
+    use tempfile::NamedTempFile;
+
+    #[cfg(feature = "async")]
+    use async_trait::async_trait;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct TestData {
+        name: String,
+        value: u8,
+    }
+
+    impl Fetch for TestData {
+        type Cached = TestData;
+
+        fn fetch() -> Result<TestData, Box<dyn std::error::Error>> {
+            Ok(TestData {
+                name: "Test".to_owned(),
+                value: 50,
+            })
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        use std::sync::atomic::{AtomicU64, Ordering};
+
+        let file = NamedTempFile::new().unwrap();
+        // Clock which can be advanced past expiry without sleeping
+        let offset_ms = Arc::new(AtomicU64::new(0));
+        let offset = offset_ms.clone();
+        let cache: Tote<TestData> =
+            Tote::new(file.path(), Duration::from_millis(300)).with_clock(move || {
+                SystemTime::now() + Duration::from_millis(offset.load(Ordering::Relaxed))
+            });
+
+        // Stage cached data
+        cache
+            .put(&TestData {
+                name: "Test".to_owned(),
+                value: 50,
+            })
+            .unwrap();
+
+        assert!(cache.is_valid());
+        let res = cache.get().unwrap();
+        assert!(cache.is_valid());
+        assert_eq!(res.name, "Test".to_owned());
+        assert_eq!(res.value, 50);
+
+        offset_ms.store(305, Ordering::Relaxed);
+        assert!(!cache.is_valid());
+    }
+
+    #[test]
+    fn test_expiry_boundary() {
+        use std::sync::atomic::{AtomicU64, Ordering};
+
+        let file = NamedTempFile::new().unwrap();
+        let written = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let max_age = Duration::from_millis(300);
+        let offset_ns = Arc::new(AtomicU64::new(0));
+        let offset = offset_ns.clone();
+        let cache: Tote<TestData> = Tote::new(file.path(), max_age).with_clock(move || {
+            written + max_age + Duration::from_nanos(offset.load(Ordering::Relaxed))
+        });
+        cache
+            .put(&TestData {
+                name: "Cached".to_owned(),
+                value: 1,
+            })
+            .unwrap();
+        touch(file.path(), written).unwrap();
+
+        // Exactly `max_age` old is still valid
+        assert!(cache.is_valid());
+        assert_eq!(cache.age().unwrap(), Some(max_age));
+        assert_eq!(cache.read().unwrap().value, 1);
+
+        offset_ns.store(1, Ordering::Relaxed);
+        assert!(!cache.is_valid());
+        assert!(matches!(cache.read(), Err(ToteError::InvalidCache)));
+        assert_eq!(cache.get().unwrap().value, 50);
+    }
+
+    #[test]
+    fn test_grace() {
+        use std::sync::atomic::{AtomicU64, Ordering};
+
+        let file = NamedTempFile::new().unwrap();
+        let offset_secs = Arc::new(AtomicU64::new(0));
+        let offset = offset_secs.clone();
+        let cache: Tote<TestData> = Tote::new(file.path(), Duration::from_secs(60))
+            .with_grace(Duration::from_secs(30))
+            .with_clock(move || {
+                SystemTime::now() + Duration::from_secs(offset.load(Ordering::Relaxed))
+            });
+        assert_eq!(cache.freshness(), Freshness::Expired);
+        let cached = TestData {
+            name: "Cached".to_owned(),
+            value: 1,
+        };
+        cache.put(&cached).unwrap();
+        assert_eq!(cache.freshness(), Freshness::Fresh);
+        assert_eq!(cache.get().unwrap(), cached);
+
+        // Past `max_age`, but served without fetching
+        offset_secs.store(70, Ordering::Relaxed);
+        assert_eq!(cache.freshness(), Freshness::Stale);
+        assert!(cache.is_valid());
+        assert_eq!(cache.get().unwrap(), cached);
+
+        // Past the grace window
+        offset_secs.store(100, Ordering::Relaxed);
+        assert_eq!(cache.freshness(), Freshness::Expired);
+        assert!(!cache.is_valid());
+        assert_eq!(cache.get().unwrap().value, 50);
+    }
+
+    #[test]
+    fn test_empty_file_is_invalid() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> = Tote::new(file.path(), Duration::from_secs(60));
+        assert!(!cache.is_valid());
+    }
+
+    #[test]
+    fn test_old_empty_file() {
+        let file = NamedTempFile::new().unwrap();
+        touch(file.path(), SystemTime::now() - Duration::from_secs(3600)).unwrap();
+        // Invalid for being empty, whether or not it's expired
+        for max_age in [Duration::from_secs(60), Duration::MAX] {
+            let cache: Tote<TestData> = Tote::new(file.path(), max_age);
+            assert!(!cache.is_valid());
+            assert!(matches!(cache.read(), Err(ToteError::InvalidCache)));
+            assert!(matches!(
+                cache.read_from(file.path()),
+                Err(ToteError::InvalidCache)
+            ));
+            assert_eq!(cache.peek().unwrap(), None);
+        }
+
+        // Including from a backend, which doesn't check the file size
+        let backend = MemoryBackend::new();
+        backend.write_bytes(file.path(), b"").unwrap();
+        let cache: Tote<TestData> = Tote::persistent(file.path()).with_backend(backend);
+        assert!(matches!(cache.read(), Err(ToteError::InvalidCache)));
+        assert_eq!(cache.get().unwrap().value, 50);
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn test_get_bytes() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> = Tote::new(file.path(), Duration::from_secs(60));
+        cache
+            .put(&TestData {
+                name: "Test".to_owned(),
+                value: 50,
+            })
+            .unwrap();
+
+        let bytes = cache.get_bytes().unwrap();
+        assert_eq!(&bytes[..], br#"{"name":"Test","value":50}"#);
+
+        // Clones share the same underlying allocation
+        let shared = bytes.clone();
+        assert_eq!(bytes.as_ptr(), shared.as_ptr());
+    }
+
+    #[test]
+    fn test_deterministic() {
+        use std::collections::HashMap;
+
+        let entries: Vec<_> = (0..32).map(|i| (format!("key{}", i), i)).collect();
+        let first = NamedTempFile::new().unwrap();
+        let second = NamedTempFile::new().unwrap();
+
+        let cache: Tote<HashMap<String, u32>> =
+            Tote::new(first.path(), Duration::from_secs(60)).deterministic(true);
+        cache.put(&entries.iter().cloned().collect()).unwrap();
+        let cache: Tote<HashMap<String, u32>> =
+            Tote::new(second.path(), Duration::from_secs(60)).deterministic(true);
+        cache.put(&entries.iter().rev().cloned().collect()).unwrap();
+
+        assert_eq!(
+            fs::read(first.path()).unwrap(),
+            fs::read(second.path()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_pretty() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> = Tote::new(file.path(), Duration::from_secs(60)).pretty(true);
+        let data = cache.get().unwrap();
+        let written = fs::read_to_string(file.path()).unwrap();
+        assert_eq!(written, serde_json::to_string_pretty(&data).unwrap());
+        assert_eq!(cache.read().unwrap(), data);
+
+        // Compact cache files are still read
+        let cache = cache.pretty(false);
+        assert_eq!(cache.read().unwrap(), data);
+        cache.put(&data).unwrap();
+        assert!(!fs::read_to_string(file.path()).unwrap().contains('\n'));
+    }
+
+    #[test]
+    fn test_expire_now() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> = Tote::new(file.path(), Duration::from_secs(60));
+        cache
+            .put(&TestData {
+                name: "Stale".to_owned(),
+                value: 50,
+            })
+            .unwrap();
+        assert!(cache.is_valid());
+
+        cache.expire_now().unwrap();
+        assert!(!cache.is_valid());
+        // Stale data is still on disk
+        let contents = fs::read_to_string(file.path()).unwrap();
+        let res: TestData = serde_json::from_str(&contents).unwrap();
+        assert_eq!(res.name, "Stale".to_owned());
+
+        // Expiring a missing cache is a no-op
+        let missing: Tote<TestData> = Tote::new(
+            file.path().with_extension("missing"),
+            Duration::from_secs(60),
+        );
+        missing.expire_now().unwrap();
+    }
+
+    #[test]
+    fn test_expire_now_enveloped() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> =
+            Tote::new(file.path(), Duration::from_secs(60)).with_embedded_timestamp(true);
+        let data = cache.get().unwrap();
+        assert!(cache.peek().unwrap().is_some());
+
+        cache.expire_now().unwrap();
+        assert!(cache.peek().unwrap().is_none());
+        assert_eq!(cache.read_from(file.path()).unwrap(), data);
+        let written: serde_json::Value =
+            serde_json::from_slice(&fs::read(file.path()).unwrap()).unwrap();
+        assert_eq!(written["fetched_at"], 0);
+
+        // Backends can't set modified times without an envelope
+        let backend: Tote<TestData> =
+            Tote::new("cache", Duration::from_secs(60)).with_backend(MemoryBackend::new());
+        backend.get().unwrap();
+        assert!(matches!(
+            backend.expire_now(),
+            Err(ToteError::InvalidOptions(_))
+        ));
+        let backend = backend.with_embedded_timestamp(true);
+        backend.get().unwrap();
+        backend.expire_now().unwrap();
+        assert!(backend.peek().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_with_mirror() {
+        let dir = tempfile::tempdir().unwrap();
+        let primary = dir.path().join("primary");
+        let mirror = dir.path().join("mirror");
+
+        let cache: Tote<TestData> =
+            Tote::new(&primary, Duration::from_secs(60)).with_mirror(mirror.clone());
+        cache
+            .put(&TestData {
+                name: "Mirrored".to_owned(),
+                value: 5,
+            })
+            .unwrap();
+        assert!(primary.exists());
+        assert!(mirror.exists());
+
+        // Read falls back to the mirror when the primary is missing
+        fs::remove_file(&primary).unwrap();
+        let res = cache.read().unwrap();
+        assert_eq!(res.name, "Mirrored".to_owned());
+
+        // A failing mirror doesn't fail the primary write
+        let cache: Tote<TestData> = Tote::new(&primary, Duration::from_secs(60))
+            .with_mirror(dir.path().join("missing").join("mirror"));
+        cache
+            .put(&TestData {
+                name: "Primary".to_owned(),
+                value: 6,
+            })
+            .unwrap();
+        assert_eq!(cache.read().unwrap().value, 6);
+    }
+
+    #[test]
+    fn test_with_buffer_size() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<Vec<String>> =
+            Tote::new(file.path(), Duration::from_secs(60)).with_buffer_size(1024 * 1024);
+        assert_eq!(cache.buffer_size, 1024 * 1024);
+
+        let data: Vec<String> = (0..100_000).map(|i| format!("entry-{}", i)).collect();
+        cache.put(&data).unwrap();
+        assert_eq!(cache.read().unwrap(), data);
+    }
+
+    #[test]
+    fn test_clone_with_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache: Tote<TestData> = Tote::new(dir.path().join("first"), Duration::from_secs(42))
+            .deterministic(true)
+            .with_buffer_size(1024);
+        let derived = cache.clone_with_path(dir.path().join("second"));
+
+        assert_eq!(derived.path, dir.path().join("second"));
+        assert_eq!(derived.max_age, Duration::from_secs(42));
+        assert!(derived.deterministic);
+        assert_eq!(derived.buffer_size, 1024);
+        assert_eq!(cache.path, dir.path().join("first"));
+    }
+
+    #[test]
+    fn test_set_path_and_max_age() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut cache: Tote<TestData> =
+            Tote::new(dir.path().join("first"), Duration::from_secs(60));
+        cache.get().unwrap();
+        assert!(cache.is_valid());
+
+        cache.set_path(dir.path().join("second"));
+        assert_eq!(cache.path(), dir.path().join("second"));
+        assert!(!cache.is_valid());
+        assert!(cache.peek().unwrap().is_none());
+        cache.get().unwrap();
+        assert!(dir.path().join("second").exists());
+
+        cache.set_max_age(Duration::ZERO);
+        assert_eq!(cache.max_age(), Duration::ZERO);
+        assert!(!cache.is_valid());
+    }
+
+    /// Serializes a different value than it holds, simulating a corrupt write
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Skewed(u8);
+
+    impl serde::Serialize for Skewed {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_u8(self.0 + 1)
+        }
+    }
+
+    #[test]
+    fn test_verify_after_write() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> =
+            Tote::new(file.path(), Duration::from_secs(60)).verify_after_write(true);
+        cache
+            .put(&TestData {
+                name: "Verified".to_owned(),
+                value: 1,
+            })
+            .unwrap();
+
+        let cache: Tote<Skewed> =
+            Tote::new(file.path(), Duration::from_secs(60)).verify_after_write(true);
+        assert!(matches!(
+            cache.put(&Skewed(1)),
+            Err(ToteError::WriteVerificationFailed)
+        ));
+    }
+
+    #[test]
+    fn test_strict_serialization() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Reading {
+            sensor: String,
+            value: f64,
+        }
+
+        let file = NamedTempFile::new().unwrap();
+        let nan = Reading {
+            sensor: "temp".to_owned(),
+            value: f64::NAN,
+        };
+        // Written as `null`, failing to deserialize on read
+        let cache: Tote<Reading> = Tote::new(file.path(), Duration::from_secs(60));
+        cache.put(&nan).unwrap();
+        assert!(matches!(cache.read(), Err(ToteError::Parse(_))));
+
+        let cache: Tote<Reading> =
+            Tote::new(file.path(), Duration::from_secs(60)).strict_serialization(true);
+        fs::write(file.path(), "").unwrap();
+        assert!(matches!(cache.put(&nan), Err(ToteError::Serde(_))));
+        // Nothing is written
+        assert_eq!(fs::read_to_string(file.path()).unwrap(), "");
+
+        let reading = Reading {
+            sensor: "temp".to_owned(),
+            value: 21.5,
+        };
+        cache.put(&reading).unwrap();
+        assert_eq!(cache.read().unwrap(), reading);
+    }
+
+    #[test]
+    fn test_stream_items() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<Vec<TestData>> = Tote::new(file.path(), Duration::from_secs(60));
+        let data: Vec<TestData> = (0..5000)
+            .map(|i| TestData {
+                name: format!("item, \"{}\"]", i),
+                value: (i % 256) as u8,
+            })
+            .collect();
+        cache.put(&data).unwrap();
+
+        let streamed = cache
+            .stream_items::<TestData>()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(streamed, data);
+
+        // Options streaming would bypass are rejected
+        let cache = cache.with_backend(MemoryBackend::new());
+        assert!(matches!(
+            cache.stream_items::<TestData>(),
+            Err(ToteError::InvalidOptions(_))
+        ));
+        #[cfg(feature = "sha256")]
+        {
+            let cache: Tote<Vec<TestData>> =
+                Tote::new(file.path(), Duration::from_secs(60)).with_hash_sidecar(true);
+            cache.put(&data).unwrap();
+            assert!(matches!(
+                cache.stream_items::<TestData>(),
+                Err(ToteError::InvalidOptions(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_get_with_meta() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> = Tote::new(file.path(), Duration::from_secs(60));
+
+        let (data, meta) = cache.get_with_meta().unwrap();
+        assert_eq!(data.name, "Test");
+        assert_eq!(meta.source, Source::Fetched);
+        assert_eq!(meta.age, Some(Duration::ZERO));
+
+        touch(file.path(), SystemTime::now() - Duration::from_secs(30)).unwrap();
+        let (cached, meta) = cache.get_with_meta().unwrap();
+        assert_eq!(cached, data);
+        assert_eq!(meta.source, Source::Cache);
+        assert!(meta.age.unwrap() >= Duration::from_secs(30));
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_tracing() {
+        use std::sync::Mutex;
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata};
+
+        /// Records the names & fields of events, and names of spans
+        #[derive(Clone, Default)]
+        struct Recorder(Arc<Mutex<Vec<String>>>);
+
+        impl tracing::Subscriber for Recorder {
+            fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, span: &Attributes<'_>) -> Id {
+                let name = span.metadata().name();
+                self.0.lock().unwrap().push(format!("span {}", name));
+                Id::from_u64(1)
+            }
+
+            fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+            fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+            fn event(&self, event: &Event<'_>) {
+                let fields: Vec<_> = event.fields().map(|f| f.name()).collect();
+                let name = event.metadata().name();
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push(format!("{} {}", name, fields.join(",")));
+            }
+
+            fn enter(&self, _span: &Id) {}
+
+            fn exit(&self, _span: &Id) {}
+        }
+
+        let recorder = Recorder::default();
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> = Tote::new(file.path(), Duration::from_secs(60));
+        tracing::subscriber::with_default(recorder.clone(), || {
+            cache.get().unwrap();
+            cache.get().unwrap();
+        });
+        let recorded = recorder.0.lock().unwrap();
+        let events: Vec<_> = recorded.iter().filter(|r| !r.starts_with("span")).collect();
+        assert_eq!(
+            events,
+            [
+                "cache.miss path",
+                "fetch.duration path,duration_ms",
+                "cache.hit path,age"
+            ]
+        );
+        for span in ["span tote.get", "span tote.read", "span tote.put"] {
+            assert!(recorded.iter().any(|r| r == span), "missing {}", span);
+        }
+        drop(recorded);
+
+        // Failed best-effort writes are warned about
+        let dir = tempfile::tempdir().unwrap();
+        let cache: Tote<TestData> = Tote::new(dir.path().join("cache"), Duration::from_secs(60))
+            .with_mirror(dir.path().to_owned());
+        let recorder = Recorder::default();
+        tracing::subscriber::with_default(recorder.clone(), || {
+            cache
+                .put(&TestData {
+                    name: "Mirrored".to_owned(),
+                    value: 1,
+                })
+                .unwrap();
+        });
+        let recorded = recorder.0.lock().unwrap();
+        assert!(recorded.contains(&"mirror.write_failed path,error".to_owned()));
+        drop(recorded);
+
+        let cache: Tote<TestData> =
+            Tote::new(dir.path(), Duration::from_secs(60)).on_write_error(OnWriteError::ReturnData);
+        let recorder = Recorder::default();
+        tracing::subscriber::with_default(recorder.clone(), || {
+            cache.get().unwrap();
+        });
+        let recorded = recorder.0.lock().unwrap();
+        assert!(recorded.contains(&"cache.write_failed path,error".to_owned()));
+    }
+
+    #[test]
+    fn test_get_mapped() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> = Tote::new(file.path(), Duration::from_secs(60));
+        assert_eq!(cache.get_mapped(|data| data.value).unwrap(), 50);
+        assert_eq!(cache.get_mapped(|data| data.name).unwrap(), "Test");
+    }
+
+    #[test]
+    fn test_set() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("seeded.json");
+        let cache: Tote<TestData> = Tote::new(&path, Duration::from_secs(60));
+        let seeded = TestData {
+            name: "Seeded".to_owned(),
+            value: 1,
+        };
+        cache.set(&seeded).unwrap();
+        assert!(cache.is_valid());
+        // Returned without fetching
+        assert_eq!(cache.get().unwrap(), seeded);
+
+        let replaced = TestData {
+            name: "Replaced".to_owned(),
+            value: 2,
+        };
+        cache.set(&replaced).unwrap();
+        assert_eq!(cache.get().unwrap(), replaced);
+    }
+
+    #[test]
+    fn test_get_within() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> = Tote::new(file.path(), Duration::from_secs(60));
+        let cached = TestData {
+            name: "Cached".to_owned(),
+            value: 1,
+        };
+        cache.put(&cached).unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+
+        assert_eq!(cache.get_within(Duration::from_secs(30)).unwrap(), cached);
+        // Re-fetched, though the configured `max_age` hasn't elapsed
+        assert_eq!(
+            cache.get_within(Duration::from_millis(1)).unwrap().value,
+            50
+        );
+        assert_eq!(cache.max_age(), Duration::from_secs(60));
+        assert_eq!(cache.read().unwrap().value, 50);
+    }
+
+    #[test]
+    fn test_get_fresh() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> = Tote::new(file.path(), Duration::from_secs(60));
+
+        let (data, fresh) = cache.get_fresh().unwrap();
+        assert!(fresh);
+        assert_eq!(cache.get_fresh().unwrap(), (data, false));
+
+        cache.expire_now().unwrap();
+        assert!(cache.get_fresh().unwrap().1);
+    }
+
+    #[test]
+    fn test_get_cached_arc() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> =
+            Tote::new(file.path(), Duration::from_secs(60)).with_memory_cache(true);
+        let data = cache.get_cached_arc().unwrap();
+        assert_eq!(data.value, 50);
+
+        // Served from memory, without re-reading the cache file
+        fs::write(file.path(), r#"{"name":"Disk","value":1}"#).unwrap();
+        assert!(Arc::ptr_eq(&cache.get_cached_arc().unwrap(), &data));
+
+        cache.force_refresh().unwrap();
+        assert!(!Arc::ptr_eq(&cache.get_cached_arc().unwrap(), &data));
+
+        let updated = TestData {
+            name: "Updated".to_owned(),
+            value: 2,
+        };
+        cache.put(&updated).unwrap();
+        assert_eq!(*cache.get_cached_arc().unwrap(), updated);
+
+        cache.invalidate().unwrap();
+        assert_eq!(cache.get_cached_arc().unwrap().value, 50);
+
+        // Disabled reads the cache file each time
+        let cache: Tote<TestData> = Tote::new(file.path(), Duration::from_secs(60));
+        fs::write(file.path(), r#"{"name":"Disk","value":1}"#).unwrap();
+        assert_eq!(cache.get_cached_arc().unwrap().value, 1);
+        fs::write(file.path(), r#"{"name":"Disk","value":3}"#).unwrap();
+        assert_eq!(cache.get_cached_arc().unwrap().value, 3);
+    }
+
+    #[test]
+    fn test_reload() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> =
+            Tote::new(file.path(), Duration::from_secs(60)).with_memory_cache(true);
+        assert_eq!(cache.get_cached_arc().unwrap().value, 50);
+
+        // Rewritten by another process
+        fs::write(file.path(), r#"{"name":"Disk","value":1}"#).unwrap();
+        assert_eq!(cache.get_cached_arc().unwrap().value, 50);
+        assert_eq!(cache.reload().unwrap().unwrap().value, 1);
+        assert_eq!(cache.get_cached_arc().unwrap().value, 1);
+
+        // Expired data isn't returned or kept in memory
+        cache.expire_now().unwrap();
+        assert_eq!(cache.reload().unwrap(), None);
+        assert!(cache.remembered().is_none());
+
+        // Without the in-memory layer it's the same as `peek`
+        let cache: Tote<TestData> = Tote::new(file.path(), Duration::from_secs(60));
+        fs::write(file.path(), r#"{"name":"Disk","value":2}"#).unwrap();
+        assert_eq!(cache.reload().unwrap(), cache.peek().unwrap());
+        assert!(cache.remembered().is_none());
+    }
+
+    #[test]
+    fn test_get_opts() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> = Tote::new(file.path(), Duration::from_secs(60));
+        let cached = TestData {
+            name: "Old".to_owned(),
+            value: 1,
+        };
+
+        // Offline with no cache
+        let opts = GetOptions {
+            offline: true,
+            ..Default::default()
+        };
+        assert!(matches!(cache.get_opts(opts), Err(ToteError::InvalidCache)));
+
+        // Fetch without writing
+        let opts = GetOptions {
+            write: false,
+            ..Default::default()
+        };
+        let (res, source) = cache.get_opts(opts).unwrap();
+        assert_eq!(res.name, "Test".to_owned());
+        assert_eq!(source, Source::Fetched);
+        assert!(!cache.is_valid());
+
+        // Offline with cached data
+        cache.put(&cached).unwrap();
+        let opts = GetOptions {
+            offline: true,
+            ..Default::default()
+        };
+        assert_eq!(cache.get_opts(opts).unwrap(), (cached, Source::Cache));
+
+        // Force refresh skips the cached data and writes the fetched data
+        let opts = GetOptions {
+            force_refresh: true,
+            ..Default::default()
+        };
+        let (res, source) = cache.get_opts(opts).unwrap();
+        assert_eq!(res.name, "Test".to_owned());
+        assert_eq!(source, Source::Fetched);
+        assert_eq!(cache.read().unwrap().name, "Test".to_owned());
+
+        // Defaults behave like `get`
+        let (_, source) = cache.get_opts(GetOptions::default()).unwrap();
+        assert_eq!(source, Source::Cache);
+
+        // Conflicting options
+        let opts = GetOptions {
+            force_refresh: true,
+            offline: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            cache.get_opts(opts),
+            Err(ToteError::InvalidOptions(_))
+        ));
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_size_budget() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<Vec<String>> = Tote::new(file.path(), Duration::from_secs(60))
+            .with_size_budget(SizeBudget {
+                soft: 256,
+                hard: 1024,
+            });
+
+        // Under the soft limit, uncompressed
+        let small = vec!["small".to_owned()];
+        cache.put(&small).unwrap();
+        assert_eq!(fs::read(file.path()).unwrap(), b"[\"small\"]");
+
+        // Between the limits, compressed
+        let medium: Vec<String> = (0..100).map(|i| format!("Medium {}", i % 4)).collect();
+        assert!(serde_json::to_vec(&medium).unwrap().len() > 256);
+        cache.put(&medium).unwrap();
+        let written = fs::read(file.path()).unwrap();
+        assert!(written.starts_with(&GZIP_MAGIC));
+        assert!(written.len() <= 1024);
+        assert_eq!(cache.read().unwrap(), medium);
+
+        // Over the hard limit once compressed, not written
+        let large: Vec<String> = (0..200)
+            .map(|i| format!("{:x}", crc32(&[i as u8])))
+            .collect();
+        match cache.put(&large) {
+            Err(ToteError::TooLarge { size, limit: 1024 }) => assert!(size > 1024),
+            res => panic!("expected TooLarge, got {:?}", res),
+        }
+        assert_eq!(cache.read().unwrap(), medium);
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_gzip() {
+        let file = NamedTempFile::new().unwrap();
+        let data: Vec<String> = (0..100)
+            .map(|i| format!("Compressible {}", i % 4))
+            .collect();
+        let raw = serde_json::to_vec(&data).unwrap();
+
+        // Uncompressed caches remain readable with gzip enabled
+        let cache: Tote<Vec<String>> = Tote::new(file.path(), Duration::from_secs(60));
+        cache.put(&data).unwrap();
+        let cache = cache.with_gzip(true);
+        assert_eq!(cache.read().unwrap(), data);
+
+        cache.put(&data).unwrap();
+        let written = fs::read(file.path()).unwrap();
+        assert!(written.starts_with(&GZIP_MAGIC));
+        assert!(written.len() < raw.len());
+        assert_eq!(cache.read().unwrap(), data);
+        let items: Vec<String> = cache
+            .stream_items()
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(items, data);
+
+        // Corrupt compressed data
+        fs::write(file.path(), &written[..written.len() / 2]).unwrap();
+        assert!(matches!(cache.read(), Err(ToteError::Codec(_))));
+    }
+
+    #[cfg(feature = "encrypt")]
+    #[test]
+    fn test_cipher() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> =
+            Tote::new(file.path(), Duration::from_secs(60)).with_cipher(ChaChaCipher::new([1; 32]));
+        let data = cache.get().unwrap();
+        let written = fs::read(file.path()).unwrap();
+        assert!(serde_json::from_slice::<TestData>(&written).is_err());
+        assert_eq!(cache.read().unwrap(), data);
+        assert!(cache.stream_items::<u8>().is_err());
+
+        // Wrong key (or unencrypted data) falls through to a re-fetch
+        let rekeyed = cache.clone().with_cipher(ChaChaCipher::new([2; 32]));
+        assert!(matches!(rekeyed.read(), Err(ToteError::InvalidCache)));
+        assert_eq!(rekeyed.get().unwrap(), data);
+        assert_eq!(rekeyed.read().unwrap(), data);
+        fs::write(file.path(), serde_json::to_vec(&data).unwrap()).unwrap();
+        assert!(matches!(rekeyed.read(), Err(ToteError::InvalidCache)));
+    }
+
+    #[cfg(feature = "sha256")]
+    #[test]
+    fn test_hash_sidecar() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.cache");
+        let sidecar = dir.path().join("data.cache.sha256");
+        let cache: Tote<TestData> =
+            Tote::new(&path, Duration::from_secs(60)).with_hash_sidecar(true);
+        let stage = || {
+            cache
+                .put(&TestData {
+                    name: "Hashed".to_owned(),
+                    value: 1,
+                })
+                .unwrap()
+        };
+
+        stage();
+        assert!(fs::read_to_string(&sidecar)
+            .unwrap()
+            .ends_with("  data.cache\n"));
+        assert_eq!(cache.read().unwrap().name, "Hashed".to_owned());
+
+        // Tampered data
+        fs::write(&path, r#"{"name":"Tamper","value":1}"#).unwrap();
+        assert!(matches!(cache.read(), Err(ToteError::InvalidCache)));
+
+        // Tampered sidecar
+        stage();
+        fs::write(&sidecar, format!("{}  data.cache\n", "0".repeat(64))).unwrap();
+        assert!(matches!(cache.read(), Err(ToteError::InvalidCache)));
+
+        // Missing sidecar
+        stage();
+        fs::remove_file(&sidecar).unwrap();
+        assert!(matches!(cache.read(), Err(ToteError::InvalidCache)));
+    }
+
+    #[test]
+    fn test_sliding_expiration() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> =
+            Tote::new(file.path(), Duration::from_secs(60)).sliding_expiration(true);
+        cache
+            .put(&TestData {
+                name: "Sliding".to_owned(),
+                value: 1,
+            })
+            .unwrap();
+        let age = || {
+            fs::metadata(file.path())
+                .unwrap()
+                .modified()
+                .unwrap()
+                .elapsed()
+                .unwrap()
+        };
+
+        // Each read pushes the expiry back out, even past the original window
+        for _ in 0..3 {
+            fs::File::options()
+                .write(true)
+                .open(file.path())
+                .unwrap()
+                .set_modified(SystemTime::now() - Duration::from_secs(50))
+                .unwrap();
+            cache.read().unwrap();
+            assert!(age() < Duration::from_secs(10));
+        }
+        assert!(cache.is_valid());
+    }
+
+    /// Seed which scales deserialized values by a runtime factor
+    struct Scale(u32);
+
+    impl<'de> DeserializeSeed<'de> for Scale {
+        type Value = u32;
+
+        fn deserialize<D: serde::Deserializer<'de>>(
+            self,
+            deserializer: D,
+        ) -> Result<u32, D::Error> {
+            Ok(u32::deserialize(deserializer)? * self.0)
+        }
+    }
+
+    #[test]
+    fn test_read_seeded() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<u32> = Tote::new(file.path(), Duration::from_secs(60));
+        cache.put(&21).unwrap();
+        assert_eq!(cache.read_seeded(Scale(2)).unwrap(), 42);
+
+        cache.expire_now().unwrap();
+        assert!(matches!(
+            cache.read_seeded(Scale(2)),
+            Err(ToteError::InvalidCache)
+        ));
+    }
+
+    #[test]
+    fn test_on_write_error() {
+        let file = NamedTempFile::new().unwrap();
+        // The parent of this path is a file, so writes always fail
+        let path = file.path().join("unwritable");
+
+        let cache: Tote<TestData> = Tote::new(&path, Duration::from_secs(60));
+        assert!(matches!(cache.get(), Err(ToteError::FileAccess(_))));
+
+        let cache = cache.on_write_error(OnWriteError::ReturnData);
+        let res = cache.get().unwrap();
+        assert_eq!(res.name, "Test".to_owned());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_probe() {
+        let file = NamedTempFile::new().unwrap();
+        let max_age = Duration::from_secs(60);
+        assert_eq!(
+            Tote::<TestData>::probe(file.path(), max_age),
+            CacheStatus::Miss
+        );
+        assert_eq!(
+            Tote::<TestData>::probe(file.path().with_extension("missing"), max_age),
+            CacheStatus::Miss
+        );
+
+        let cache: Tote<TestData> = Tote::new(file.path(), max_age);
+        cache
+            .put(&TestData {
+                name: "Probed".to_owned(),
+                value: 1,
+            })
+            .unwrap();
+        assert_eq!(
+            Tote::<TestData>::probe(file.path(), max_age),
+            CacheStatus::Hit
+        );
+
+        cache.expire_now().unwrap();
+        assert_eq!(
+            Tote::<TestData>::probe(file.path(), max_age),
+            CacheStatus::Expired
+        );
+    }
+
+    #[test]
+    fn test_segmented_by() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache: Tote<TestData> =
+            Tote::new(dir.path().join("colors.cache"), Duration::from_secs(60));
+        let fr = cache.segmented_by("fr");
+        let en = cache.segmented_by("en/US");
+        assert_eq!(fr.path, dir.path().join("colors.fr.cache"));
+        assert_eq!(en.path, dir.path().join("colors.en_US.cache"));
+        // Built keys are already filename-safe
+        let key = KeyBuilder::new().push("en/US").push(2022);
+        let keyed = cache.segmented_by(&key);
+        assert_eq!(keyed.path, dir.path().join(format!("colors.{}.cache", key)));
+
+        fr.put(&TestData {
+            name: "Rouge".to_owned(),
+            value: 1,
+        })
+        .unwrap();
+        en.put(&TestData {
+            name: "Red".to_owned(),
+            value: 2,
+        })
+        .unwrap();
+        assert_eq!(fr.read().unwrap().name, "Rouge".to_owned());
+        assert_eq!(en.read().unwrap().name, "Red".to_owned());
+        assert!(!cache.is_valid());
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Versioned(u8);
+
+    impl FetchConditional for Versioned {
+        type Cached = Versioned;
+
+        fn fetch_conditional(
+            meta: Option<&FetchMeta>,
+        ) -> Result<Conditional<Versioned>, Box<dyn std::error::Error>> {
+            match meta.and_then(|meta| meta.etag.as_deref()) {
+                Some("v1") => Ok(Conditional::Unchanged),
+                _ => Ok(Conditional::Changed(
+                    Versioned(1),
+                    FetchMeta {
+                        etag: Some("v1".to_owned()),
+                        last_modified: None,
+                    },
+                )),
+            }
+        }
+    }
+
+    #[test]
+    fn test_get_conditional() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("versioned.cache");
+        let cache: Tote<Versioned> = Tote::new(&path, Duration::from_secs(60));
+
+        // Changed, data & meta are written
+        assert_eq!(cache.get_conditional().unwrap(), Versioned(1));
+        let meta: FetchMeta =
+            serde_json::from_slice(&fs::read(dir.path().join("versioned.cache.meta")).unwrap())
+                .unwrap();
+        assert_eq!(meta.etag.as_deref(), Some("v1"));
+
+        // Unchanged, stale data is kept & expiry is reset
+        cache.put(&Versioned(2)).unwrap();
+        cache.expire_now().unwrap();
+        assert_eq!(cache.get_conditional().unwrap(), Versioned(2));
+        assert!(cache.is_valid());
+
+        // Enveloped data's fetch time is reset
+        let enveloped = cache.clone().with_embedded_timestamp(true);
+        enveloped.put(&Versioned(3)).unwrap();
+        enveloped.expire_now().unwrap();
+        assert!(enveloped.peek().unwrap().is_none());
+        assert_eq!(enveloped.get_conditional().unwrap(), Versioned(3));
+        assert_eq!(enveloped.peek().unwrap(), Some(Versioned(3)));
+        let files = fs::read_dir(dir.path()).unwrap().count();
+        assert_eq!(files, 2);
+
+        let backend = cache.with_backend(MemoryBackend::new());
+        assert!(matches!(
+            backend.get_conditional(),
+            Err(ToteError::InvalidOptions(_))
+        ));
+    }
+
+    #[test]
+    fn test_try_new() {
+        let dir = tempfile::tempdir().unwrap();
+        let max_age = Duration::from_secs(60);
+
+        assert!(Tote::<TestData>::try_new(dir.path().join("data.cache"), max_age).is_ok());
+        assert!(Tote::<TestData>::try_new(dir.path().join("sub/data.cache"), max_age).is_ok());
+        assert!(matches!(
+            Tote::<TestData>::try_new(dir.path(), max_age),
+            Err(ToteError::InvalidPath { .. })
+        ));
+        assert!(matches!(
+            Tote::<TestData>::try_new("", max_age),
+            Err(ToteError::InvalidPath { .. })
+        ));
+
+        let file = NamedTempFile::new().unwrap();
+        assert!(matches!(
+            Tote::<TestData>::try_new(file.path().join("data.cache"), max_age),
+            Err(ToteError::InvalidPath { .. })
+        ));
+
+        // An existing file is probed without changing it
+        fs::write(file.path(), "[1]").unwrap();
+        assert!(Tote::<TestData>::try_new(file.path(), max_age).is_ok());
+        assert_eq!(fs::read_to_string(file.path()).unwrap(), "[1]");
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct SelfExpiring {
+        valid_for_seconds: u64,
+    }
+
+    #[test]
+    fn test_with_ttl_from() {
+        let file = NamedTempFile::new().unwrap();
+        // `max_age` is overridden by the per-value TTL
+        let cache: Tote<SelfExpiring> = Tote::new(file.path(), Duration::from_secs(1))
+            .with_ttl_from(|data: &SelfExpiring| Duration::from_secs(data.valid_for_seconds));
+        let stage = |valid_for_seconds| {
+            cache.put(&SelfExpiring { valid_for_seconds }).unwrap();
+            touch(file.path(), SystemTime::now() - Duration::from_secs(30)).unwrap();
+        };
+
+        stage(60);
+        assert_eq!(cache.read().unwrap().valid_for_seconds, 60);
+
+        assert!(cache.is_valid());
+
+        stage(10);
+        assert!(matches!(cache.read(), Err(ToteError::InvalidCache)));
+        assert!(!cache.is_valid());
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Scheduled {
+        /// Unix seconds
+        starts_at: Option<u64>,
+    }
+
+    #[test]
+    fn test_with_valid_until() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<Scheduled> = Tote::new(file.path(), Duration::from_secs(3600))
+            .with_valid_until(|data: &Scheduled| data.starts_at.map(unix_time));
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        // A future deadline expires by `max_age` as usual
+        cache
+            .put(&Scheduled {
+                starts_at: Some(now + 60),
+            })
+            .unwrap();
+        let written: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(file.path()).unwrap()).unwrap();
+        assert_eq!(written["valid_until"].as_u64(), Some(now + 60));
+        assert!(cache.is_valid());
+        assert!(cache.read().is_ok());
+
+        // A past deadline is expired, even though just written
+        cache
+            .put(&Scheduled {
+                starts_at: Some(now - 1),
+            })
+            .unwrap();
+        assert!(!cache.is_valid());
+        assert!(matches!(cache.read(), Err(ToteError::InvalidCache)));
+
+        // No deadline
+        cache.put(&Scheduled { starts_at: None }).unwrap();
+        assert!(cache.is_valid());
+
+        // Also stored in a versioned envelope
+        let cache: Tote<Scheduled> = Tote::new(file.path(), Duration::from_secs(3600))
+            .with_schema_version(2)
+            .with_valid_until(|data: &Scheduled| data.starts_at.map(unix_time));
+        cache
+            .put(&Scheduled {
+                starts_at: Some(now - 1),
+            })
+            .unwrap();
+        assert!(!cache.is_valid());
+    }
+
+    #[test]
+    fn test_quarantine_to() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.cache");
+        let quarantine_dir = dir.path().join("quarantine");
+        let cache: Tote<TestData> =
+            Tote::new(&path, Duration::from_secs(60)).quarantine_to(&quarantine_dir);
+
+        for _ in 0..2 {
+            fs::write(&path, "corrupt!").unwrap();
+            let res = cache.get().unwrap();
+            assert_eq!(res.name, "Test".to_owned());
+        }
+
+        let quarantined: Vec<_> = fs::read_dir(&quarantine_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(quarantined.len(), 2);
+        for file in quarantined {
+            assert!(file
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .starts_with("data.cache."));
+            assert_eq!(fs::read_to_string(file).unwrap(), "corrupt!");
+        }
+    }
+
+    /// Ignores time entirely
+    struct AlwaysFresh;
+
+    impl ExpiryPolicy for AlwaysFresh {
+        fn is_fresh(&self, _: SystemTime, _: SystemTime, _: &fs::Metadata) -> bool {
+            true
+        }
+    }
+
+    /// Fresh until the start of the next (UTC) hour
+    struct SameHour;
+
+    impl ExpiryPolicy for SameHour {
+        fn is_fresh(&self, created: SystemTime, now: SystemTime, _: &fs::Metadata) -> bool {
+            let hour = |time: SystemTime| {
+                time.duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs()
+                    / 3600
+            };
+            hour(created) == hour(now)
+        }
+    }
+
+    #[test]
+    fn test_with_expiry_policy() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> = Tote::new(file.path(), Duration::from_secs(1));
+        cache
+            .put(&TestData {
+                name: "Policy".to_owned(),
+                value: 1,
+            })
+            .unwrap();
+
+        cache.expire_now().unwrap();
+        assert!(!cache.is_valid());
+        let always = cache.clone().with_expiry_policy(AlwaysFresh);
+        assert!(always.is_valid());
+
+        let same_hour = cache.clone().with_expiry_policy(SameHour);
+        assert!(!same_hour.is_valid());
+        touch(file.path(), SystemTime::now()).unwrap();
+        assert!(same_hour.is_valid());
+
+        // Built-in absolute deadline
+        let deadline = SystemTime::now() + Duration::from_secs(60);
+        assert!(cache.clone().with_expiry_policy(deadline).is_valid());
+        let deadline = SystemTime::now() - Duration::from_secs(60);
+        assert!(!cache.clone().with_expiry_policy(deadline).is_valid());
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Counter(u32);
+
+    impl Fetch for Counter {
+        type Cached = Counter;
+
+        fn fetch() -> Result<Counter, Box<dyn std::error::Error>> {
+            Ok(Counter(0))
+        }
+    }
+
+    #[test]
+    fn test_lock_fetches() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static FETCHES: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Slow(u8);
+
+        impl Fetch for Slow {
+            type Cached = Slow;
+
+            fn fetch() -> Result<Slow, Box<dyn std::error::Error>> {
+                FETCHES.fetch_add(1, Ordering::SeqCst);
+                std::thread::sleep(Duration::from_millis(100));
+                Ok(Slow(1))
+            }
+        }
+
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<Slow> = Tote::new(file.path(), Duration::from_secs(60)).lock_fetches(true);
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                let cache = cache.clone();
+                scope.spawn(move || assert_eq!(cache.get().unwrap(), Slow(1)));
+            }
+        });
+        assert_eq!(FETCHES.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_get_shared() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static FETCHES: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Slow(u8);
+
+        impl Fetch for Slow {
+            type Cached = Slow;
+
+            fn fetch() -> Result<Slow, Box<dyn std::error::Error>> {
+                let fetches = FETCHES.fetch_add(1, Ordering::SeqCst);
+                std::thread::sleep(Duration::from_millis(100));
+                if fetches > 0 {
+                    return Err("unavailable".into());
+                }
+                Ok(Slow(1))
+            }
+        }
+
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<Slow> = Tote::new(file.path(), Duration::from_secs(60));
+        let results: Vec<Arc<Slow>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..8)
+                .map(|_| scope.spawn(|| cache.get_shared().unwrap()))
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        assert_eq!(FETCHES.load(Ordering::SeqCst), 1);
+        assert!(results.iter().all(|data| **data == Slow(1)));
+
+        // A failed fetch is shared too
+        cache.invalidate().unwrap();
+        let errors: Vec<ToteError> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..8)
+                .map(|_| scope.spawn(|| SharedError::from(&cache.get_shared().unwrap_err())))
+                .collect();
+            handles
+                .into_iter()
+                .map(|h| ToteError::from(&h.join().unwrap()))
+                .collect()
+        });
+        assert_eq!(FETCHES.load(Ordering::SeqCst), 2);
+        assert!(errors
+            .iter()
+            .all(|e| e.kind() == ToteErrorKind::Fetching && e.to_string() == "unavailable"));
+
+        // Shared errors keep their kind & message
+        let errors = [
+            ToteError::FileAccess(io::ErrorKind::PermissionDenied.into()),
+            ToteError::InvalidOptions("conflicting"),
+            ToteError::Timeout(Duration::from_secs(1)),
+            ToteError::TooLarge { size: 2, limit: 1 },
+            ToteError::UnreadableCache {
+                path: file.path().to_owned(),
+                read: Box::new(ToteError::InvalidCache),
+                source: Box::new(ToteError::Fetching("unavailable".into())),
+            },
+        ];
+        for e in errors {
+            let shared = ToteError::from(&SharedError::from(&e));
+            assert_eq!(shared.kind(), e.kind());
+            assert_eq!(shared.to_string(), e.to_string());
+        }
+    }
+
+    #[test]
+    fn test_update() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<Counter> = Tote::new(file.path(), Duration::from_secs(60));
+
+        std::thread::scope(|scope| {
+            for _ in 0..2 {
+                let cache = cache.clone();
+                scope.spawn(move || {
+                    for _ in 0..50 {
+                        cache.update(|counter| counter.0 += 1).unwrap();
+                    }
+                });
+            }
+        });
+        assert_eq!(cache.get().unwrap(), Counter(100));
+    }
+
+    #[test]
+    fn test_fetch_retry() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static FETCHES: AtomicUsize = AtomicUsize::new(0);
+
+        /// Fails every fetch but the third
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Flaky(usize);
+
+        impl Fetch for Flaky {
+            type Cached = Flaky;
+
+            fn fetch() -> Result<Flaky, Box<dyn std::error::Error>> {
+                match FETCHES.fetch_add(1, Ordering::SeqCst) + 1 {
+                    3 => Ok(Flaky(3)),
+                    n => Err(format!("transient failure {}", n).into()),
+                }
+            }
+        }
+
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<Flaky> = Tote::new(file.path(), Duration::from_secs(60));
+        assert!(matches!(cache.get(), Err(ToteError::Fetching(_))));
+        assert_eq!(FETCHES.load(Ordering::SeqCst), 1);
+
+        FETCHES.store(0, Ordering::SeqCst);
+        let cache = cache.with_fetch_retry(3, Duration::from_millis(1));
+        assert_eq!(cache.get().unwrap(), Flaky(3));
+        assert_eq!(FETCHES.load(Ordering::SeqCst), 3);
+
+        // The last error is returned once attempts run out
+        cache.invalidate().unwrap();
+        let cache = cache.with_fetch_retry(2, Duration::from_millis(1));
+        match cache.get() {
+            Err(ToteError::Fetching(e)) => assert_eq!(e.to_string(), "transient failure 5"),
+            res => panic!("unexpected {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_read_retry() {
+        // Fails the first read, then succeeds
+        let mut reads = 0;
+        let res = retry(3, || {
+            reads += 1;
+            match reads {
+                1 => Err(io::Error::other("Stale file handle")),
+                _ => Ok(reads),
+            }
+        });
+        assert_eq!(res.unwrap(), 2);
+
+        // Gives up after the configured attempts
+        let mut reads = 0;
+        let res: io::Result<()> = retry(2, || {
+            reads += 1;
+            Err(io::Error::other("Stale file handle"))
+        });
+        assert!(res.is_err());
+        assert_eq!(reads, 2);
+
+        // Missing files aren't retried
+        let mut reads = 0;
+        let res: io::Result<()> = retry(3, || {
+            reads += 1;
+            Err(io::ErrorKind::NotFound.into())
+        });
+        assert!(res.is_err());
+        assert_eq!(reads, 1);
+
+        let file = NamedTempFile::new().unwrap();
+        write!(file.as_file(), r#"{{"name": "Cached", "value": 1}}"#).unwrap();
+        let cache: Tote<TestData> =
+            Tote::new(file.path(), Duration::from_secs(60)).with_read_retry(3);
+        assert_eq!(cache.get().unwrap().name, "Cached");
+    }
+
+    #[test]
+    fn test_max_depth() {
+        assert_eq!(json_depth(br#"{"a": [1, {"b": "[[["}], "c": "\"{"}"#), 3);
+
+        let file = NamedTempFile::new().unwrap();
+        write!(file.as_file(), "{}{}", "[".repeat(8), "]".repeat(8)).unwrap();
+        let cache: Tote<TestData> =
+            Tote::new(file.path(), Duration::from_secs(60)).with_max_depth(4);
+
+        assert!(matches!(cache.read(), Err(ToteError::InvalidCache)));
+        // Rejected cache is re-fetched
+        assert_eq!(cache.get().unwrap().name, "Test");
+    }
+
+    #[test]
+    fn test_get_no_write() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> = Tote::new(file.path(), Duration::from_secs(60));
+
+        assert_eq!(cache.get_no_write().unwrap().name, "Test");
+        // Fetched data isn't written for this call only
+        assert!(std::fs::read(file.path()).unwrap().is_empty());
+        cache.get().unwrap();
+        assert!(!std::fs::read(file.path()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_get_or_static() {
+        static DEFAULT: TestData = TestData {
+            name: String::new(),
+            value: 0,
+        };
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> = Tote::new(file.path(), Duration::from_secs(60));
+
+        let res = cache.get_or_static(&DEFAULT);
+        assert!(res.is_default());
+        assert_eq!(res.value, 0);
+        // Default isn't written
+        assert!(std::fs::read(file.path()).unwrap().is_empty());
+
+        cache.get().unwrap();
+        let res = cache.get_or_static(&DEFAULT);
+        assert!(!res.is_default());
+        assert_eq!(
+            *res,
+            TestData {
+                name: "Test".to_owned(),
+                value: 50
+            }
+        );
+    }
+
+    impl SizeHint for TestData {
+        fn size_hint(&self) -> Option<usize> {
+            Some(self.name.len() + 32)
+        }
+    }
+
+    #[test]
+    fn test_size_hint() {
+        let data = TestData {
+            name: "Hinted".to_owned(),
+            value: 5,
+        };
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> = Tote::new(file.path(), Duration::from_secs(60));
+        cache.put(&data).unwrap();
+        let unhinted = std::fs::read(file.path()).unwrap();
+
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> =
+            Tote::new(file.path(), Duration::from_secs(60)).with_size_hint();
+        cache.put(&data).unwrap();
+        assert_eq!(std::fs::read(file.path()).unwrap(), unhinted);
+    }
+
+    #[cfg(feature = "bincode")]
+    #[test]
+    fn test_size_hint_codec() {
+        let data = TestData {
+            name: "Hinted".to_owned(),
+            value: 5,
+        };
+        // Written with the codec, not as JSON
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData, BincodeCodec> =
+            Tote::new_with_codec(file.path(), Duration::from_secs(60), BincodeCodec)
+                .with_size_hint();
+        cache.put(&data).unwrap();
+        assert_eq!(
+            fs::read(file.path()).unwrap(),
+            bincode::serialize(&data).unwrap()
+        );
+        // Read back without re-fetching
+        assert_eq!(cache.get_fresh().unwrap(), (data, false));
+    }
+
+    #[test]
+    fn test_export_json() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> = Tote::new(file.path(), Duration::from_secs(60));
+        let out = NamedTempFile::new().unwrap();
+
+        assert!(matches!(
+            cache.export_json(out.path()),
+            Err(ToteError::InvalidCache)
+        ));
+        let data = cache.get().unwrap();
+        let before = std::fs::read(file.path()).unwrap();
+
+        cache.export_json(out.path()).unwrap();
+        let exported = std::fs::read_to_string(out.path()).unwrap();
+        assert!(exported.contains('\n'));
+        assert_eq!(serde_json::from_str::<TestData>(&exported).unwrap(), data);
+        assert_eq!(std::fs::read(file.path()).unwrap(), before);
+    }
+
+    #[test]
+    fn test_with_open_options() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let called = Arc::new(AtomicBool::new(false));
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> = Tote::new(file.path(), Duration::from_secs(60))
+            .with_open_options({
+                let called = called.clone();
+                move |options| {
+                    called.store(true, Ordering::SeqCst);
+                    options.read(true);
+                }
+            });
+
+        let data = cache.get().unwrap();
+        assert!(called.load(Ordering::SeqCst));
+        assert_eq!(cache.read().unwrap(), data);
+    }
+
+    #[test]
+    fn test_generation() {
+        let dir = tempfile::tempdir().unwrap();
+        let generation = dir.path().join("generation");
+        let first: Tote<TestData> = Tote::new(dir.path().join("first"), Duration::from_secs(60))
+            .with_generation_file(&generation);
+        let second: Tote<TestData> = Tote::new(dir.path().join("second"), Duration::from_secs(60))
+            .with_generation_file(&generation);
+
+        first.get().unwrap();
+        second.get().unwrap();
+        assert!(second.is_valid());
+
+        assert_eq!(first.bump_generation().unwrap(), 1);
+        assert!(!first.is_valid());
+        assert!(!second.is_valid());
+        // Re-fetched under the new generation
+        second.get().unwrap();
+        assert!(second.is_valid());
+
+        let cache: Tote<TestData> = Tote::new(dir.path().join("third"), Duration::from_secs(60));
+        assert!(matches!(
+            cache.bump_generation(),
+            Err(ToteError::InvalidOptions(_))
+        ));
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Unreachable;
+
+    impl Fetch for Unreachable {
+        type Cached = Unreachable;
+
+        fn fetch() -> Result<Unreachable, Box<dyn std::error::Error>> {
+            Err("Connection refused".into())
+        }
+    }
+
+    #[test]
+    fn test_unreadable_cache() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<Unreachable> = Tote::new(file.path(), Duration::from_secs(60));
+        // No cache file
+        fs::remove_file(file.path()).unwrap();
+        assert!(matches!(cache.get(), Err(ToteError::Fetching(_))));
+
+        // Written with an older data type
+        fs::write(file.path(), r#"{"hosts":[]}"#).unwrap();
+        match cache.get() {
+            Err(ToteError::UnreadableCache { path, read, source }) => {
+                assert_eq!(path, file.path());
+                assert!(matches!(*read, ToteError::Parse(_)));
+                assert_eq!(source.to_string(), "Connection refused");
+            }
+            res => panic!("expected UnreadableCache, got {:?}", res),
+        }
+        // Left as-is
+        assert_eq!(fs::read_to_string(file.path()).unwrap(), r#"{"hosts":[]}"#);
+
+        // Expired data isn't reported as unreadable
+        touch(file.path(), SystemTime::now() - Duration::from_secs(120)).unwrap();
+        assert!(matches!(cache.get(), Err(ToteError::Fetching(_))));
+    }
+
+    #[test]
+    fn test_get_or_default() {
+        use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+        static ONLINE: AtomicBool = AtomicBool::new(false);
+
+        #[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+        struct Motd(String);
+
+        impl Fetch for Motd {
+            type Cached = Motd;
+
+            fn fetch() -> Result<Motd, Box<dyn std::error::Error>> {
+                if !ONLINE.load(Ordering::SeqCst) {
+                    return Err("Connection refused".into());
+                }
+                Ok(Motd("Welcome".to_owned()))
+            }
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("motd.json");
+        let failures = Arc::new(AtomicUsize::new(0));
+        let counter = failures.clone();
+        let cache: Tote<Motd> = Tote::new(&path, Duration::from_secs(60)).on_fetch(move |res| {
+            if res.is_err() {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+        assert_eq!(cache.get_or_default(), Motd::default());
+        assert_eq!(failures.load(Ordering::SeqCst), 1);
+        assert!(!path.exists());
+
+        // A successful fetch is cached
+        ONLINE.store(true, Ordering::SeqCst);
+        assert_eq!(cache.get_or_default(), Motd("Welcome".to_owned()));
+        ONLINE.store(false, Ordering::SeqCst);
+        assert_eq!(cache.get_or_default(), Motd("Welcome".to_owned()));
+        assert_eq!(failures.load(Ordering::SeqCst), 1);
+
+        // Fetched data is returned even if it can't be written
+        ONLINE.store(true, Ordering::SeqCst);
+        let unwritable: Tote<Motd> = Tote::new(
+            dir.path().join("missing/motd.json"),
+            Duration::from_secs(60),
+        )
+        .create_dirs(false);
+        assert_eq!(unwritable.get_or_default(), Motd("Welcome".to_owned()));
+        assert!(unwritable.get().is_err());
+    }
+
+    struct Auth {
+        token: String,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Profile {
+        user: String,
+    }
+
+    impl FetchWith<&Auth> for Profile {
+        type Cached = Profile;
+
+        fn fetch_with(auth: &Auth) -> Result<Profile, Box<dyn std::error::Error>> {
+            match auth.token.strip_prefix("token-") {
+                Some(user) => Ok(Profile {
+                    user: user.to_owned(),
+                }),
+                None => Err("Unauthorized".into()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_get_with() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<Profile> = Tote::new(file.path(), Duration::from_secs(60));
+        let bad = Auth {
+            token: "bad".to_owned(),
+        };
+        assert!(matches!(cache.get_with(&bad), Err(ToteError::Fetching(_))));
+
+        let auth = Auth {
+            token: "token-alice".to_owned(),
+        };
+        assert_eq!(cache.get_with(&auth).unwrap().user, "alice");
+        // Cached data is read without fetching
+        assert_eq!(cache.get_with(&bad).unwrap().user, "alice");
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Inventory(Vec<String>);
+
+    impl FetchWithWarnings for Inventory {
+        type Cached = Inventory;
+        type Warning = String;
+
+        fn fetch_with_warnings() -> Result<(Inventory, Vec<String>), Box<dyn std::error::Error>> {
+            let devices = vec!["router-1".to_owned()];
+            Ok((Inventory(devices), vec!["switch-2 unreachable".to_owned()]))
+        }
+    }
+
+    #[test]
+    fn test_get_with_warnings() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<Inventory> = Tote::new(file.path(), Duration::from_secs(60));
+        let (data, warnings) = cache.get_with_warnings().unwrap();
+        assert_eq!(data.0, ["router-1"]);
+        assert_eq!(warnings, ["switch-2 unreachable"]);
+
+        // Partial data is cached
+        assert!(cache.is_valid());
+        let (cached, warnings) = cache.get_with_warnings().unwrap();
+        assert_eq!(cached, data);
+        assert!(warnings.is_empty());
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct HostResponse {
+        hostname: String,
+        #[allow(dead_code)]
+        uptime: u64,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Host {
+        hostname: String,
+    }
+
+    impl From<HostResponse> for Host {
+        fn from(resp: HostResponse) -> Host {
+            Host {
+                hostname: resp.hostname,
+            }
+        }
+    }
+
+    impl FetchInto for Host {
+        type Raw = HostResponse;
+
+        fn fetch_raw() -> Result<HostResponse, Box<dyn std::error::Error>> {
+            Ok(serde_json::from_str(
+                r#"{"hostname":"router-1","uptime":3600}"#,
+            )?)
+        }
+    }
+
+    #[test]
+    fn test_fetch_into() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<Host> = Tote::new(file.path(), Duration::from_secs(60));
+        let host = cache.get().unwrap();
+        assert_eq!(host.hostname, "router-1");
+        // Only the converted data is cached
+        assert_eq!(
+            fs::read_to_string(file.path()).unwrap(),
+            r#"{"hostname":"router-1"}"#
+        );
+        assert_eq!(cache.read().unwrap(), host);
+    }
+
+    #[test]
+    fn test_persistent() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> = Tote::persistent(file.path());
+        assert_eq!(cache.max_age(), Duration::MAX);
+        let data = cache.get().unwrap();
+
+        touch(file.path(), SystemTime::UNIX_EPOCH + Duration::from_secs(1)).unwrap();
+        assert!(cache.is_valid());
+        assert_eq!(cache.read().unwrap(), data);
+
+        // Also valid with a modified time in the future
+        touch(file.path(), SystemTime::now() + Duration::from_secs(3600)).unwrap();
+        assert!(cache.is_valid());
+
+        let cache = cache.with_embedded_timestamp(true);
+        cache.put(&data).unwrap();
+        touch(file.path(), SystemTime::UNIX_EPOCH).unwrap();
+        assert_eq!(cache.read().unwrap(), data);
+
+        cache.invalidate().unwrap();
+        assert!(!cache.is_valid());
+    }
+
+    #[test]
+    fn test_exists() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache: Tote<TestData> = Tote::new(dir.path().join("cache"), Duration::from_secs(60));
+        assert!(!cache.exists());
+        assert!(!cache.is_valid());
+
+        cache.get().unwrap();
+        assert!(cache.exists());
+        assert!(cache.is_valid());
+
+        cache.expire_now().unwrap();
+        assert!(cache.exists());
+        assert!(!cache.is_valid());
+    }
+
+    #[test]
+    fn test_checksum() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> =
+            Tote::new(file.path(), Duration::from_secs(60)).with_checksum(true);
+        cache
+            .put(&TestData {
+                name: "Test".to_owned(),
+                value: 1,
+            })
+            .unwrap();
+        let written = fs::read(file.path()).unwrap();
+        assert!(written.starts_with(b"crc32:"));
+        assert_eq!(cache.get().unwrap().value, 1);
+
+        // A flipped byte still parses, but fails the checksum & is re-fetched
+        let corrupted = String::from_utf8(written).unwrap().replace(":1}", ":9}");
+        fs::write(file.path(), corrupted).unwrap();
+        assert!(matches!(cache.read(), Err(ToteError::InvalidCache)));
+        assert_eq!(cache.get().unwrap().value, 50);
+
+        // Truncated & un-checksummed files are re-fetched too
+        let written = fs::read(file.path()).unwrap();
+        fs::write(file.path(), &written[..written.len() - 1]).unwrap();
+        assert!(matches!(cache.read(), Err(ToteError::InvalidCache)));
+        fs::write(file.path(), br#"{"name":"Test","value":2}"#).unwrap();
+        assert!(matches!(cache.read(), Err(ToteError::InvalidCache)));
+    }
+
+    #[test]
+    fn test_embedded_timestamp() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> =
+            Tote::new(file.path(), Duration::from_secs(60)).with_embedded_timestamp(true);
+        let data = cache.get().unwrap();
+        let written: serde_json::Value =
+            serde_json::from_slice(&fs::read(file.path()).unwrap()).unwrap();
+        assert_eq!(written["data"]["name"], "Test");
+        assert!(written["fetched_at"].as_u64().unwrap() > 0);
+
+        // Expiry ignores the modified time
+        touch(file.path(), SystemTime::now() - Duration::from_secs(120)).unwrap();
+        assert_eq!(cache.read().unwrap(), data);
+
+        let fetched_at = SystemTime::now() - Duration::from_secs(120);
+        let secs = fetched_at.duration_since(SystemTime::UNIX_EPOCH).unwrap();
+        let stale = format!(
+            r#"{{"fetched_at":{},"data":{{"name":"Old","value":1}}}}"#,
+            secs.as_secs()
+        );
+        fs::write(file.path(), stale).unwrap();
+        assert!(matches!(cache.read(), Err(ToteError::InvalidCache)));
+        assert_eq!(cache.read_from(file.path()).unwrap().name, "Old");
+        assert!(matches!(
+            cache.stream_items::<u8>(),
+            Err(ToteError::InvalidOptions(_))
+        ));
+    }
+
+    #[test]
+    fn test_embedded_timestamp_legacy_file() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> =
+            Tote::new(file.path(), Duration::from_secs(60)).with_embedded_timestamp(true);
+        fs::write(file.path(), r#"{"name":"Legacy","value":1}"#).unwrap();
+        assert!(cache.is_valid());
+        let legacy = cache.get().unwrap();
+        assert_eq!(legacy.name, "Legacy");
+
+        // Expires by the modified time
+        touch(file.path(), SystemTime::now() - Duration::from_secs(120)).unwrap();
+        assert!(matches!(cache.read(), Err(ToteError::InvalidCache)));
+
+        // Upgraded on the next write
+        cache.put(&legacy).unwrap();
+        let written: serde_json::Value =
+            serde_json::from_slice(&fs::read(file.path()).unwrap()).unwrap();
+        assert_eq!(written["data"]["name"], "Legacy");
+        assert!(written["fetched_at"].as_u64().unwrap() > 0);
+        assert_eq!(cache.read().unwrap(), legacy);
+    }
+
+    #[test]
+    fn test_schema_migration() {
+        #[derive(Serialize, Deserialize)]
+        struct V1 {
+            name: String,
+        }
+
+        #[derive(Deserialize)]
+        struct V1Envelope {
+            data: V1,
+        }
+
+        let file = NamedTempFile::new().unwrap();
+        let v1: Tote<V1> = Tote::new(file.path(), Duration::from_secs(60)).with_schema_version(1);
+        v1.put(&V1 {
+            name: "Old".to_owned(),
+        })
+        .unwrap();
+        let written: serde_json::Value =
+            serde_json::from_slice(&fs::read(file.path()).unwrap()).unwrap();
+        assert_eq!(written["version"], 1);
+
+        // Without a migration, other versions are re-fetched
+        let v2: Tote<TestData> =
+            Tote::new(file.path(), Duration::from_secs(60)).with_schema_version(2);
+        assert!(matches!(v2.read(), Err(ToteError::InvalidCache)));
+
+        let v2 = v2.with_migration(|version, bytes| {
+            assert_eq!(version, 1);
+            let old: V1Envelope = serde_json::from_slice(bytes).map_err(ToteError::Parse)?;
+            Ok(TestData {
+                name: old.data.name,
+                value: 0,
+            })
+        });
+        let migrated = v2.read().unwrap();
+        assert_eq!(migrated.name, "Old");
+        assert_eq!(migrated.value, 0);
+
+        // Current version is read as-is
+        v2.put(&migrated).unwrap();
+        assert_eq!(v2.read().unwrap(), migrated);
+    }
+
+    #[test]
+    fn test_last_modified_and_age() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache");
+        let cache: Tote<TestData> = Tote::new(&path, Duration::from_secs(60));
+        assert_eq!(cache.last_modified().unwrap(), None);
+        assert_eq!(cache.age().unwrap(), None);
+        assert_eq!(cache.size_on_disk().unwrap(), None);
+
+        cache.get().unwrap();
+        let size = fs::read(&path).unwrap().len() as u64;
+        assert_eq!(cache.size_on_disk().unwrap(), Some(size));
+        let modified = SystemTime::now() - Duration::from_secs(30);
+        touch(&path, modified).unwrap();
+        assert_eq!(cache.last_modified().unwrap(), Some(modified));
+        assert!(cache.age().unwrap().unwrap() >= Duration::from_secs(30));
+
+        // Modified in the future
+        touch(&path, SystemTime::now() + Duration::from_secs(30)).unwrap();
+        assert_eq!(cache.age().unwrap(), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_future_modified_time() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> = Tote::new(file.path(), Duration::from_secs(60));
+        let data = cache.get().unwrap();
+
+        touch(file.path(), SystemTime::now() + Duration::from_secs(3600)).unwrap();
+        assert!(cache.is_valid());
+        assert_eq!(cache.freshness(), Freshness::Fresh);
+        assert_eq!(cache.read().unwrap(), data);
+    }
+
+    #[test]
+    fn test_error_variants() {
+        let file = NamedTempFile::new().unwrap();
+        // Never expires, so the corrupt file below is read
+        let expiry = SystemTime::now() + Duration::from_secs(3600);
+        let cache: Tote<Unreachable> =
+            Tote::new(file.path(), Duration::from_secs(60)).with_expiry_policy(expiry);
+
+        // Fetch failure
+        assert!(matches!(cache.get(), Err(ToteError::Fetching(_))));
+
+        // Unparseable cache file
+        fs::write(file.path(), "{ not json").unwrap();
+        assert!(matches!(cache.peek(), Err(ToteError::Parse(_))));
+        assert!(matches!(
+            cache.get(),
+            Err(ToteError::UnreadableCache { .. })
+        ));
+
+        // Unserializable data
+        let cache: Tote<std::collections::HashMap<(u8, u8), u8>> =
+            Tote::new(file.path(), Duration::from_secs(60));
+        let data = std::collections::HashMap::from([((1, 2), 3)]);
+        assert!(matches!(cache.put(&data), Err(ToteError::Serde(_))));
+    }
+
+    #[test]
+    fn test_error_kind() {
+        let json_err = || serde_json::from_str::<u8>("x").unwrap_err();
+        let cases = [
+            (
+                ToteError::FileAccess(std::io::ErrorKind::NotFound.into()),
+                ToteErrorKind::FileAccess,
+            ),
+            (ToteError::Serde(json_err()), ToteErrorKind::Serde),
+            (ToteError::Parse(json_err()), ToteErrorKind::Parse),
+            (ToteError::InvalidCache, ToteErrorKind::InvalidCache),
+            (
+                ToteError::Fetching("offline".into()),
+                ToteErrorKind::Fetching,
+            ),
+            (
+                ToteError::FetchingFor {
+                    path: PathBuf::from("cache.json"),
+                    source: "offline".into(),
+                },
+                ToteErrorKind::Fetching,
+            ),
+            (
+                ToteError::InvalidPath {
+                    path: PathBuf::new(),
+                    reason: "empty",
+                },
+                ToteErrorKind::InvalidPath,
+            ),
+            (
+                ToteError::InvalidOptions("conflict"),
+                ToteErrorKind::InvalidOptions,
+            ),
+            (ToteError::Codec("bad".into()), ToteErrorKind::Codec),
+            (
+                ToteError::WriteVerificationFailed,
+                ToteErrorKind::WriteVerificationFailed,
+            ),
+            (
+                ToteError::Timeout(Duration::from_secs(1)),
+                ToteErrorKind::Timeout,
+            ),
+            (
+                ToteError::UnreadableCache {
+                    path: PathBuf::from("cache.json"),
+                    read: Box::new(ToteError::Parse(json_err())),
+                    source: Box::new(ToteError::Fetching("offline".into())),
+                },
+                ToteErrorKind::UnreadableCache,
+            ),
+            (
+                ToteError::TooLarge { size: 2, limit: 1 },
+                ToteErrorKind::TooLarge,
+            ),
+        ];
+        for (err, kind) in cases {
+            assert_eq!(err.kind(), kind);
+        }
+
+        // From a real failure
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<Unreachable> = Tote::new(file.path(), Duration::from_secs(60));
+        let kind = cache.get().unwrap_err().kind();
+        assert_eq!(kind, ToteErrorKind::Fetching);
+        assert_ne!(kind, ToteErrorKind::InvalidCache);
+    }
+
+    #[test]
+    fn test_get_or_stale() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<Unreachable> = Tote::new(file.path(), Duration::from_secs(60));
+        assert!(matches!(cache.get_or_stale(), Err(ToteError::Fetching(_))));
+
+        cache.put(&Unreachable).unwrap();
+        touch(file.path(), SystemTime::now() - Duration::from_secs(120)).unwrap();
+        assert!(cache.get().is_err());
+        assert!(cache.get_or_stale().is_ok());
+        assert!(!cache.is_valid());
+    }
+
+    #[test]
+    fn test_map_err_with_path() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<Unreachable> = Tote::new(file.path(), Duration::from_secs(60));
+        let err = cache.get().unwrap_err();
+        assert!(matches!(err, ToteError::Fetching(_)));
+        assert_eq!(err.to_string(), "Connection refused");
+
+        let err = cache.map_err_with_path(true).get().unwrap_err();
+        assert!(matches!(&err, ToteError::FetchingFor { path, .. } if path == file.path()));
+        let message = err.to_string();
+        assert!(message.contains(file.path().to_str().unwrap()));
+        assert!(message.ends_with("Connection refused"));
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Response {
+        name: String,
+        #[serde(default)]
+        request_id: Option<u32>,
+        #[serde(default)]
+        name_len: usize,
+    }
+
+    #[test]
+    fn test_with_serde_transform() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<Response> = Tote::new(file.path(), Duration::from_secs(60))
+            .with_serde_transform(
+                |value| {
+                    value.as_object_mut().unwrap().remove("request_id");
+                },
+                |value| {
+                    let len = value["name"].as_str().unwrap().len();
+                    value["name_len"] = len.into();
+                },
+            );
+
+        cache
+            .put(&Response {
+                name: "Test".to_owned(),
+                request_id: Some(1234),
+                name_len: 0,
+            })
+            .unwrap();
+        let written = std::fs::read_to_string(file.path()).unwrap();
+        assert!(!written.contains("request_id"));
+        assert_eq!(
+            cache.read().unwrap(),
+            Response {
+                name: "Test".to_owned(),
+                request_id: None,
+                name_len: 4,
+            }
+        );
+    }
+
+    #[test]
+    fn test_read_arbitrary_bytes() {
+        // Regression seeds for the `read` fuzz target
+        let seeds: &[&[u8]] = &[
+            b"[",
+            b"{\"name\": \"Test\", \"val",
+            b"\"\\",
+            b"[1}[",
+            b"]]]]",
+            b"\xff\xfe\x00\x01",
+            &[b'['; 4096],
+        ];
+        for seed in seeds {
+            let file = NamedTempFile::new().unwrap();
+            std::fs::write(file.path(), seed).unwrap();
+            let cache: Tote<TestData> =
+                Tote::new(file.path(), Duration::from_secs(60)).with_max_depth(64);
+            assert!(cache.read().is_err());
+            assert!(cache.clone().with_max_depth(usize::MAX).read().is_err());
+            if let Ok(items) = cache.stream_items::<serde_json::Value>() {
+                items.for_each(drop);
+            }
+        }
+    }
+
+    #[test]
+    fn test_capabilities() {
+        let caps = capabilities();
+        assert_eq!(caps.async_fetch, cfg!(feature = "async"));
+        assert_eq!(caps.async_native, cfg!(feature = "async-native"));
+        assert_eq!(caps.bytes, cfg!(feature = "bytes"));
+        assert_eq!(caps.sha256, cfg!(feature = "sha256"));
+        assert_eq!(caps.mmap, cfg!(feature = "mmap"));
+        assert_eq!(caps.bincode, cfg!(feature = "bincode"));
+        assert_eq!(caps.cbor, cfg!(feature = "cbor"));
+        assert_eq!(caps.gzip, cfg!(feature = "gzip"));
+        assert_eq!(caps.encrypt, cfg!(feature = "encrypt"));
+        assert_eq!(caps.tracing, cfg!(feature = "tracing"));
+        assert_eq!(caps.zip, cfg!(feature = "zip"));
+    }
+
+    #[test]
+    fn test_min_refresh_interval() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static FETCHES: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(Debug, Serialize, Deserialize)]
+        struct Counted(usize);
+
+        impl Fetch for Counted {
+            type Cached = Counted;
+
+            fn fetch() -> Result<Counted, Box<dyn std::error::Error>> {
+                Ok(Counted(FETCHES.fetch_add(1, Ordering::SeqCst) + 1))
+            }
+        }
+
+        let file = NamedTempFile::new().unwrap();
+        // Always expired, but rate-limited
+        let cache: Tote<Counted> = Tote::new(file.path(), Duration::ZERO)
+            .with_min_refresh_interval(Duration::from_secs(60));
+        assert_eq!(cache.get().unwrap().0, 1);
+        assert_eq!(cache.get().unwrap().0, 1);
+        assert_eq!(FETCHES.load(Ordering::SeqCst), 1);
+
+        // Fetches again once the interval has passed
+        touch(file.path(), SystemTime::now() - Duration::from_secs(61)).unwrap();
+        assert_eq!(cache.get().unwrap().0, 2);
+
+        // Reads with sliding expiration don't extend the interval
+        let now = Arc::new(std::sync::Mutex::new(SystemTime::now()));
+        let clock = {
+            let now = now.clone();
+            move || *now.lock().unwrap()
+        };
+        let cache: Tote<Counted> = Tote::new(file.path(), Duration::from_secs(30))
+            .with_min_refresh_interval(Duration::from_secs(60))
+            .sliding_expiration(true)
+            .with_clock(clock.clone());
+        cache.invalidate().unwrap();
+        assert_eq!(cache.get().unwrap().0, 3);
+        *now.lock().unwrap() += Duration::from_secs(20);
+        assert_eq!(cache.get().unwrap().0, 3);
+        *now.lock().unwrap() += Duration::from_secs(50);
+        assert_eq!(cache.get().unwrap().0, 4);
+
+        // Or the embedded fetch time, if enveloped
+        let cache: Tote<Counted> = Tote::new(file.path(), Duration::from_secs(30))
+            .with_min_refresh_interval(Duration::from_secs(60))
+            .with_embedded_timestamp(true)
+            .with_clock(clock);
+        cache.invalidate().unwrap();
+        assert_eq!(cache.get().unwrap().0, 5);
+        touch(file.path(), SystemTime::now()).unwrap();
+        *now.lock().unwrap() += Duration::from_secs(40);
+        assert_eq!(cache.get().unwrap().0, 5);
+        *now.lock().unwrap() += Duration::from_secs(30);
+        assert_eq!(cache.get().unwrap().0, 6);
+    }
+
+    #[test]
+    fn test_path_is_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache: Tote<TestData> = Tote::new(dir.path(), Duration::from_secs(60));
+        let is_dir = |res: Result<_, ToteError>| match res {
+            Err(ToteError::InvalidPath { path, reason }) => {
+                path == dir.path() && reason == "path is a directory"
+            }
+            _ => false,
+        };
+        let data = TestData {
+            name: "Test".to_owned(),
+            value: 1,
+        };
+        assert!(is_dir(cache.put(&data)));
+        assert!(is_dir(cache.peek().map(|_| ())));
+        assert!(is_dir(cache.get().map(|_| ())));
+        assert!(!cache.is_valid());
+        assert!(dir.path().is_dir());
+    }
+
+    #[test]
+    fn test_zero_max_age() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static FETCHES: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(Debug, Serialize, Deserialize)]
+        struct Counted(usize);
+
+        impl Fetch for Counted {
+            type Cached = Counted;
+
+            fn fetch() -> Result<Counted, Box<dyn std::error::Error>> {
+                Ok(Counted(FETCHES.fetch_add(1, Ordering::SeqCst) + 1))
+            }
+        }
+
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<Counted> = Tote::new(file.path(), Duration::ZERO);
+        assert_eq!(cache.get().unwrap().0, 1);
+        assert_eq!(cache.get().unwrap().0, 2);
+        assert_eq!(FETCHES.load(Ordering::SeqCst), 2);
+        // Still written for inspection
+        assert_eq!(fs::read_to_string(file.path()).unwrap(), "2");
+        assert!(!cache.is_valid());
+    }
+
+    #[test]
+    fn test_put_smaller_payload() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> = Tote::new(file.path(), Duration::from_secs(60));
+
+        cache
+            .put(&TestData {
+                name: "A much longer name than the next one".to_owned(),
+                value: 255,
+            })
+            .unwrap();
+        let small = TestData {
+            name: "S".to_owned(),
+            value: 1,
+        };
+        cache.put(&small).unwrap();
+        // Served from cache rather than re-fetched ("Test")
+        assert_eq!(cache.get().unwrap(), small);
+    }
+
+    #[test]
+    fn test_into_from_parts() {
+        let cache: Tote<TestData> = Tote::new("colors.cache", Duration::from_secs(60))
+            .deterministic(true)
+            .with_mirror(PathBuf::from("/tmp/colors.cache"))
+            .with_read_retry(3)
+            .on_write_error(OnWriteError::ReturnData);
+        let config = cache.clone().into_parts();
+
+        let json = serde_json::to_string(&config).unwrap();
+        let restored: ToteConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, config);
+        let rebuilt: Tote<TestData> = Tote::from_parts(restored);
+        assert_eq!(format!("{:?}", rebuilt), format!("{:?}", cache));
+
+        // Platform & feature specific options are preserved
+        #[cfg(unix)]
+        let cache = cache.with_file_mode(0o640);
+        #[cfg(feature = "mmap")]
+        let cache = cache.memory_mapped(true);
+        let config = cache.into_parts();
+        let rebuilt: Tote<TestData> = Tote::from_parts(config.clone());
+        assert_eq!(rebuilt.into_parts(), config);
+        #[cfg(unix)]
+        assert_eq!(config.file_mode, 0o640);
+        #[cfg(feature = "mmap")]
+        assert!(config.memory_mapped);
+
+        // Omitted options are defaulted
+        let config: ToteConfig =
+            serde_json::from_str(r#"{"path": "a.cache", "max_age": {"secs": 5, "nanos": 0}}"#)
+                .unwrap();
+        let rebuilt: Tote<TestData> = Tote::from_parts(config);
+        assert_eq!(
+            format!("{:?}", rebuilt),
+            format!(
+                "{:?}",
+                Tote::<TestData>::new("a.cache", Duration::from_secs(5))
+            )
+        );
+    }
+
+    #[test]
+    fn test_invalidate() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> = Tote::new(file.path(), Duration::from_secs(60));
+        cache
+            .put(&TestData {
+                name: "Old".to_owned(),
+                value: 1,
+            })
+            .unwrap();
+        assert!(cache.is_valid());
+
+        cache.invalidate().unwrap();
+        assert!(!cache.is_valid());
+        assert!(!file.path().exists());
+        // Already removed
+        cache.invalidate().unwrap();
+        assert_eq!(cache.get().unwrap().name, "Test");
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_memory_mapped() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> =
+            Tote::new(file.path(), Duration::from_secs(60)).memory_mapped(true);
+        assert!(cache.read().is_err());
+
+        let data = cache.get().unwrap();
+        assert!(matches!(
+            cache.read_bytes_from(file.path()).unwrap(),
+            Contents::Mapped(_)
+        ));
+        assert_eq!(cache.read().unwrap(), data);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_file_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        let cache: Tote<TestData> = Tote::new(dir.path().join("private"), Duration::from_secs(60));
+        cache.get().unwrap();
+        assert_eq!(mode(&cache.path), 0o600);
+
+        let cache: Tote<TestData> =
+            Tote::new(dir.path().join("shared"), Duration::from_secs(60)).with_file_mode(0o640);
+        cache.get().unwrap();
+        assert_eq!(mode(&cache.path), 0o640);
+    }
+
+    #[test]
+    fn test_force_refresh() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> = Tote::new(file.path(), Duration::from_secs(60));
+        cache
+            .put(&TestData {
+                name: "Old".to_owned(),
+                value: 1,
+            })
+            .unwrap();
+
+        assert_eq!(cache.force_refresh().unwrap().name, "Test");
+        assert_eq!(cache.read().unwrap().name, "Test");
+    }
+
+    #[test]
+    fn test_clear_if_expired() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> = Tote::new(file.path(), Duration::from_secs(60));
+        cache.get().unwrap();
+
+        // Fresh, kept
+        assert!(!cache.clear_if_expired().unwrap());
+        assert!(file.path().exists());
+
+        // Expired, removed
+        cache.expire_now().unwrap();
+        assert!(cache.clear_if_expired().unwrap());
+        assert!(!file.path().exists());
+        assert!(!cache.clear_if_expired().unwrap());
+
+        // Removal errors are surfaced
+        struct Undeletable(MemoryBackend);
+
+        impl Backend for Undeletable {
+            fn read_bytes(&self, path: &Path) -> io::Result<Vec<u8>> {
+                self.0.read_bytes(path)
+            }
+
+            fn write_bytes(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+                self.0.write_bytes(path, data)
+            }
+
+            fn modified(&self, path: &Path) -> io::Result<SystemTime> {
+                self.0.modified(path)
+            }
+
+            fn remove(&self, _path: &Path) -> io::Result<()> {
+                Err(io::ErrorKind::PermissionDenied.into())
+            }
+        }
+
+        let cache: Tote<TestData> =
+            Tote::new(file.path(), Duration::ZERO).with_backend(Undeletable(MemoryBackend::new()));
+        cache.get().unwrap();
+        assert!(matches!(
+            cache.clear_if_expired(),
+            Err(ToteError::FileAccess(e)) if e.kind() == io::ErrorKind::PermissionDenied
+        ));
+    }
+
+    #[test]
+    fn test_peek() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> = Tote::new(file.path(), Duration::from_secs(60));
+        // Missing, never fetches
+        assert_eq!(cache.peek().unwrap(), None);
+        assert!(std::fs::read(file.path()).unwrap().is_empty());
+
+        let data = cache.get().unwrap();
+        assert_eq!(cache.peek().unwrap(), Some(data));
+
+        cache.expire_now().unwrap();
+        assert_eq!(cache.peek().unwrap(), None);
+
+        std::fs::write(file.path(), "{").unwrap();
+        assert!(matches!(cache.peek(), Err(ToteError::Parse(_))));
+    }
+
+    /// JSON with a magic header, standing in for a binary format
+    #[derive(Clone)]
+    struct Prefixed;
+
+    impl Codec for Prefixed {
+        fn serialize<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, ToteError> {
+            let mut data = b"TOTE".to_vec();
+            data.extend(serde_json::to_vec(value)?);
+            Ok(data)
+        }
+
+        fn deserialize<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, ToteError> {
+            let data = bytes
+                .strip_prefix(b"TOTE")
+                .ok_or_else(|| ToteError::Codec("Missing header".into()))?;
+            Ok(serde_json::from_slice(data)?)
+        }
+    }
+
+    #[test]
+    fn test_codec() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData, Prefixed> =
+            Tote::new_with_codec(file.path(), Duration::from_secs(60), Prefixed);
+
+        let data = cache.get().unwrap();
+        assert!(std::fs::read(file.path()).unwrap().starts_with(b"TOTE{"));
+        assert_eq!(cache.read().unwrap(), data);
+
+        // Undecodable data is re-fetched
+        std::fs::write(file.path(), r#"{"name": "Old", "value": 1}"#).unwrap();
+        assert!(matches!(cache.read(), Err(ToteError::Codec(_))));
+        assert_eq!(cache.get().unwrap(), data);
+    }
+
+    #[test]
+    fn test_get_and_merge() {
+        use std::collections::HashMap;
+
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<HashMap<String, u8>> = Tote::new(file.path(), Duration::from_secs(60));
+
+        let base = HashMap::from([("a".to_owned(), 1), ("b".to_owned(), 2)]);
+        assert_eq!(cache.get_and_merge(base.clone()).unwrap(), base);
+
+        let delta = HashMap::from([("b".to_owned(), 3), ("c".to_owned(), 4)]);
+        let merged = cache.get_and_merge(delta).unwrap();
+        let expected = HashMap::from([
+            ("a".to_owned(), 1),
+            ("b".to_owned(), 3),
+            ("c".to_owned(), 4),
+        ]);
+        assert_eq!(merged, expected);
+        assert_eq!(cache.read().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_on_unchanged() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> = Tote::new(file.path(), Duration::from_secs(60));
+        let data = TestData {
+            name: "Test".to_owned(),
+            value: 50,
+        };
+        let modified = || std::fs::metadata(file.path()).unwrap().modified().unwrap();
+        let an_hour_ago = SystemTime::now() - Duration::from_secs(3600);
+
+        // Identical, expiry preserved
+        cache.put(&data).unwrap();
+        touch(file.path(), an_hour_ago).unwrap();
+        let preserve = cache.clone().on_unchanged(OnUnchanged::PreserveExpiry);
+        preserve.put(&data).unwrap();
+        assert_eq!(modified(), an_hour_ago);
+
+        // Identical, expiry refreshed
+        let refresh = cache.clone().on_unchanged(OnUnchanged::RefreshExpiry);
+        refresh.put(&data).unwrap();
+        assert!(modified() > an_hour_ago);
+
+        // Differing, written
+        touch(file.path(), an_hour_ago).unwrap();
+        let changed = TestData {
+            name: "Changed".to_owned(),
+            value: 1,
+        };
+        preserve.put(&changed).unwrap();
+        assert!(modified() > an_hour_ago);
+        assert_eq!(preserve.read().unwrap(), changed);
+    }
+
+    #[test]
+    fn test_on_unchanged_enveloped() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> = Tote::new(file.path(), Duration::from_secs(60))
+            .with_embedded_timestamp(true)
+            .on_unchanged(OnUnchanged::PreserveExpiry);
+        let an_hour_ago = SystemTime::now() - Duration::from_secs(3600);
+        let data = TestData {
+            name: "Test".to_owned(),
+            value: 50,
+        };
+
+        // Identical (despite a new fetch time), expiry preserved
+        cache
+            .clone()
+            .with_clock(move || an_hour_ago)
+            .put(&data)
+            .unwrap();
+        let written = fs::read(file.path()).unwrap();
+        cache.put(&data).unwrap();
+        assert_eq!(fs::read(file.path()).unwrap(), written);
+        assert!(cache.read().is_err());
+
+        // Identical, fetch time refreshed
+        let refresh = cache.clone().on_unchanged(OnUnchanged::RefreshExpiry);
+        refresh.put(&data).unwrap();
+        assert_eq!(cache.read().unwrap(), data);
+
+        // Differing, written
+        let changed = TestData {
+            name: "Changed".to_owned(),
+            value: 1,
+        };
+        let written = fs::read(file.path()).unwrap();
+        cache.put(&changed).unwrap();
+        assert_ne!(fs::read(file.path()).unwrap(), written);
+        assert_eq!(cache.read().unwrap(), changed);
+    }
+
+    #[cfg(feature = "encrypt")]
+    #[test]
+    fn test_on_unchanged_cipher() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> = Tote::new(file.path(), Duration::from_secs(60))
+            .with_cipher(ChaChaCipher::new([1; 32]))
+            .on_unchanged(OnUnchanged::PreserveExpiry);
+        let data = TestData {
+            name: "Test".to_owned(),
+            value: 50,
+        };
+        let modified = || fs::metadata(file.path()).unwrap().modified().unwrap();
+        let an_hour_ago = SystemTime::now() - Duration::from_secs(3600);
+
+        // Identical (despite a new nonce), expiry preserved
+        cache.put(&data).unwrap();
+        let written = fs::read(file.path()).unwrap();
+        touch(file.path(), an_hour_ago).unwrap();
+        cache.put(&data).unwrap();
+        assert_eq!(modified(), an_hour_ago);
+
+        // Identical, expiry refreshed without rewriting
+        let refresh = cache.clone().on_unchanged(OnUnchanged::RefreshExpiry);
+        refresh.put(&data).unwrap();
+        assert!(modified() > an_hour_ago);
+        assert_eq!(fs::read(file.path()).unwrap(), written);
+
+        // Differing, written
+        let changed = TestData {
+            name: "Changed".to_owned(),
+            value: 1,
+        };
+        cache.put(&changed).unwrap();
+        assert_eq!(cache.read().unwrap(), changed);
+    }
+
+    #[cfg(feature = "bincode")]
+    #[test]
+    fn test_bincode_codec() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData, BincodeCodec> =
+            Tote::new_with_codec(file.path(), Duration::from_secs(60), BincodeCodec);
+        let data = TestData {
+            name: "Binary".to_owned(),
+            value: 255,
+        };
+
+        cache.put(&data).unwrap();
+        assert!(std::fs::read_to_string(file.path()).is_err());
+        assert_eq!(cache.get().unwrap(), data);
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn test_cbor_codec() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData, CborCodec> =
+            Tote::new_with_codec(file.path(), Duration::from_secs(60), CborCodec);
+        let data = TestData {
+            name: "Binary".to_owned(),
+            value: 255,
+        };
+
+        cache.put(&data).unwrap();
+        assert!(serde_json::from_slice::<TestData>(&fs::read(file.path()).unwrap()).is_err());
+        assert_eq!(cache.get().unwrap(), data);
+    }
+
+    #[test]
+    fn test_extension_codec() {
+        assert_eq!(
+            ExtensionCodec::from_extension("cache.json"),
+            ExtensionCodec::Json
+        );
+        assert_eq!(
+            ExtensionCodec::from_extension("cache.yaml"),
+            ExtensionCodec::Json
+        );
+        assert_eq!(
+            ExtensionCodec::from_extension("cache"),
+            ExtensionCodec::Json
+        );
+        #[cfg(feature = "cbor")]
+        assert_eq!(
+            ExtensionCodec::from_extension("cache.cbor"),
+            ExtensionCodec::Cbor
+        );
+        #[cfg(feature = "bincode")]
+        assert_eq!(
+            ExtensionCodec::from_extension("cache.bin"),
+            ExtensionCodec::Bincode
+        );
+    }
+
+    #[test]
+    fn test_atomic_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache");
+        let cache: Tote<TestData> = Tote::new(&path, Duration::from_secs(60));
+
+        // Leftover from an interrupted write
+        let bogus = sidecar_path(&path, &format!("tmp-{}", std::process::id()));
+        std::fs::write(&bogus, "{\"name\": \"Tor").unwrap();
+
+        let data = cache.get().unwrap();
+        assert_eq!(cache.read().unwrap(), data);
+        // Only the cache & the untouched leftover remain
+        let mut files: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        files.sort();
+        assert_eq!(files, vec![path.clone(), bogus]);
+    }
+
+    #[test]
+    fn test_adaptive_ttl() {
+        #[derive(Debug, Serialize, Deserialize)]
+        struct Slow;
+
+        impl Fetch for Slow {
+            type Cached = Slow;
+
+            fn fetch() -> Result<Slow, Box<dyn std::error::Error>> {
+                std::thread::sleep(Duration::from_millis(100));
+                Ok(Slow)
+            }
+        }
+
+        // Cache for 100x as long as the fetch took
+        let ttl = |took: Duration| took * 100;
+        let file = NamedTempFile::new().unwrap();
+        let fast: Tote<TestData> = Tote::new(file.path(), Duration::ZERO).adaptive_ttl(ttl);
+        assert_eq!(fast.last_fetch_duration(), None);
+        fast.get().unwrap();
+        let slow_file = NamedTempFile::new().unwrap();
+        let slow: Tote<Slow> = Tote::new(slow_file.path(), Duration::ZERO).adaptive_ttl(ttl);
+        slow.get().unwrap();
+
+        assert!(slow.last_fetch_duration().unwrap() >= Duration::from_millis(100));
+        assert!(fast.last_fetch_duration().unwrap() < Duration::from_millis(100));
+        // Written a second ago, only the slow fetch's data is still fresh
+        let a_second_ago = SystemTime::now() - Duration::from_secs(1);
+        touch(file.path(), a_second_ago).unwrap();
+        touch(slow_file.path(), a_second_ago).unwrap();
+        assert!(!fast.is_valid());
+        assert!(slow.is_valid());
+    }
+
+    #[test]
+    fn test_put_creates_parent_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("myapp").join("nested").join("cache.json");
+        let cache: Tote<TestData> = Tote::new(&path, Duration::from_secs(60));
+        let data = cache.get().unwrap();
+        assert_eq!(cache.read().unwrap(), data);
+
+        // Parent exists but isn't a directory
+        let file = dir.path().join("file");
+        std::fs::write(&file, "").unwrap();
+        let cache: Tote<TestData> = Tote::new(file.join("cache.json"), Duration::from_secs(60));
+        match cache.put(&data) {
+            Err(ToteError::FileAccess(e)) => {
+                assert_eq!(e.kind(), std::io::ErrorKind::NotADirectory)
+            }
+            res => panic!("Unexpected result: {:?}", res),
+        }
 
-#[cfg(feature = "async")]
-/// A trait provided to allow `Tote` to fetch the data
-/// when no cache exists or cache is expired
-#[async_trait]
-pub trait AsyncFetch {
-    /// The data type to be fetched & cached
-    type Cached;
+        // No parent to create
+        assert!(create_parent_dir(Path::new("cache.json")).is_ok());
+    }
 
-    #[cfg(feature = "async")]
-    /// Strategy for fetching data to cache
-    async fn fetch_async() -> std::result::Result<Self::Cached, Box<dyn std::error::Error>>;
-}
+    #[test]
+    fn test_with_tempfile_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache");
+        let tmp = temp_path(&path, Some(tmp_dir.path()), None);
+        assert_eq!(tmp.parent().unwrap(), tmp_dir.path());
+        assert!(tmp
+            .file_name()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .starts_with("cache.tmp-"));
 
-/// Errors that can occur during `Tote` operations
-#[derive(Error, Debug)]
-pub enum ToteError {
-    /// Error reading/writing from given cache file path
-    #[error(transparent)]
-    FileAccess(#[from] std::io::Error),
-    /// Error with Serde (de)serialization
-    #[error(transparent)]
-    Serde(#[from] serde_json::Error),
-    /// Cached data is missing or cannot be read
-    #[error("Cached data is not valid")]
-    InvalidCache,
-    /// Error while fetching data
-    #[error(transparent)]
-    Fetching(#[from] Box<dyn std::error::Error>),
-}
+        let cache: Tote<TestData> =
+            Tote::new(&path, Duration::from_secs(60)).with_tempfile_dir(tmp_dir.path());
+        let data = cache.get().unwrap();
+        assert_eq!(cache.read().unwrap(), data);
+        // Temp file was renamed away
+        assert_eq!(std::fs::read_dir(tmp_dir.path()).unwrap().count(), 0);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
 
-/// Local file cache for data needed across binary instantiations
-///
-/// Given a path & maximum cache age, provides methods
-/// for fetching (unexpired) and writing device info
-#[derive(Debug)]
-pub struct Tote<T> {
-    /// Filepath to write cached data
-    path: PathBuf,
-    /// Cached data older than this age is considered expired
-    max_age: Duration,
-    _phantom: PhantomData<T>,
-}
+    #[test]
+    fn test_with_tempfile_affixes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache.json");
+        // Next to the cache file by default
+        let tmp = temp_path(&path, None, None);
+        assert_eq!(tmp.parent().unwrap(), dir.path());
 
-impl<T> Tote<T> {
-    /// Create a new cache for a given filepath & expiry age
-    pub fn new<P: AsRef<Path>>(path: P, max_age: Duration) -> Self {
-        Self {
-            path: path.as_ref().to_owned(),
-            max_age,
-            _phantom: PhantomData,
-        }
+        let tmp = temp_path(&path, None, Some((".", ".partial")));
+        assert_eq!(tmp.parent().unwrap(), dir.path());
+        let name = tmp.file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with(".cache.json-"));
+        assert!(name.ends_with(".partial"));
+        assert_ne!(temp_path(&path, None, Some((".", ".partial"))), tmp);
+
+        let cache: Tote<TestData> =
+            Tote::new(&path, Duration::from_secs(60)).with_tempfile_affixes(".", ".partial");
+        let data = cache.get().unwrap();
+        assert_eq!(cache.read().unwrap(), data);
+        // Temp file was renamed over the cache file
+        let files: Vec<_> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(files, vec![path]);
     }
 
-    /// Fetch the cached data, returning Err for I/O issues
-    /// or if the cache file is expired
-    pub fn get<'a>(&self) -> Result<T, ToteError>
-    where
-        for<'de> T: Deserialize<'de> + 'a,
-        T: Serialize + Fetch<Cached = T>,
-    {
-        if let Ok(data) = self.read() {
-            return Ok(data);
-        }
-        // Fall-back to fetching data and updating cache file
-        let data = T::fetch()?;
-        self.put(&data)?;
-        Ok(data)
+    #[test]
+    fn test_newest_of() {
+        let dir = tempfile::tempdir().unwrap();
+        let now = SystemTime::now();
+        let stage = |name: &str, value: u8, age: u64| {
+            let path = dir.path().join(name);
+            let cache: Tote<TestData> = Tote::new(&path, Duration::from_secs(60));
+            cache
+                .put(&TestData {
+                    name: name.to_owned(),
+                    value,
+                })
+                .unwrap();
+            fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(now - Duration::from_secs(age))
+                .unwrap();
+            path
+        };
+        let primary = stage("primary", 1, 30);
+        let newest = stage("newest", 2, 10);
+        let older = stage("older", 3, 20);
+        let expired = stage("expired", 4, 120);
+
+        let cache: Tote<TestData> =
+            Tote::new(&primary, Duration::from_secs(60)).newest_of(vec![older, newest, expired]);
+        let res = cache.get().unwrap();
+        assert_eq!(res.name, "newest".to_owned());
+        assert_eq!(res.value, 2);
     }
 
-    #[cfg(feature = "async")]
-    /// Fetch the cached data, returning Err for I/O issues
-    /// or if the cache file is expired
-    pub async fn get_async<'a>(&self) -> Result<T, ToteError>
-    where
-        for<'de> T: Deserialize<'de> + 'a,
-        T: Serialize + AsyncFetch<Cached = T>,
-    {
-        if let Ok(data) = self.read() {
-            return Ok(data);
+    #[derive(Debug, PartialEq)]
+    struct CustomError(u8);
+
+    impl std::fmt::Display for CustomError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "custom error {}", self.0)
         }
-        // Fall-back to fetching data and updating cache file
-        let data = T::fetch_async().await?;
-        self.put(&data)?;
-        Ok(data)
     }
 
-    fn read<'a>(&self) -> Result<T, ToteError>
-    where
-        for<'de> T: Deserialize<'de> + 'a,
-    {
-        if self.is_valid() {
-            // If the cache file is valid (exists & not expired)
-            // attempt to deserialize.
-            // If either fails, fall through and re-fetch the data below
-            let contents = fs::read_to_string(&self.path)?;
-            let data = serde_json::from_str::<T>(&contents)?;
-            return Ok(data);
+    #[test]
+    fn test_then() {
+        let dir = tempfile::tempdir().unwrap();
+        let a: Tote<TestData> = Tote::new(dir.path().join("a"), Duration::from_secs(60));
+        let b: Tote<String> = Tote::new(dir.path().join("b"), Duration::from_secs(60));
+        let derive =
+            |data: &TestData| Ok::<_, CustomError>(format!("{}-{}", data.name, data.value));
+
+        assert_eq!(a.then(&b, derive).unwrap(), "Test-50");
+        let now = SystemTime::now();
+        touch(&dir.path().join("a"), now - Duration::from_secs(10)).unwrap();
+        touch(&dir.path().join("b"), now - Duration::from_secs(5)).unwrap();
+
+        // Fresh `b` derived from current `a` isn't re-fetched
+        let res = a.then(&b, |_| Err(CustomError(1)));
+        assert_eq!(res.unwrap(), "Test-50");
+
+        // Updating `a` forces `b` to be re-fetched from the new value
+        a.put(&TestData {
+            name: "New".to_owned(),
+            value: 2,
+        })
+        .unwrap();
+        assert_eq!(a.then(&b, derive).unwrap(), "New-2");
+        assert_eq!(b.read().unwrap(), "New-2");
+
+        // Expired `b` is rate-limited & locked like `get`
+        let b = Tote::new(dir.path().join("b"), Duration::ZERO)
+            .with_min_refresh_interval(Duration::from_secs(60))
+            .lock_fetches(true);
+        let res = a.then(&b, |_| Err(CustomError(1)));
+        assert_eq!(res.unwrap(), "New-2");
+        touch(&dir.path().join("b"), now - Duration::from_secs(61)).unwrap();
+        match a.then(&b, |_| Err(CustomError(2))) {
+            Err(GetError::Fetch(err)) => assert_eq!(err, CustomError(2)),
+            other => panic!("Unexpected result: {:?}", other),
         }
-        Err(ToteError::InvalidCache)
+        assert_eq!(a.then(&b, derive).unwrap(), "New-2");
     }
 
-    /// Write new or updated device cache data
-    fn put(&self, value: &T) -> Result<(), ToteError>
-    where
-        T: Serialize,
-    {
-        let data = serde_json::to_string(value)?;
-        let file = fs::OpenOptions::new()
-            .create(true)
-            .write(true)
-            .open(&self.path)?;
-        let mut writer = io::BufWriter::new(file);
-        writer.write_all(data.as_bytes())?;
-        Ok(())
-    }
+    #[test]
+    fn test_get_or_fetch() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<Vec<u32>> = Tote::new(file.path(), Duration::from_secs(60));
 
-    /// Is the cached data valid (exists & not expired)
-    fn is_valid(&self) -> bool {
-        fs::metadata(&self.path)
-            .map_err(|_| ())
-            .and_then(|metadata| metadata.modified().map_err(|_| ()))
-            .and_then(|modified| modified.elapsed().map_err(|_| ()))
-            .map(|age| age <= self.max_age)
-            .unwrap_or(false)
+        let res = cache.get_or_fetch(|| Err("Connection refused".into()));
+        assert!(matches!(res, Err(ToteError::Fetching(_))));
+        assert_eq!(cache.get_or_fetch(|| Ok(vec![1, 2])).unwrap(), vec![1, 2]);
+        // Cached data is read without calling the closure
+        assert_eq!(cache.get_or_fetch(|| unreachable!()).unwrap(), vec![1, 2]);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use serde_derive::{Deserialize, Serialize};
-    use tempfile::NamedTempFile;
+    #[test]
+    fn test_get_or_try_insert_with() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> = Tote::new(file.path(), Duration::from_secs(60));
 
-    #[cfg(feature = "async")]
-    use async_trait::async_trait;
+        match cache.get_or_try_insert_with(|| Err(CustomError(7))) {
+            Err(GetError::Fetch(err)) => assert_eq!(err, CustomError(7)),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+
+        let res = cache
+            .get_or_try_insert_with(|| {
+                Ok::<_, CustomError>(TestData {
+                    name: "Closure".to_owned(),
+                    value: 3,
+                })
+            })
+            .unwrap();
+        assert_eq!(res.name, "Closure".to_owned());
+
+        // Served from cache, closure is not called
+        let res = cache
+            .get_or_try_insert_with(|| Err(CustomError(8)))
+            .unwrap();
+        assert_eq!(res.value, 3);
+    }
 
     #[derive(Debug, Serialize, Deserialize)]
-    struct TestData {
+    struct OldTestData {
         name: String,
-        value: u8,
     }
 
-    impl Fetch for TestData {
+    #[test]
+    fn test_migrate_from() {
+        let dir = tempfile::tempdir().unwrap();
+        let old_path = dir.path().join("v1.cache");
+        let new_path = dir.path().join("v2.cache");
+
+        let old_cache: Tote<OldTestData> = Tote::new(&old_path, Duration::from_secs(60));
+        old_cache
+            .put(&OldTestData {
+                name: "Migrated".to_owned(),
+            })
+            .unwrap();
+
+        let cache: Tote<TestData> = Tote::new(&new_path, Duration::from_secs(60));
+        let migrated = cache
+            .migrate_from(&old_path, |old: OldTestData| TestData {
+                name: old.name,
+                value: 1,
+            })
+            .unwrap();
+        assert!(migrated);
+        assert!(!old_path.exists());
+        assert!(cache.is_valid());
+
+        let res = cache.read().unwrap();
+        assert_eq!(res.name, "Migrated".to_owned());
+        assert_eq!(res.value, 1);
+
+        // Nothing left to migrate
+        let migrated = cache
+            .migrate_from(&old_path, |old: OldTestData| TestData {
+                name: old.name,
+                value: 2,
+            })
+            .unwrap();
+        assert!(!migrated);
+    }
+
+    #[cfg(feature = "async")]
+    #[async_trait]
+    impl AsyncFetch for TestData {
         type Cached = TestData;
 
-        fn fetch() -> Result<TestData, Box<dyn std::error::Error>> {
+        async fn fetch_async() -> Result<TestData, Box<dyn std::error::Error>> {
             Ok(TestData {
                 name: "Test".to_owned(),
                 value: 50,
@@ -176,8 +7920,9 @@ mod tests {
         }
     }
 
-    #[test]
-    fn test_round_trip() {
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_round_trip_async() {
         let file = NamedTempFile::new().unwrap();
         let cache: Tote<TestData> = Tote::new(file.path(), Duration::from_millis(300));
 
@@ -190,7 +7935,7 @@ mod tests {
             .unwrap();
 
         assert!(cache.is_valid());
-        let res = cache.get().unwrap();
+        let res = cache.get_async().await.unwrap();
         assert!(cache.is_valid());
         assert_eq!(res.name, "Test".to_owned());
         assert_eq!(res.value, 50);
@@ -200,40 +7945,274 @@ mod tests {
     }
 
     #[cfg(feature = "async")]
-    #[async_trait]
-    impl AsyncFetch for TestData {
-        type Cached = TestData;
+    #[tokio::test]
+    async fn test_large_payload_async() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("cache");
+        let cache: Tote<Vec<String>> = Tote::new(&path, Duration::from_secs(60));
+        let data: Vec<String> = (0..200_000).map(|i| format!("item-{}", i)).collect();
 
-        async fn fetch_async() -> Result<TestData, Box<dyn std::error::Error>> {
-            Ok(TestData {
-                name: "Test".to_owned(),
-                value: 50,
-            })
-        }
+        cache.put_async(&data).await.unwrap();
+        assert!(std::fs::metadata(&path).unwrap().len() > 2_000_000);
+        assert_eq!(cache.read_async().await.unwrap(), data);
+        assert_eq!(cache.read().unwrap(), data);
     }
 
     #[cfg(feature = "async")]
     #[tokio::test]
-    async fn test_round_trip_async() {
+    async fn test_get_no_write_async() {
         let file = NamedTempFile::new().unwrap();
-        let cache: Tote<TestData> = Tote::new(file.path(), Duration::from_millis(300));
+        let cache: Tote<TestData> = Tote::new(file.path(), Duration::from_secs(60));
 
-        // Stage cached data
+        assert_eq!(cache.get_no_write_async().await.unwrap().name, "Test");
+        assert!(std::fs::read(file.path()).unwrap().is_empty());
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_force_refresh_async() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> = Tote::new(file.path(), Duration::from_secs(60));
         cache
             .put(&TestData {
-                name: "Test".to_owned(),
-                value: 50,
+                name: "Old".to_owned(),
+                value: 1,
             })
             .unwrap();
 
-        assert!(cache.is_valid());
+        assert_eq!(cache.force_refresh_async().await.unwrap().name, "Test");
+        assert_eq!(cache.read().unwrap().name, "Test");
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_async_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache");
+        let cache: Tote<TestData> = Tote::new(&path, Duration::from_secs(60))
+            .with_open_options(|_| std::thread::sleep(Duration::from_millis(200)))
+            .async_writes(true);
+
+        let data = cache.get_async().await.unwrap();
+        assert!(!path.exists());
+        cache.flush_writes().await.unwrap();
+        assert_eq!(cache.read().unwrap(), data);
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_async_writes_failing() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache: Tote<TestData> = Tote::new(dir.path().join("missing/cache"), Duration::ZERO)
+            .create_dirs(false)
+            .async_writes(true);
+
+        // Finished writes aren't kept around until a flush
+        for _ in 0..10 {
+            cache.get_async().await.unwrap();
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert!(cache.pending_writes().handles.len() <= 1);
+        assert!(cache.pending_writes().error.is_some());
+
+        // Errors are returned once
+        assert!(matches!(
+            cache.flush_writes().await,
+            Err(ToteError::FileAccess(_))
+        ));
+        cache.flush_writes().await.unwrap();
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_get_or_try_insert_with_async() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> = Tote::new(file.path(), Duration::from_secs(60));
+
+        match cache
+            .get_or_try_insert_with_async(|| async { Err(CustomError(7)) })
+            .await
+        {
+            Err(GetError::Fetch(err)) => assert_eq!(err, CustomError(7)),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+
+        let res = cache
+            .get_or_try_insert_with_async(|| async {
+                Ok::<_, CustomError>(TestData {
+                    name: "Closure".to_owned(),
+                    value: 3,
+                })
+            })
+            .await
+            .unwrap();
+        assert_eq!(res.name, "Closure".to_owned());
+    }
+
+    #[cfg(feature = "async")]
+    #[derive(Debug, Serialize, Deserialize)]
+    struct SlowData(u8);
+
+    #[cfg(feature = "async")]
+    #[async_trait]
+    impl AsyncFetch for SlowData {
+        type Cached = SlowData;
+
+        async fn fetch_async() -> Result<SlowData, Box<dyn std::error::Error>> {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            Ok(SlowData(1))
+        }
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_startup_prefetch() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<SlowData> =
+            Tote::new(file.path(), Duration::from_secs(60)).with_startup_prefetch();
+
+        // Other startup work overlaps with the fetch
+        tokio::time::sleep(Duration::from_millis(250)).await;
+
+        let start = std::time::Instant::now();
         let res = cache.get_async().await.unwrap();
+        assert_eq!(res.0, 1);
+        assert!(start.elapsed() < Duration::from_millis(100));
         assert!(cache.is_valid());
-        assert_eq!(res.name, "Test".to_owned());
-        assert_eq!(res.value, 50);
+    }
 
-        std::thread::sleep(Duration::from_millis(305));
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_get_or_fetch_async() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<Vec<u32>> = Tote::new(file.path(), Duration::from_secs(60));
+
+        let res = cache.get_or_fetch_async(|| async { Ok(vec![1, 2]) }).await;
+        assert_eq!(res.unwrap(), vec![1, 2]);
+        let res = cache.get_or_fetch_async(|| async { unreachable!() }).await;
+        assert_eq!(res.unwrap(), vec![1, 2]);
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_fetch_timeout() {
+        let file = NamedTempFile::new().unwrap();
+        let timeout = Duration::from_millis(20);
+        let cache: Tote<SlowData> =
+            Tote::new(file.path(), Duration::from_secs(60)).with_fetch_timeout(timeout, false);
+        let res = cache.get_async().await;
+        assert!(matches!(res, Err(ToteError::Timeout(t)) if t == timeout));
+        assert!(!cache.is_valid());
+
+        // Expired data is returned instead, if enabled
+        cache.put(&SlowData(0)).unwrap();
+        touch(file.path(), SystemTime::now() - Duration::from_secs(120)).unwrap();
+        assert!(cache.get_async().await.is_err());
+        let cache = cache.with_fetch_timeout(timeout, true);
+        assert_eq!(cache.get_async().await.unwrap().0, 0);
+
+        // No timeout by default
+        let cache: Tote<SlowData> = Tote::new(file.path(), Duration::from_secs(60));
+        assert_eq!(cache.get_async().await.unwrap().0, 1);
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_get_stale_while_revalidate() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<SlowData> = Tote::new(file.path(), Duration::from_secs(60));
+        cache.put(&SlowData(0)).unwrap();
+        touch(file.path(), SystemTime::now() - Duration::from_secs(120)).unwrap();
+
+        // Stale data is returned without waiting on the fetch
+        let start = std::time::Instant::now();
+        let res = cache.get_stale_while_revalidate().await.unwrap();
+        assert_eq!(res.0, 0);
+        assert!(start.elapsed() < Duration::from_millis(100));
         assert!(!cache.is_valid());
+
+        // Refreshed in the background
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(cache.read().unwrap().0, 1);
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_get_stale_while_revalidate_once() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static FETCHES: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(Debug, Serialize, Deserialize)]
+        struct Counted(usize);
+
+        #[async_trait]
+        impl AsyncFetch for Counted {
+            type Cached = Counted;
+
+            async fn fetch_async() -> Result<Counted, Box<dyn std::error::Error>> {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                Ok(Counted(FETCHES.fetch_add(1, Ordering::SeqCst) + 1))
+            }
+        }
+
+        let file = NamedTempFile::new().unwrap();
+        let fetch_starts = Arc::new(AtomicUsize::new(0));
+        let counter = fetch_starts.clone();
+        let cache: Tote<Counted> =
+            Tote::new(file.path(), Duration::from_secs(60)).on_fetch_start(move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+            });
+        cache.put(&Counted(0)).unwrap();
+        touch(file.path(), SystemTime::now() - Duration::from_secs(120)).unwrap();
+
+        for _ in 0..10 {
+            assert_eq!(cache.get_stale_while_revalidate().await.unwrap().0, 0);
+        }
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(FETCHES.load(Ordering::SeqCst), 1);
+        assert_eq!(fetch_starts.load(Ordering::SeqCst), 1);
+        assert_eq!(cache.read().unwrap().0, 1);
+    }
+
+    #[cfg(feature = "async-native")]
+    impl AsyncFetchNative for TestData {
+        type Cached = TestData;
+
+        async fn fetch_async() -> Result<TestData, Box<dyn std::error::Error>> {
+            Ok(TestData {
+                name: "Test".to_owned(),
+                value: 50,
+            })
+        }
+    }
+
+    #[cfg(feature = "async-native")]
+    #[tokio::test]
+    async fn test_round_trip_async_native() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: Tote<TestData> = Tote::new(file.path(), Duration::from_secs(60));
+
+        let fetched = cache.get_async_native().await.unwrap();
+        assert!(cache.is_valid());
+        let cached = cache.get_async_native().await.unwrap();
+        assert_eq!(fetched, cached);
+        assert_eq!(cached, cache.get().unwrap());
+        #[cfg(feature = "async")]
+        assert_eq!(cached, cache.get_async().await.unwrap());
+    }
+
+    #[cfg(feature = "async-native")]
+    #[tokio::test]
+    async fn test_async_native_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache.json");
+        let cache: Tote<TestData> = Tote::new(&path, Duration::from_secs(60)).async_writes(true);
+
+        let fetched = cache.get_async_native().await.unwrap();
+        cache.flush_writes().await.unwrap();
+        assert_eq!(cache.get_async_native().await.unwrap(), fetched);
+        assert!(path.exists());
     }
 
     #[cfg(feature = "async")]
@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::hash::Hash;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{Clock, ExpiryPolicy, Tote, ToteError};
+
+type Entries<K, V> = HashMap<K, (SystemTime, V)>;
+
+/// The map file itself never expires, only its entries
+struct NeverExpires;
+
+impl ExpiryPolicy for NeverExpires {
+    fn is_fresh(&self, _created: SystemTime, _now: SystemTime, _metadata: &fs::Metadata) -> bool {
+        true
+    }
+}
+
+/// Local file cache for a map of entries which each expire individually
+///
+/// All entries are stored in a single file (as `HashMap<K, (SystemTime, V)>`),
+/// which is read & atomically written as a whole. Expired entries are
+/// ignored on read and dropped on the next write
+pub struct MapTote<K, V> {
+    inner: Tote<Entries<K, V>>,
+    max_age: Duration,
+}
+
+impl<K, V> fmt::Debug for MapTote<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MapTote")
+            .field("path", &self.inner.path)
+            .field("max_age", &self.max_age)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<K, V> MapTote<K, V>
+where
+    K: Serialize + DeserializeOwned + Eq + Hash,
+    V: Serialize + DeserializeOwned,
+{
+    /// Create a new cache for a given filepath & per-entry expiry age
+    pub fn new<P: AsRef<Path>>(path: P, max_age: Duration) -> Self {
+        Self {
+            inner: Tote::new(path, max_age).with_expiry_policy(NeverExpires),
+            max_age,
+        }
+    }
+
+    /// Use the given `Clock` as the current time when writing & expiring
+    /// entries, rather than the system clock
+    pub fn with_clock<C>(mut self, clock: C) -> Self
+    where
+        C: Clock + Send + Sync + 'static,
+    {
+        self.inner = self.inner.with_clock(clock);
+        self
+    }
+
+    /// Get the value cached for `key`, returning None if the entry
+    /// is missing or expired
+    pub fn get(&self, key: &K) -> Result<Option<V>, ToteError> {
+        let now = self.inner.now();
+        Ok(self
+            .entries()?
+            .remove(key)
+            .filter(|(written, _)| self.is_fresh(*written, now))
+            .map(|(_, value)| value))
+    }
+
+    /// Cache `value` for `key`, replacing any existing entry
+    pub fn insert(&self, key: K, value: V) -> Result<(), ToteError> {
+        self.modify(|entries| {
+            entries.insert(key, (self.inner.now(), value));
+        })
+    }
+
+    /// Remove the entry for `key`, returning its value if unexpired
+    pub fn remove(&self, key: &K) -> Result<Option<V>, ToteError> {
+        let now = self.inner.now();
+        let mut removed = None;
+        self.modify(|entries| removed = entries.remove(key))?;
+        Ok(removed
+            .filter(|(written, _)| self.is_fresh(*written, now))
+            .map(|(_, value)| value))
+    }
+
+    /// Read-modify-write all entries while holding the cache lock,
+    /// dropping expired entries
+    ///
+    /// A map file which fails to parse (e.g. corrupt or truncated) is
+    /// replaced, rather than failing every write
+    fn modify<F>(&self, f: F) -> Result<(), ToteError>
+    where
+        F: FnOnce(&mut Entries<K, V>),
+    {
+        let _lock = self.inner.lock()?;
+        let mut entries = match self.entries() {
+            Err(ToteError::Parse(_) | ToteError::Serde(_) | ToteError::Codec(_)) => {
+                Entries::default()
+            }
+            res => res?,
+        };
+        f(&mut entries);
+        let now = self.inner.now();
+        entries.retain(|_, (written, _)| self.is_fresh(*written, now));
+        self.inner.put(&entries)
+    }
+
+    fn entries(&self) -> Result<Entries<K, V>, ToteError> {
+        Ok(self.inner.peek()?.unwrap_or_default())
+    }
+
+    fn is_fresh(&self, written: SystemTime, now: SystemTime) -> bool {
+        now.duration_since(written)
+            .map(|age| age <= self.max_age)
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_map_tote() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: MapTote<String, u32> = MapTote::new(file.path(), Duration::from_secs(60));
+        assert_eq!(cache.get(&"a".to_owned()).unwrap(), None);
+
+        // Stage a file with mixed fresh/expired entries
+        let now = SystemTime::now();
+        let entries: Entries<String, u32> = HashMap::from([
+            ("fresh".to_owned(), (now - Duration::from_secs(10), 1)),
+            ("expired".to_owned(), (now - Duration::from_secs(120), 2)),
+        ]);
+        cache.inner.put(&entries).unwrap();
+        assert_eq!(cache.get(&"fresh".to_owned()).unwrap(), Some(1));
+        assert_eq!(cache.get(&"expired".to_owned()).unwrap(), None);
+
+        // Writes keep fresh entries and drop expired ones
+        cache.insert("new".to_owned(), 3).unwrap();
+        assert_eq!(cache.get(&"new".to_owned()).unwrap(), Some(3));
+        let entries = cache.entries().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(!entries.contains_key("expired"));
+
+        assert_eq!(cache.remove(&"fresh".to_owned()).unwrap(), Some(1));
+        assert_eq!(cache.get(&"fresh".to_owned()).unwrap(), None);
+    }
+
+    #[test]
+    fn test_map_tote_corrupt() {
+        let file = NamedTempFile::new().unwrap();
+        let cache: MapTote<String, u32> = MapTote::new(file.path(), Duration::from_secs(60));
+        fs::write(file.path(), "{\"a\": [1, ").unwrap();
+        assert!(cache.get(&"a".to_owned()).is_err());
+
+        // Writes replace the corrupt file
+        cache.insert("b".to_owned(), 2).unwrap();
+        assert_eq!(cache.get(&"b".to_owned()).unwrap(), Some(2));
+        assert_eq!(cache.get(&"a".to_owned()).unwrap(), None);
+    }
+
+    #[test]
+    fn test_map_tote_clock() {
+        use std::sync::{Arc, Mutex};
+
+        let file = NamedTempFile::new().unwrap();
+        let now = Arc::new(Mutex::new(SystemTime::now()));
+        let clock = now.clone();
+        let cache: MapTote<String, u32> = MapTote::new(file.path(), Duration::from_secs(60))
+            .with_clock(move || *clock.lock().unwrap());
+        cache.insert("a".to_owned(), 1).unwrap();
+        assert_eq!(cache.get(&"a".to_owned()).unwrap(), Some(1));
+
+        *now.lock().unwrap() += Duration::from_secs(61);
+        assert_eq!(cache.get(&"a".to_owned()).unwrap(), None);
+        assert_eq!(cache.remove(&"a".to_owned()).unwrap(), None);
+    }
+}
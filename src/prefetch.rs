@@ -0,0 +1,120 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::Poll;
+
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::{AsyncFetch, Codec, Tote, ToteError};
+
+/// A cache which can be warmed (read, or fetched & written if missing or
+/// expired) without naming its data type, see `prefetch_all`
+///
+/// Like `Tote::get_async`, the returned future isn't `Send`
+#[async_trait(?Send)]
+pub trait Prefetch {
+    /// Make sure the cache holds valid data, like `Tote::get_async`
+    /// without returning it
+    async fn warm(&self) -> Result<(), ToteError>;
+}
+
+#[async_trait(?Send)]
+impl<T, C> Prefetch for Tote<T, C>
+where
+    T: DeserializeOwned,
+    T: Serialize + AsyncFetch<Cached = T>,
+    C: Codec,
+{
+    async fn warm(&self) -> Result<(), ToteError> {
+        self.get_async().await.map(|_| ())
+    }
+}
+
+/// Warm each of `caches` concurrently, returning their results in order
+///
+/// ```ignore
+/// let results = tote::prefetch_all(&[&devices, &interfaces]).await;
+/// ```
+pub async fn prefetch_all(caches: &[&dyn Prefetch]) -> Vec<Result<(), ToteError>> {
+    type Warming<'a> = Pin<Box<dyn Future<Output = Result<(), ToteError>> + 'a>>;
+
+    let mut warming: Vec<Option<Warming>> = caches.iter().map(|cache| Some(cache.warm())).collect();
+    let mut results: Vec<Option<Result<(), ToteError>>> = caches.iter().map(|_| None).collect();
+    std::future::poll_fn(|cx| {
+        for (fut, result) in warming.iter_mut().zip(results.iter_mut()) {
+            if let Some(pending) = fut {
+                if let Poll::Ready(res) = pending.as_mut().poll(cx) {
+                    *result = Some(res);
+                    *fut = None;
+                }
+            }
+        }
+        if warming.iter().all(Option::is_none) {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    })
+    .await;
+    results.into_iter().flatten().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Devices(Vec<String>);
+
+    #[async_trait]
+    impl AsyncFetch for Devices {
+        type Cached = Devices;
+
+        async fn fetch_async() -> Result<Devices, Box<dyn std::error::Error>> {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            Ok(Devices(vec!["router-1".to_owned()]))
+        }
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Interfaces(u32);
+
+    #[async_trait]
+    impl AsyncFetch for Interfaces {
+        type Cached = Interfaces;
+
+        async fn fetch_async() -> Result<Interfaces, Box<dyn std::error::Error>> {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            Ok(Interfaces(48))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_prefetch_all() {
+        let dir = tempfile::tempdir().unwrap();
+        let devices: Tote<Devices> =
+            Tote::new(dir.path().join("devices.json"), Duration::from_secs(60));
+        let interfaces: Tote<Interfaces> =
+            Tote::new(dir.path().join("interfaces.json"), Duration::from_secs(60));
+
+        // Fetched concurrently
+        let start = Instant::now();
+        let results = prefetch_all(&[&devices, &interfaces]).await;
+        assert!(start.elapsed() < Duration::from_millis(190));
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(Result::is_ok));
+        assert!(devices.is_valid());
+        assert_eq!(interfaces.read().unwrap(), Interfaces(48));
+
+        // Results are per-cache & in order
+        let invalid: Tote<Interfaces> = Tote::new(dir.path(), Duration::from_secs(60));
+        let results = prefetch_all(&[&devices, &invalid]).await;
+        assert!(results[0].is_ok());
+        assert!(matches!(results[1], Err(ToteError::InvalidPath { .. })));
+    }
+}
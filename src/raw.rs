@@ -0,0 +1,139 @@
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use crate::{touch, Tote, ToteError};
+
+type Encoder<T> = Box<dyn Fn(&T) -> Result<Vec<u8>, Box<dyn Error>>>;
+type Decoder<T> = Box<dyn Fn(&[u8]) -> Result<T, Box<dyn Error>>>;
+
+/// Local file cache for data without `serde` support
+///
+/// `RawTote` caches data using caller-provided encode/decode closures
+/// operating on bytes, while sharing the filepath, expiry & write behavior
+/// of a configured `Tote`
+pub struct RawTote<T> {
+    inner: Tote<T>,
+    encode: Encoder<T>,
+    decode: Decoder<T>,
+}
+
+impl<T> fmt::Debug for RawTote<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RawTote")
+            .field("path", &self.inner.path)
+            .field("max_age", &self.inner.max_age)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> RawTote<T> {
+    /// Create a new cache for a given filepath & expiry age,
+    /// using `encode`/`decode` to convert data to & from bytes
+    pub fn new<P, E, D>(path: P, max_age: Duration, encode: E, decode: D) -> Self
+    where
+        P: AsRef<Path>,
+        E: Fn(&T) -> Result<Vec<u8>, Box<dyn Error>> + 'static,
+        D: Fn(&[u8]) -> Result<T, Box<dyn Error>> + 'static,
+    {
+        Self::from_tote(Tote::new(path, max_age), encode, decode)
+    }
+
+    /// Create a new cache using the configuration of an existing `Tote`,
+    /// using `encode`/`decode` to convert data to & from bytes
+    pub fn from_tote<E, D>(tote: Tote<T>, encode: E, decode: D) -> Self
+    where
+        E: Fn(&T) -> Result<Vec<u8>, Box<dyn Error>> + 'static,
+        D: Fn(&[u8]) -> Result<T, Box<dyn Error>> + 'static,
+    {
+        Self {
+            inner: tote,
+            encode: Box::new(encode),
+            decode: Box::new(decode),
+        }
+    }
+
+    /// Fetch the cached data, falling back to the given closure
+    /// when the cache file is missing or expired
+    pub fn get<F>(&self, fetch: F) -> Result<T, ToteError>
+    where
+        F: FnOnce() -> Result<T, Box<dyn Error>>,
+    {
+        if let Ok(data) = self.read() {
+            return Ok(data);
+        }
+        // Fall-back to fetching data and updating cache file
+        let data = fetch().map_err(|e| self.inner.fetch_error(e))?;
+        self.put(&data)?;
+        Ok(data)
+    }
+
+    fn read(&self) -> Result<T, ToteError> {
+        let path = self.inner.read_path().ok_or(ToteError::InvalidCache)?;
+        let contents = self.inner.read_bytes_from(path)?;
+        let data = (self.decode)(&contents).map_err(ToteError::Codec)?;
+        if self.inner.sliding_expiration {
+            // Best-effort, failing to extend the expiry doesn't invalidate the data
+            let _ = touch(path, SystemTime::now());
+        }
+        Ok(data)
+    }
+
+    /// Write new or updated cache data
+    fn put(&self, value: &T) -> Result<(), ToteError> {
+        let data = (self.encode)(value).map_err(ToteError::Codec)?;
+        Ok(self.inner.write_bytes(&data)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::NamedTempFile;
+
+    /// Deliberately has no `serde` impls
+    #[derive(Debug, PartialEq)]
+    struct Point {
+        x: u8,
+        y: u8,
+    }
+
+    fn cache(path: &Path) -> RawTote<Point> {
+        RawTote::new(
+            path,
+            Duration::from_secs(60),
+            |point: &Point| Ok(vec![point.x, point.y]),
+            |bytes: &[u8]| match bytes {
+                [x, y] => Ok(Point { x: *x, y: *y }),
+                _ => Err("Expected 2 bytes".into()),
+            },
+        )
+    }
+
+    #[test]
+    fn test_raw_round_trip() {
+        let file = NamedTempFile::new().unwrap();
+        let cache = cache(file.path());
+
+        let res = cache.get(|| Ok(Point { x: 1, y: 2 })).unwrap();
+        assert_eq!(res, Point { x: 1, y: 2 });
+        assert_eq!(std::fs::read(file.path()).unwrap(), vec![1, 2]);
+
+        // Served from cache
+        let res = cache.get(|| Err("Should not fetch".into())).unwrap();
+        assert_eq!(res, Point { x: 1, y: 2 });
+    }
+
+    #[test]
+    fn test_raw_decode_error() {
+        let file = NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), [1, 2, 3]).unwrap();
+        let cache = cache(file.path());
+
+        assert!(matches!(cache.read(), Err(ToteError::Codec(_))));
+        // Undecodable data is re-fetched
+        let res = cache.get(|| Ok(Point { x: 3, y: 4 })).unwrap();
+        assert_eq!(res, Point { x: 3, y: 4 });
+    }
+}
@@ -0,0 +1,100 @@
+use std::io::{self, Read};
+
+/// Reader adapter which rewrites a top-level JSON array (`[a, b, c]`)
+/// into a whitespace separated stream of its elements (`a  b  c`)
+///
+/// This lets `serde_json::StreamDeserializer` yield array elements one at a
+/// time without buffering the whole array in memory
+pub(crate) struct ArrayElements<R> {
+    inner: R,
+    /// Nesting depth, the top-level array is depth 1
+    depth: usize,
+    /// Whether the top-level array has been closed
+    closed: bool,
+    in_string: bool,
+    escaped: bool,
+}
+
+impl<R: Read> ArrayElements<R> {
+    pub(crate) fn new(inner: R) -> Self {
+        Self {
+            inner,
+            depth: 0,
+            closed: false,
+            in_string: false,
+            escaped: false,
+        }
+    }
+}
+
+impl<R: Read> Read for ArrayElements<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        for byte in &mut buf[..n] {
+            if self.in_string {
+                if self.escaped {
+                    self.escaped = false;
+                } else if *byte == b'\\' {
+                    self.escaped = true;
+                } else if *byte == b'"' {
+                    self.in_string = false;
+                }
+                continue;
+            }
+            match (self.depth, *byte) {
+                (_, b' ' | b'\t' | b'\n' | b'\r') => {}
+                (0, b'[') if !self.closed => {
+                    self.depth = 1;
+                    *byte = b' ';
+                }
+                (0, _) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "Cached data is not a JSON array",
+                    ))
+                }
+                (1, b',') => *byte = b' ',
+                (1, b'}') => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "Mismatched brackets in cached data",
+                    ))
+                }
+                (1, b']') => {
+                    self.depth = 0;
+                    self.closed = true;
+                    *byte = b' ';
+                }
+                (_, b'[' | b'{') => self.depth += 1,
+                (_, b']' | b'}') => self.depth -= 1,
+                (_, b'"') => self.in_string = true,
+                _ => {}
+            }
+        }
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rewrite(input: &str) -> io::Result<String> {
+        let mut output = String::new();
+        ArrayElements::new(input.as_bytes()).read_to_string(&mut output)?;
+        Ok(output)
+    }
+
+    #[test]
+    fn test_array_elements() {
+        assert_eq!(rewrite("[1,2,3]").unwrap(), " 1 2 3 ");
+        assert_eq!(
+            rewrite(r#"[{"a":[1,2]},"x,]\"y"]"#).unwrap(),
+            r#" {"a":[1,2]} "x,]\"y" "#
+        );
+        assert_eq!(rewrite(" [] \n").unwrap(), "    \n");
+        assert!(rewrite(r#"{"a":1}"#).is_err());
+        assert!(rewrite("[1] [2]").is_err());
+        assert!(rewrite("[1}").is_err());
+    }
+}
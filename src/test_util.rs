@@ -0,0 +1,232 @@
+//! Helpers for testing code which uses `Tote` (test-only, not for production use)
+//!
+//! `CountingFetcher` and `FailingFetcher` wrap a `Fetch` type to observe or
+//! inject failures into its fetches. Their counts are tracked per wrapper
+//! type for the whole process, so use a distinct wrapped type per test
+//! when tests run in parallel
+//!
+//! `Tote::for_test` creates a cache stored in a `MemoryBackend`, expiring
+//! by a `ManualClock` which tests advance rather than sleeping
+use std::any::TypeId;
+use std::collections::BTreeMap;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Clock, Fetch, MemoryBackend, Tote};
+
+/// Fetch counts, keyed by wrapper type
+static COUNTS: Mutex<BTreeMap<TypeId, usize>> = Mutex::new(BTreeMap::new());
+
+/// Increment the count for `K`, returning the new count
+fn increment<K: 'static>() -> usize {
+    let mut counts = COUNTS.lock().unwrap_or_else(|e| e.into_inner());
+    let count = counts.entry(TypeId::of::<K>()).or_default();
+    *count += 1;
+    *count
+}
+
+fn count<K: 'static>() -> usize {
+    let counts = COUNTS.lock().unwrap_or_else(|e| e.into_inner());
+    counts.get(&TypeId::of::<K>()).copied().unwrap_or_default()
+}
+
+fn reset<K: 'static>() {
+    let mut counts = COUNTS.lock().unwrap_or_else(|e| e.into_inner());
+    counts.remove(&TypeId::of::<K>());
+}
+
+/// `Fetch` wrapper which counts calls to `T::fetch`
+///
+/// Cache as `Tote<CountingFetcher<T>>`, serialized the same as `T`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CountingFetcher<T>(pub T);
+
+impl<T: 'static> CountingFetcher<T> {
+    /// Number of fetches since the last `reset`
+    pub fn count() -> usize {
+        count::<Self>()
+    }
+
+    /// Reset the fetch count to zero
+    pub fn reset() {
+        reset::<Self>()
+    }
+}
+
+impl<T> Deref for CountingFetcher<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> Fetch for CountingFetcher<T>
+where
+    T: Fetch<Cached = T> + 'static,
+{
+    type Cached = Self;
+
+    fn fetch() -> Result<Self, Box<dyn std::error::Error>> {
+        increment::<Self>();
+        T::fetch().map(Self)
+    }
+}
+
+/// `Fetch` wrapper which fails the first `N` fetches, then fetches `T`
+///
+/// Cache as `Tote<FailingFetcher<T, N>>`, serialized the same as `T`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct FailingFetcher<T, const N: usize>(pub T);
+
+impl<T: 'static, const N: usize> FailingFetcher<T, N> {
+    /// Number of fetch attempts (failed or not) since the last `reset`
+    pub fn attempts() -> usize {
+        count::<Self>()
+    }
+
+    /// Reset the attempt count, so the next `N` fetches fail again
+    pub fn reset() {
+        reset::<Self>()
+    }
+}
+
+impl<T, const N: usize> Deref for FailingFetcher<T, N> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T, const N: usize> Fetch for FailingFetcher<T, N>
+where
+    T: Fetch<Cached = T> + 'static,
+{
+    type Cached = Self;
+
+    fn fetch() -> Result<Self, Box<dyn std::error::Error>> {
+        let attempt = increment::<Self>();
+        if attempt <= N {
+            return Err(format!("FailingFetcher failure {} of {}", attempt, N).into());
+        }
+        T::fetch().map(Self)
+    }
+}
+
+/// `Clock` which only moves when advanced, starting at the current time
+///
+/// Clones share the same time
+#[derive(Debug, Clone)]
+pub struct ManualClock(Arc<Mutex<SystemTime>>);
+
+impl ManualClock {
+    /// Create a clock at the current system time
+    pub fn new() -> Self {
+        Self(Arc::new(Mutex::new(SystemTime::now())))
+    }
+
+    /// Move the clock forward by `duration`
+    pub fn advance(&self, duration: Duration) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) += duration;
+    }
+
+    /// Set the clock to `time`
+    pub fn set(&self, time: SystemTime) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = time;
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> SystemTime {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<T> Tote<T> {
+    /// Create a cache stored in a new `MemoryBackend`, using `clock` as the
+    /// current time for both writes & expiry
+    ///
+    /// Nothing is written to the filesystem
+    pub fn for_test(max_age: Duration, clock: &ManualClock) -> Self {
+        Self::new("tote-test.json", max_age)
+            .with_backend(MemoryBackend::new().with_clock(clock.clone()))
+            .with_clock(clock.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Data(u8);
+
+    impl Fetch for Data {
+        type Cached = Data;
+
+        fn fetch() -> Result<Data, Box<dyn std::error::Error>> {
+            Ok(Data(1))
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Other(u8);
+
+    impl Fetch for Other {
+        type Cached = Other;
+
+        fn fetch() -> Result<Other, Box<dyn std::error::Error>> {
+            Ok(Other(2))
+        }
+    }
+
+    #[test]
+    fn test_counting_fetcher() {
+        let clock = ManualClock::new();
+        let cache: Tote<CountingFetcher<Data>> = Tote::for_test(Duration::from_secs(60), &clock);
+        assert_eq!(CountingFetcher::<Data>::count(), 0);
+        assert_eq!(cache.get().unwrap().0, Data(1));
+        assert_eq!(cache.get().unwrap().0, Data(1));
+        assert_eq!(CountingFetcher::<Data>::count(), 1);
+
+        cache.invalidate().unwrap();
+        cache.get().unwrap();
+        assert_eq!(CountingFetcher::<Data>::count(), 2);
+
+        // Re-fetched once the clock passes `max_age`
+        clock.advance(Duration::from_secs(30));
+        cache.get().unwrap();
+        assert_eq!(CountingFetcher::<Data>::count(), 2);
+        clock.advance(Duration::from_secs(31));
+        cache.get().unwrap();
+        assert_eq!(CountingFetcher::<Data>::count(), 3);
+        CountingFetcher::<Data>::reset();
+        assert_eq!(CountingFetcher::<Data>::count(), 0);
+    }
+
+    #[test]
+    fn test_failing_fetcher() {
+        let clock = ManualClock::new();
+        let cache: Tote<FailingFetcher<Other, 2>> = Tote::for_test(Duration::from_secs(60), &clock);
+        assert!(cache.get().is_err());
+        assert!(cache.get().is_err());
+        assert_eq!(cache.get().unwrap().0, Other(2));
+        assert_eq!(FailingFetcher::<Other, 2>::attempts(), 3);
+
+        // Cached data is read without fetching
+        assert_eq!(*cache.get().unwrap(), Other(2));
+        assert_eq!(FailingFetcher::<Other, 2>::attempts(), 3);
+    }
+}
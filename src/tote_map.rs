@@ -0,0 +1,347 @@
+use std::collections::HashSet;
+use std::fmt::{self, Display};
+use std::fs;
+use std::io;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::{crc32, fnv1a, Tote, ToteError};
+
+/// Longest file name (without the `.json` extension) from the default key mapper
+const MAX_NAME_LEN: usize = 200;
+
+/// Windows device names, which can't be used as file names
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+type KeyMapper<K> = Box<dyn Fn(&K) -> String + Send + Sync>;
+
+/// Local file cache for values keyed by `K`, stored as one file per key
+///
+/// Each entry is a `Tote` at `<dir>/<name>.json`, so entries expire & are
+/// written independently. By default the name is the key with characters
+/// other than ASCII alphanumerics, `-` & `_` percent-encoded, see
+/// `with_key_mapper`. See `MapTote` to keep all entries in a single file instead
+pub struct ToteMap<K, T> {
+    dir: PathBuf,
+    max_age: Duration,
+    /// Levels of hash-prefixed subdirectories entries are sharded into
+    shard_depth: usize,
+    /// Mapping of keys to file names, overriding `default_file_name`
+    key_mapper: Option<KeyMapper<K>>,
+    _phantom: PhantomData<fn(&K) -> T>,
+}
+
+impl<K, T> fmt::Debug for ToteMap<K, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ToteMap")
+            .field("dir", &self.dir)
+            .field("max_age", &self.max_age)
+            .field("shard_depth", &self.shard_depth)
+            .field("key_mapper", &self.key_mapper.is_some())
+            .finish()
+    }
+}
+
+impl<K, T> ToteMap<K, T>
+where
+    K: Display,
+    T: DeserializeOwned,
+    T: Serialize,
+{
+    /// Create a new cache storing entries in `dir`, each expiring after `max_age`
+    ///
+    /// `dir` is created on the first `put`
+    pub fn new<P: AsRef<Path>>(dir: P, max_age: Duration) -> Self {
+        Self {
+            dir: dir.as_ref().to_owned(),
+            max_age,
+            shard_depth: 0,
+            key_mapper: None,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Map keys to entry file names (without the `.json` extension) with
+    /// `mapper`, e.g. to case-fold keys on case-insensitive filesystems
+    ///
+    /// The default percent-encodes characters other than ASCII alphanumerics,
+    /// `-` & `_`, escapes Windows reserved names (e.g. `CON` & `NUL`), and
+    /// shortens names over 200 characters to a prefix & hash of the key.
+    /// `mapper` must return distinct, filename-safe names for distinct keys.
+    /// Entries written with another mapping aren't found
+    pub fn with_key_mapper<F>(mut self, mapper: F) -> Self
+    where
+        F: Fn(&K) -> String + Send + Sync + 'static,
+    {
+        self.key_mapper = Some(Box::new(mapper));
+        self
+    }
+
+    /// Store entries in `depth` levels of subdirectories, each named by
+    /// 2 hex characters of a hash of the key (e.g. `<dir>/3f/a0/<key>.json`),
+    /// so no single directory holds thousands of files
+    ///
+    /// `depth` is limited to 4. Entries written with another depth aren't found
+    pub fn with_shard_depth(mut self, depth: usize) -> Self {
+        self.shard_depth = depth.min(4);
+        self
+    }
+
+    /// Get the value cached for `key`, returning None if the entry
+    /// is missing or expired
+    pub fn get(&self, key: &K) -> Result<Option<T>, ToteError> {
+        match self.entry(key).read() {
+            Ok(data) => Ok(Some(data)),
+            Err(ToteError::InvalidCache) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Cache `value` for `key`, replacing any existing entry
+    pub fn put(&self, key: &K, value: &T) -> Result<(), ToteError> {
+        self.entry(key).put(value)
+    }
+
+    /// Cache each value for its key, replacing any existing entries
+    ///
+    /// Each directory is created once for the whole batch. A failed write
+    /// doesn't stop the batch: every entry is attempted, and any which
+    /// failed are returned with their errors (entries written before or
+    /// after a failure are kept)
+    pub fn set_many<I>(&self, entries: I) -> Result<(), Vec<(K, ToteError)>>
+    where
+        I: IntoIterator<Item = (K, T)>,
+    {
+        let mut created = HashSet::new();
+        let mut failed = Vec::new();
+        for (key, value) in entries {
+            let path = self.path_for(&key);
+            if let Some(parent) = path.parent() {
+                if !created.contains(parent) {
+                    if let Err(e) = fs::create_dir_all(parent) {
+                        failed.push((key, e.into()));
+                        continue;
+                    }
+                    created.insert(parent.to_owned());
+                }
+            }
+            let entry: Tote<T> = Tote::new(path, self.max_age).create_dirs(false);
+            if let Err(e) = entry.put(&value) {
+                failed.push((key, e));
+            }
+        }
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            Err(failed)
+        }
+    }
+
+    /// Is the entry for `key` valid (exists & not expired)
+    pub fn is_valid(&self, key: &K) -> bool {
+        self.entry(key).is_valid()
+    }
+
+    /// Delete the entry for `key`
+    ///
+    /// Returns Ok if the entry is already missing
+    pub fn invalidate(&self, key: &K) -> Result<(), ToteError> {
+        self.entry(key).invalidate()
+    }
+
+    /// Delete the whole cache directory, including any other files in it
+    ///
+    /// Returns Ok if the directory is already missing
+    pub fn clear(&self) -> Result<(), ToteError> {
+        match fs::remove_dir_all(&self.dir) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Filepath of the entry for `key`
+    pub fn path_for(&self, key: &K) -> PathBuf {
+        let name = match &self.key_mapper {
+            Some(mapper) => mapper(key),
+            None => default_file_name(&key.to_string()),
+        };
+        let hash = format!("{:08x}", crc32(name.as_bytes()));
+        let mut path = self.dir.clone();
+        for level in 0..self.shard_depth {
+            path.push(&hash[level * 2..level * 2 + 2]);
+        }
+        path.join(format!("{}.json", name))
+    }
+
+    fn entry(&self, key: &K) -> Tote<T> {
+        Tote::new(self.path_for(key), self.max_age)
+    }
+}
+
+/// Filename-safe name for `key`, see `ToteMap::with_key_mapper`
+fn default_file_name(key: &str) -> String {
+    let mut name = String::with_capacity(key.len());
+    for byte in key.bytes() {
+        if byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_' {
+            name.push(byte as char);
+        } else {
+            name.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    if RESERVED_NAMES.contains(&name.to_ascii_uppercase().as_str()) {
+        // Encode the first (alphabetic) character, e.g. `%43ON`
+        name = format!("%{:02X}{}", name.as_bytes()[0], &name[1..]);
+    }
+    if name.len() > MAX_NAME_LEN {
+        // `~` is always percent-encoded, so can't collide with a short name
+        let hash = format!("~{:016x}", fnv1a(key.as_bytes()));
+        name.truncate(MAX_NAME_LEN - hash.len());
+        name.push_str(&hash);
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tote_map() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache: ToteMap<String, u32> =
+            ToteMap::new(dir.path().join("hosts"), Duration::from_secs(60));
+        let (a, b) = ("router-1".to_owned(), "switch/2".to_owned());
+        assert_eq!(cache.get(&a).unwrap(), None);
+
+        cache.put(&a, &1).unwrap();
+        cache.put(&b, &2).unwrap();
+        assert_eq!(cache.get(&a).unwrap(), Some(1));
+        assert_eq!(cache.get(&b).unwrap(), Some(2));
+        assert_eq!(
+            cache.path_for(&b).parent().unwrap(),
+            dir.path().join("hosts")
+        );
+
+        // Entries expire individually
+        let old = std::time::SystemTime::now() - Duration::from_secs(120);
+        fs::File::options()
+            .write(true)
+            .open(cache.path_for(&a))
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+        assert!(!cache.is_valid(&a));
+        assert_eq!(cache.get(&a).unwrap(), None);
+        assert_eq!(cache.get(&b).unwrap(), Some(2));
+
+        cache.invalidate(&b).unwrap();
+        assert_eq!(cache.get(&b).unwrap(), None);
+        cache.invalidate(&b).unwrap();
+
+        cache.clear().unwrap();
+        assert!(!dir.path().join("hosts").exists());
+        cache.clear().unwrap();
+    }
+
+    #[test]
+    fn test_default_file_name() {
+        assert_eq!(default_file_name("router-1"), "router-1");
+        assert_eq!(default_file_name("switch/2"), "switch%2F2");
+        assert_eq!(default_file_name("a.b%"), "a%2Eb%25");
+        assert_eq!(default_file_name("é"), "%C3%A9");
+
+        // Windows reserved names
+        assert_eq!(default_file_name("CON"), "%43ON");
+        assert_eq!(default_file_name("nul"), "%6Eul");
+        assert_eq!(default_file_name("COM1"), "%43OM1");
+        assert_eq!(default_file_name("CONSOLE"), "CONSOLE");
+        assert_ne!(default_file_name("CON"), default_file_name("%43ON"));
+
+        // Long keys are shortened, without colliding
+        let long = "k".repeat(500);
+        let longer = format!("{}!", long);
+        assert_eq!(default_file_name(&long).len(), MAX_NAME_LEN);
+        assert_eq!(default_file_name(&longer).len(), MAX_NAME_LEN);
+        assert_ne!(default_file_name(&long), default_file_name(&longer));
+        assert_eq!(default_file_name(&"k".repeat(200)), "k".repeat(200));
+
+        let dir = tempfile::tempdir().unwrap();
+        let cache: ToteMap<String, u32> = ToteMap::new(dir.path(), Duration::from_secs(60));
+        for (value, key) in ["CON", "NUL", &long, &longer].into_iter().enumerate() {
+            cache.put(&key.to_owned(), &(value as u32)).unwrap();
+        }
+        assert_eq!(cache.get(&"NUL".to_owned()).unwrap(), Some(1));
+        assert_eq!(cache.get(&longer).unwrap(), Some(3));
+    }
+
+    #[test]
+    fn test_with_key_mapper() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache: ToteMap<String, u32> = ToteMap::new(dir.path(), Duration::from_secs(60))
+            .with_key_mapper(|key: &String| key.to_lowercase());
+        cache.put(&"Router".to_owned(), &1).unwrap();
+        assert_eq!(
+            cache.path_for(&"Router".to_owned()),
+            dir.path().join("router.json")
+        );
+        assert_eq!(cache.get(&"ROUTER".to_owned()).unwrap(), Some(1));
+    }
+
+    #[test]
+    fn test_set_many() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache: ToteMap<u32, String> =
+            ToteMap::new(dir.path().join("batch"), Duration::from_secs(60)).with_shard_depth(1);
+        cache
+            .set_many((0..100).map(|key| (key, format!("value-{}", key))))
+            .unwrap();
+        for key in 0..100 {
+            assert_eq!(cache.get(&key).unwrap(), Some(format!("value-{}", key)));
+        }
+
+        // Failed entries are returned, the rest are still written
+        let blocked = cache.path_for(&7);
+        fs::remove_file(&blocked).unwrap();
+        fs::create_dir(&blocked).unwrap();
+        let failed = cache
+            .set_many(vec![(7, "new".to_owned()), (8, "new".to_owned())])
+            .unwrap_err();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].0, 7);
+        assert_eq!(cache.get(&8).unwrap(), Some("new".to_owned()));
+    }
+
+    #[test]
+    fn test_tote_map_sharded() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache: ToteMap<u32, u32> =
+            ToteMap::new(dir.path(), Duration::from_secs(60)).with_shard_depth(2);
+        for key in 0..500 {
+            cache.put(&key, &(key * 2)).unwrap();
+        }
+        assert_eq!(cache.get(&42).unwrap(), Some(84));
+
+        let path = cache.path_for(&42);
+        let shard = path.parent().unwrap();
+        assert_eq!(shard.parent().unwrap().parent().unwrap(), dir.path());
+        assert_eq!(shard.file_name().unwrap().len(), 2);
+
+        // Keys spread across the top-level shards
+        let shards: Vec<_> = fs::read_dir(dir.path()).unwrap().collect();
+        assert!(shards.len() > 100);
+        assert!(shards
+            .iter()
+            .all(|shard| shard.as_ref().unwrap().path().is_dir()));
+
+        // Unsharded paths aren't found
+        let flat: ToteMap<u32, u32> = ToteMap::new(dir.path(), Duration::from_secs(60));
+        assert_eq!(flat.get(&42).unwrap(), None);
+    }
+}
@@ -0,0 +1,10 @@
+// `test-util`'s `Fetch` wrappers are listed as suggestions in the compiler
+// output, which would differ from the expected `.stderr` files
+#[cfg(not(feature = "test-util"))]
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+    #[cfg(feature = "async")]
+    t.compile_fail("tests/ui/async/*.rs");
+}
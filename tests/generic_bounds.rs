@@ -0,0 +1,46 @@
+// Generic code over `Tote` only needs `T: DeserializeOwned`, this fails to
+// compile if a method requires a stricter (e.g. higher-ranked) bound
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tote::{Fetch, Tote, ToteError};
+
+fn load<T>(cache: &Tote<T>) -> Result<T, ToteError>
+where
+    T: Serialize + DeserializeOwned + Fetch<Cached = T>,
+{
+    cache.get()
+}
+
+fn load_fresh<T>(cache: &Tote<T>) -> Result<(T, bool), ToteError>
+where
+    T: Serialize + DeserializeOwned + Fetch<Cached = T>,
+{
+    cache.get_fresh()
+}
+
+fn peek<T: DeserializeOwned>(cache: &Tote<T>) -> Result<Option<T>, ToteError> {
+    cache.peek()
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Data(u8);
+
+impl Fetch for Data {
+    type Cached = Data;
+
+    fn fetch() -> Result<Data, Box<dyn std::error::Error>> {
+        Ok(Data(1))
+    }
+}
+
+#[test]
+fn test_owned_bound() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache: Tote<Data> = Tote::new(dir.path().join("data.json"), Duration::from_secs(60));
+    assert_eq!(peek(&cache).unwrap(), None);
+    assert_eq!(load(&cache).unwrap(), Data(1));
+    assert_eq!(load_fresh(&cache).unwrap(), (Data(1), false));
+    assert_eq!(peek(&cache).unwrap(), Some(Data(1)));
+}
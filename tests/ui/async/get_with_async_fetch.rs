@@ -0,0 +1,21 @@
+use std::time::Duration;
+
+use serde_derive::{Deserialize, Serialize};
+use tote::{async_trait, AsyncFetch, Tote};
+
+#[derive(Debug, Deserialize, Serialize)]
+struct AsyncOnly(u8);
+
+#[async_trait]
+impl AsyncFetch for AsyncOnly {
+    type Cached = AsyncOnly;
+
+    async fn fetch_async() -> Result<AsyncOnly, Box<dyn std::error::Error>> {
+        Ok(AsyncOnly(1))
+    }
+}
+
+fn main() {
+    let cache: Tote<AsyncOnly> = Tote::new(".ui.cache", Duration::from_secs(60));
+    let _ = cache.get();
+}
@@ -0,0 +1,12 @@
+use std::time::Duration;
+
+use serde_derive::{Deserialize, Serialize};
+use tote::Tote;
+
+#[derive(Debug, Deserialize, Serialize)]
+struct NotFetchable(u8);
+
+fn main() {
+    let cache: Tote<NotFetchable> = Tote::new(".ui.cache", Duration::from_secs(60));
+    let _ = cache.get();
+}